@@ -29,19 +29,41 @@ const columns = [
     sortable: true,
   },
   {
-    field: 'first_plane_name',
-    headerName: 'first_plane_name',
+    field: 'level_name',
+    headerName: 'level_name',
     type: 'string',
     width: 250,
     sortable: true,
     valueGetter: (params) => [
-      params.row.first_plane_name,
+      params.row.level_name,
       params.row.first_plane_type,
       params.row.first_plane_value,
       params.row.first_plane_factor],
     renderCell: ({ value }) => (<Tooltip title={`${value[0]}, type:${value[1]}, value:${value[2]}, factor:${value[3]}`} ><span>{value[0] ? value[0] : '(unknown)'}</span></Tooltip>),
     sortComparator: (v1, v2) => v1[0].localeCompare(v2[0]),
   },
+  {
+    field: 'statistics_name',
+    headerName: 'statistics_name',
+    type: 'string',
+    width: 180,
+    sortable: true,
+  },
+  {
+    field: 'ensemble_name',
+    headerName: 'ensemble_name',
+    type: 'string',
+    width: 180,
+    sortable: true,
+    valueGetter: (params) => params.row.ensemble_name ?? params.row.derived_forecast_name,
+  },
+  {
+    field: 'radar_operation_name',
+    headerName: 'radar_operation_name',
+    type: 'string',
+    width: 180,
+    sortable: true,
+  },
   {
     field: 'parameter_description',
     headerName: 'parameter_description',
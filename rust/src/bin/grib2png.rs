@@ -0,0 +1,54 @@
+//! GRIB2 の1メッセージを凡例付きの PNG に描画するコマンド
+//!
+//! usage: grib2png <input.grib2> <index> <output.png> [colormap]
+
+use std::env;
+use std::fs;
+use std::process;
+
+use rust::Grib2Wrapper;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 4 {
+        eprintln!(
+            "usage: {} <input.grib2> <index> <output.png> [colormap]",
+            args[0]
+        );
+        process::exit(2);
+    }
+
+    let src = fs::read(&args[1]).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", args[1], e);
+        process::exit(1);
+    });
+    let index: usize = args[2].parse().unwrap_or_else(|_| {
+        eprintln!("Invalid index: {}", args[2]);
+        process::exit(2);
+    });
+
+    let mut grib2 = Grib2Wrapper::new();
+    grib2.load(&src);
+    if grib2.item_count() <= index {
+        eprintln!(
+            "Index out of range: {} (count {})",
+            index,
+            grib2.item_count()
+        );
+        process::exit(1);
+    }
+
+    let png = grib2.export_png(index, args.get(4).cloned(), "stepped", true);
+    match png {
+        Some(png) => {
+            if let Err(e) = fs::write(&args[3], png) {
+                eprintln!("Failed to write {}: {}", args[3], e);
+                process::exit(1);
+            }
+        }
+        None => {
+            eprintln!("Failed to render message {}", index);
+            process::exit(1);
+        }
+    }
+}
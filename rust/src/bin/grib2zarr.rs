@@ -0,0 +1,47 @@
+//! 同じ格子系の GRIB2 ファイル群を Zarr v2 のディレクトリストアに書き出すコマンド
+//!
+//! usage: grib2zarr <output.zarr> <input.grib2>...
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use rust::Grib2Wrapper;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        eprintln!("usage: {} <output.zarr> <input.grib2>...", args[0]);
+        process::exit(2);
+    }
+
+    let mut grib2 = Grib2Wrapper::new();
+    for path in args[2..].iter() {
+        let src = fs::read(path).unwrap_or_else(|e| {
+            eprintln!("Failed to read {}: {}", path, e);
+            process::exit(1);
+        });
+        grib2.load(&src);
+    }
+
+    let indices = (0..grib2.item_count()).collect();
+    let store = grib2.export_zarr(indices).unwrap_or_else(|| {
+        eprintln!(
+            "Failed to export: all messages must share the same grid \
+             and each element must not repeat a time, member and level"
+        );
+        process::exit(1);
+    });
+
+    let root = Path::new(&args[1]);
+    for index in 0..store.count() {
+        let path = root.join(store.path(index).unwrap());
+        let result = fs::create_dir_all(path.parent().unwrap())
+            .and_then(|_| fs::write(&path, store.file(index).unwrap()));
+        if let Err(e) = result {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            process::exit(1);
+        }
+    }
+}
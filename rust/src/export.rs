@@ -0,0 +1,45 @@
+//! ファイル出力 declaration
+
+pub mod cf_impl;
+pub mod cog_impl;
+pub mod geotiff_impl;
+pub mod lzw_impl;
+pub mod netcdf_impl;
+pub mod png_impl;
+pub mod points_impl;
+pub mod tiff_impl;
+pub mod zarr_impl;
+pub mod zlib_impl;
+
+use chrono::{DateTime, Utc};
+
+use crate::grib2::section::Level;
+
+// タイルの圧縮方法
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Compression {
+    Deflate,
+    Lzw,
+}
+
+// 格子点の書き出し形式
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PointFormat {
+    Csv,    // 見出し行付きの CSV
+    Ndjson, // 1行に1つの JSON オブジェクト
+}
+
+// NetCDF の変数にする1メッセージ分の値と属性
+pub(crate) struct Field {
+    pub(crate) key: String,  // 同じ変数とみなすためのキー（時刻とレベル以外）
+    pub(crate) name: String, // 要素の名称 e.g. Temperature
+    pub(crate) description: String, // 統計処理などを含む説明
+    pub(crate) unit: String, // パラメーター表の単位
+    pub(crate) parameter: (usize, usize, usize), // discipline, category, number
+    pub(crate) probability: bool, // 確率予報なら true（値は %）
+    pub(crate) level: Option<Level>,
+    pub(crate) member: Option<usize>, // アンサンブルの摂動番号
+    pub(crate) valid_time: DateTime<Utc>,
+    pub(crate) reference_time: DateTime<Utc>,
+    pub(crate) values: Vec<f32>,
+}
@@ -0,0 +1,654 @@
+//! CF conventions implementation
+//! メッセージの値を CF 規約に沿ったデータセット（NetCDF の次元と変数）にする。
+//! 同じ要素の値は時刻、アンサンブルのメンバー、レベルの次元を持つ1つの変数にまとめる。
+
+use crate::grib2::section::section3_template::Crs;
+use crate::grib2::section::section3_template::TemplateNumber as Section3TemplateNumber;
+use crate::grib2::section::FixedSurface;
+use crate::grib2::section::Level;
+
+use super::netcdf_impl::Dimension;
+use super::netcdf_impl::NetCdf;
+use super::netcdf_impl::Values;
+use super::netcdf_impl::Variable;
+use super::Field;
+
+pub(crate) const FILL_VALUE: f32 = 9.969_21e36; // NetCDF の float の既定の欠損値
+const TIME_UNITS: &str = "seconds since 1970-01-01 00:00:00";
+const GRID_MAPPING: &str = "crs";
+
+// CF の standard_name（要素と固定面の種類から）
+fn standard_name(
+    parameter: (usize, usize, usize),
+    surface_type: Option<usize>,
+) -> Option<&'static str> {
+    let surface = surface_type == Some(1);
+    Some(match parameter {
+        (0, 0, 0) if surface => "surface_temperature",
+        (0, 0, 0) => "air_temperature",
+        (0, 0, 6) => "dew_point_temperature",
+        (0, 1, 0) => "specific_humidity",
+        (0, 1, 1) => "relative_humidity",
+        (0, 1, 3) => "atmosphere_mass_content_of_water_vapor",
+        (0, 1, 8) => "precipitation_amount",
+        (0, 1, 13) => "surface_snow_amount",
+        (0, 1, 52) => "precipitation_flux",
+        (0, 2, 0) => "wind_from_direction",
+        (0, 2, 1) => "wind_speed",
+        (0, 2, 2) => "eastward_wind",
+        (0, 2, 3) => "northward_wind",
+        (0, 2, 8) => "lagrangian_tendency_of_air_pressure",
+        (0, 2, 9) => "upward_air_velocity",
+        (0, 3, 0) if surface => "surface_air_pressure",
+        (0, 3, 0) => "air_pressure",
+        (0, 3, 1) => "air_pressure_at_mean_sea_level",
+        (0, 3, 4) => "geopotential",
+        (0, 3, 5) => "geopotential_height",
+        (0, 4, 7) => "surface_downwelling_shortwave_flux_in_air",
+        (0, 6, 1) => "cloud_area_fraction",
+        (0, 7, 6) => "atmosphere_convective_available_potential_energy",
+        (0, 19, 0) => "visibility_in_air",
+        (10, 0, 3) => "sea_surface_wave_significant_height",
+        (10, 3, 0) => "sea_surface_temperature",
+        _ => return None,
+    })
+}
+
+// 鉛直座標の次元名、standard_name、positive
+fn vertical_axis(surface_type: usize) -> (String, Option<&'static str>, &'static str) {
+    let (name, standard_name, positive) = match surface_type {
+        100 => ("isobaric", Some("air_pressure"), "down"),
+        102 => ("altitude", Some("altitude"), "up"),
+        103 => ("height", Some("height"), "up"),
+        104 => ("sigma", None, "down"),
+        105 => ("hybrid", None, "down"),
+        106 => ("depth", Some("depth"), "down"),
+        107 => ("isentropic", Some("air_potential_temperature"), "up"),
+        160 => ("depth_below_sea", Some("depth"), "down"),
+        _ => return (format!("level_{}", surface_type), None, "up"),
+    };
+    (String::from(name), standard_name, positive)
+}
+
+// パラメーター表の単位を UDUNITS の表記にする。 e.g. kg・m-2 -> kg m-2
+fn cf_units(unit: &str) -> String {
+    match unit {
+        "kgm-2" => String::from("kg m-2"),
+        "kgm-3" => String::from("kg m-3"),
+        "m/s" => String::from("m s-1"),
+        "mm/日" => String::from("mm day-1"),
+        "degree true" | "°" => String::from("degree"),
+        "gpm" => String::from("m"),
+        _ => unit.replace('・', " "),
+    }
+}
+
+// 英数字以外を _ にして変数名にする。 e.g. Total Precipitation -> total_precipitation
+fn variable_name(name: &str) -> String {
+    let mut dest = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            dest.push(c.to_ascii_lowercase());
+        } else if !dest.is_empty() && !dest.ends_with('_') {
+            dest.push('_');
+        }
+    }
+    let dest = dest.trim_end_matches('_');
+    match dest.chars().next() {
+        None => String::from("value"),
+        Some(c) if c.is_ascii_digit() => format!("v_{}", dest),
+        Some(_) => String::from(dest),
+    }
+}
+
+fn text(value: &str) -> Values {
+    Values::Char(String::from(value))
+}
+
+fn attribute(name: &str, value: Values) -> (String, Values) {
+    (String::from(name), value)
+}
+
+// レベルの座標にする固定面（層なら上端の第1固定面）
+fn surface(level: &Option<Level>) -> Option<&FixedSurface> {
+    match level.as_ref()? {
+        Level::Surface(x) | Level::Layer(x, _) => Some(x),
+    }
+}
+
+// 層の第二固定面
+fn second_surface(level: &Option<Level>) -> Option<&FixedSurface> {
+    match level.as_ref()? {
+        Level::Layer(_, x) => Some(x),
+        Level::Surface(_) => None,
+    }
+}
+
+// 座標の値を持つ固定面の値
+fn surface_value(level: &Option<Level>) -> Option<f64> {
+    let surface = surface(level)?;
+    surface.unit?;
+    surface.value
+}
+
+// 座標参照系の grid_mapping 変数の属性。経緯度なら None を返す。
+fn grid_mapping(crs: &Crs) -> Option<Vec<(String, Values)>> {
+    let (name, radius, false_northing, mut attributes) = match *crs {
+        Crs::Geographic => return None,
+        Crs::Mercator { radius, lon0, lad } => (
+            "mercator",
+            radius,
+            0.0,
+            vec![
+                attribute("longitude_of_projection_origin", Values::Double(vec![lon0])),
+                attribute("standard_parallel", Values::Double(vec![lad])),
+            ],
+        ),
+        Crs::PolarStereographic { radius, lov, lad } => (
+            "polar_stereographic",
+            radius,
+            0.0,
+            vec![
+                attribute(
+                    "straight_vertical_longitude_from_pole",
+                    Values::Double(vec![lov]),
+                ),
+                attribute(
+                    "latitude_of_projection_origin",
+                    Values::Double(vec![90f64.copysign(lad)]),
+                ),
+                attribute("standard_parallel", Values::Double(vec![lad])),
+            ],
+        ),
+        Crs::LambertConformal {
+            radius,
+            lov,
+            latin1,
+            latin2,
+            false_northing,
+        } => (
+            "lambert_conformal_conic",
+            radius,
+            false_northing,
+            vec![
+                attribute("standard_parallel", Values::Double(vec![latin1, latin2])),
+                attribute("longitude_of_central_meridian", Values::Double(vec![lov])),
+                attribute(
+                    "latitude_of_projection_origin",
+                    Values::Double(vec![latin1]),
+                ),
+            ],
+        ),
+    };
+    attributes.insert(0, attribute("grid_mapping_name", text(name)));
+    attributes.push(attribute("false_easting", Values::Double(vec![0.0])));
+    attributes.push(attribute(
+        "false_northing",
+        Values::Double(vec![false_northing]),
+    ));
+    attributes.push(attribute("earth_radius", Values::Double(vec![radius])));
+    Some(attributes)
+}
+
+// 時刻、メンバー、レベルで並べる変数
+struct Group<'a> {
+    surface_type: Option<usize>,
+    second_surface: Option<(usize, Option<f64>)>, // 層なら第二固定面の種類と値
+    members: bool,                                // メンバーの次元を持てば true
+    levels: bool,                                 // レベルの次元を持てば true
+    fields: Vec<&'a Field>,
+}
+
+// 値を並べ替えて重複を除く。
+fn sorted(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(|a, b| a.total_cmp(b));
+    values.dedup();
+    values
+}
+
+// 同じ格子系のメッセージを CF 規約に沿ったデータセットにする。
+pub(crate) fn cf_dataset(
+    geometry: &Section3TemplateNumber,
+    fields: &[Field],
+) -> Result<NetCdf, String> {
+    let (ni, nj) = (geometry.ni(), geometry.nj());
+    if let Some(field) = fields.iter().find(|x| x.values.len() != ni * nj) {
+        return Err(format!(
+            "Invalid value count of {}: {} (expected {})",
+            field.name,
+            field.values.len(),
+            ni * nj
+        ));
+    }
+
+    // 要素と固定面の種類ごとにまとめる。
+    // 層は第二固定面ごとに、レベルの値を持たない固定面はレベルの次元を持たない変数に分ける。
+    let mut groups: Vec<Group> = Vec::new();
+    for field in fields.iter() {
+        let surface_type = surface(&field.level).map(|x| x.surface_type);
+        let second_surface = second_surface(&field.level).map(|x| (x.surface_type, x.value));
+        let levels = surface_value(&field.level).is_some();
+        match groups.iter_mut().find(|x| {
+            x.fields[0].key == field.key
+                && x.surface_type == surface_type
+                && x.second_surface == second_surface
+                && x.levels == levels
+        }) {
+            Some(group) => group.fields.push(field),
+            None => groups.push(Group {
+                surface_type,
+                second_surface,
+                members: false,
+                levels,
+                fields: vec![field],
+            }),
+        }
+    }
+    for group in groups.iter_mut() {
+        group.members = group.fields.iter().any(|x| x.member.is_some());
+    }
+
+    let mut netcdf = NetCdf {
+        attributes: vec![
+            attribute("Conventions", text("CF-1.8")),
+            attribute("source", text("grib2-visualization")),
+        ],
+        ..Default::default()
+    };
+
+    // 時刻
+    let times = sorted(
+        fields
+            .iter()
+            .map(|x| x.valid_time.timestamp() as f64)
+            .collect(),
+    );
+    netcdf.dimensions.push(Dimension {
+        name: String::from("time"),
+        length: times.len(),
+    });
+    netcdf.variables.push(Variable {
+        name: String::from("time"),
+        dimensions: vec![0],
+        attributes: vec![
+            attribute("standard_name", text("time")),
+            attribute("units", text(TIME_UNITS)),
+            attribute("calendar", text("standard")),
+            attribute("axis", text("T")),
+        ],
+        values: Values::Double(times.clone()),
+    });
+    let reference_times = sorted(
+        fields
+            .iter()
+            .map(|x| x.reference_time.timestamp() as f64)
+            .collect(),
+    );
+    if reference_times.len() == 1 {
+        netcdf.variables.push(Variable {
+            name: String::from("forecast_reference_time"),
+            dimensions: Vec::new(),
+            attributes: vec![
+                attribute("standard_name", text("forecast_reference_time")),
+                attribute("units", text(TIME_UNITS)),
+                attribute("calendar", text("standard")),
+            ],
+            values: Values::Double(reference_times),
+        });
+    }
+
+    // アンサンブルのメンバー（摂動番号）
+    let mut members: Vec<usize> = fields.iter().filter_map(|x| x.member).collect();
+    members.sort_unstable();
+    members.dedup();
+    if !members.is_empty() {
+        netcdf.dimensions.push(Dimension {
+            name: String::from("member"),
+            length: members.len(),
+        });
+        netcdf.variables.push(Variable {
+            name: String::from("member"),
+            dimensions: vec![1],
+            attributes: vec![
+                attribute("standard_name", text("realization")),
+                attribute("long_name", text("Ensemble perturbation number")),
+            ],
+            values: Values::Int(members.iter().map(|x| *x as i32).collect()),
+        });
+    }
+
+    // レベル。固定面の種類ごとに次元を作る。
+    let mut level_axes: Vec<(usize, usize, Vec<f64>)> = Vec::new(); // 固定面の種類, 次元, 値
+    for group in groups.iter().filter(|x| x.levels) {
+        let surface = match surface(&group.fields[0].level) {
+            Some(surface) => surface,
+            None => continue,
+        };
+        let surface_type = surface.surface_type;
+        if level_axes.iter().any(|(x, _, _)| *x == surface_type) {
+            continue;
+        }
+        let values = sorted(
+            groups
+                .iter()
+                .filter(|x| x.levels && x.surface_type == group.surface_type)
+                .flat_map(|x| x.fields.iter().filter_map(|x| surface_value(&x.level)))
+                .collect(),
+        );
+        let (name, standard_name, positive) = vertical_axis(surface_type);
+        let mut attributes = vec![attribute("long_name", text(surface.name))];
+        if let Some(standard_name) = standard_name {
+            attributes.push(attribute("standard_name", text(standard_name)));
+        }
+        if let Some(unit) = surface.unit.filter(|x| !x.is_empty()) {
+            attributes.push(attribute("units", text(&cf_units(unit))));
+        }
+        attributes.push(attribute("positive", text(positive)));
+        attributes.push(attribute("axis", text("Z")));
+
+        let dimension = netcdf.dimensions.len();
+        netcdf.dimensions.push(Dimension {
+            name: name.clone(),
+            length: values.len(),
+        });
+        netcdf.variables.push(Variable {
+            name,
+            dimensions: vec![dimension],
+            attributes,
+            values: Values::Double(values.clone()),
+        });
+        level_axes.push((surface_type, dimension, values));
+    }
+
+    // 水平座標。経緯度格子なら1次元の経度と緯度、投影なら x, y と2次元の経度と緯度
+    let transform = geometry.transform();
+    let mapping = grid_mapping(&transform.crs);
+    let (x_name, y_name) = match mapping {
+        Some(_) => ("x", "y"),
+        None => ("lon", "lat"),
+    };
+    let y = netcdf.dimensions.len();
+    netcdf.dimensions.push(Dimension {
+        name: String::from(y_name),
+        length: nj,
+    });
+    let x = y + 1;
+    netcdf.dimensions.push(Dimension {
+        name: String::from(x_name),
+        length: ni,
+    });
+    let xs = (0..ni)
+        .map(|i| transform.x1 + i as f64 * transform.dx)
+        .collect();
+    let ys = (0..nj)
+        .map(|j| transform.y1 + j as f64 * transform.dy)
+        .collect();
+    match &mapping {
+        Some(attributes) => {
+            for (name, dimension, values) in [("y", y, ys), ("x", x, xs)] {
+                netcdf.variables.push(Variable {
+                    name: String::from(name),
+                    dimensions: vec![dimension],
+                    attributes: vec![
+                        attribute(
+                            "standard_name",
+                            text(&format!("projection_{}_coordinate", name)),
+                        ),
+                        attribute("units", text("m")),
+                        attribute("axis", text(&name.to_uppercase())),
+                    ],
+                    values: Values::Double(values),
+                });
+            }
+            let (lons, lats) = geometry.coordinates();
+            for (name, units, values) in [
+                ("latitude", "degrees_north", lats),
+                ("longitude", "degrees_east", lons),
+            ] {
+                netcdf.variables.push(Variable {
+                    name: String::from(&name[..3]),
+                    dimensions: vec![y, x],
+                    attributes: vec![
+                        attribute("standard_name", text(name)),
+                        attribute("units", text(units)),
+                    ],
+                    values: Values::Double(values),
+                });
+            }
+            netcdf.variables.push(Variable {
+                name: String::from(GRID_MAPPING),
+                dimensions: Vec::new(),
+                attributes: attributes.clone(),
+                values: Values::Int(vec![0]),
+            });
+        }
+        None => {
+            for (name, standard_name, units, axis, dimension, values) in [
+                ("lat", "latitude", "degrees_north", "Y", y, ys),
+                ("lon", "longitude", "degrees_east", "X", x, xs),
+            ] {
+                netcdf.variables.push(Variable {
+                    name: String::from(name),
+                    dimensions: vec![dimension],
+                    attributes: vec![
+                        attribute("standard_name", text(standard_name)),
+                        attribute("units", text(units)),
+                        attribute("axis", text(axis)),
+                    ],
+                    values: Values::Double(values),
+                });
+            }
+        }
+    }
+
+    // 要素
+    let mut names: Vec<String> = netcdf.variables.iter().map(|x| x.name.clone()).collect();
+    for group in groups.iter() {
+        let first = group.fields[0];
+        let axis = level_axes
+            .iter()
+            .find(|(x, _, _)| group.levels && Some(*x) == group.surface_type);
+
+        let base = if first.probability {
+            format!("probability_of_{}", variable_name(&first.name))
+        } else {
+            variable_name(&first.name)
+        };
+        let mut name = base.clone();
+        for n in 2.. {
+            if !names.contains(&name) {
+                break;
+            }
+            name = format!("{}_{}", base, n);
+        }
+        names.push(name.clone());
+
+        let mut attributes = vec![attribute("long_name", text(&first.description))];
+        if first.probability {
+            attributes.push(attribute("units", text("%")));
+        } else {
+            if let Some(standard_name) = standard_name(first.parameter, group.surface_type) {
+                attributes.push(attribute("standard_name", text(standard_name)));
+            }
+            if !first.unit.is_empty() {
+                attributes.push(attribute("units", text(&cf_units(&first.unit))));
+            }
+        }
+        attributes.push(attribute("_FillValue", Values::Float(vec![FILL_VALUE])));
+        if axis.is_none() {
+            if let Some(level) = &first.level {
+                attributes.push(attribute("level", text(&level.to_string())));
+            }
+        } else if let Some(second) = second_surface(&first.level) {
+            attributes.push(attribute("second_level", text(&second.to_string())));
+        }
+        if mapping.is_some() {
+            attributes.push(attribute("grid_mapping", text(GRID_MAPPING)));
+            attributes.push(attribute("coordinates", text("lat lon")));
+        }
+
+        let member_count = if group.members { members.len() } else { 1 };
+        let level_count = axis.map_or(1, |(_, _, x)| x.len());
+        let mut values = vec![FILL_VALUE; times.len() * member_count * level_count * ni * nj];
+        let mut filled = vec![false; times.len() * member_count * level_count];
+        for field in group.fields.iter() {
+            let t = times
+                .iter()
+                .position(|x| *x == field.valid_time.timestamp() as f64)
+                .ok_or_else(|| format!("Unknown time of {}: {}", field.name, field.valid_time))?;
+            let m = match field.member {
+                Some(member) if group.members => members
+                    .iter()
+                    .position(|x| *x == member)
+                    .ok_or_else(|| format!("Unknown member of {}: {}", field.name, member))?,
+                _ => 0,
+            };
+            let l = match axis {
+                Some((_, _, levels)) => surface_value(&field.level)
+                    .and_then(|value| levels.iter().position(|x| *x == value))
+                    .ok_or_else(|| format!("Unknown level of {}", field.name))?,
+                None => 0,
+            };
+            let slot = (t * member_count + m) * level_count + l;
+            if filled[slot] {
+                return Err(format!(
+                    "Duplicate {} at {}: same time, member and level",
+                    field.name, field.valid_time
+                ));
+            }
+            filled[slot] = true;
+            let start = slot * ni * nj;
+            for (dest, value) in values[start..start + ni * nj].iter_mut().zip(&field.values) {
+                if !value.is_nan() {
+                    *dest = *value;
+                }
+            }
+        }
+
+        let mut dimensions = vec![0];
+        if group.members {
+            dimensions.push(1);
+        }
+        if let Some((_, dimension, _)) = axis {
+            dimensions.push(*dimension);
+        }
+        dimensions.extend(&[y, x]);
+        netcdf.variables.push(Variable {
+            name,
+            dimensions,
+            attributes,
+            values: Values::Float(values),
+        });
+    }
+
+    Ok(netcdf)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    use super::*;
+    use crate::grib2::section::Section3;
+    use crate::grib2::utils_impl::tests::latlon_grid;
+
+    fn isobaric(pressure: f64) -> Option<Level> {
+        Some(Level::Surface(FixedSurface {
+            surface_type: 100,
+            name: "Isobaric surface",
+            unit: Some("Pa"),
+            scale_factor: Some(0),
+            scaled_value: Some(pressure as isize),
+            value: Some(pressure),
+        }))
+    }
+
+    fn field(member: Option<usize>, pressure: f64, valid_time: DateTime<Utc>, value: f32) -> Field {
+        Field {
+            key: String::from("0/0/0|ensemble"),
+            name: String::from("Temperature"),
+            description: String::from("Temperature"),
+            unit: String::from("K"),
+            parameter: (0, 0, 0),
+            probability: false,
+            level: isobaric(pressure),
+            member,
+            valid_time,
+            reference_time: Utc.ymd(2023, 6, 1).and_hms(0, 0, 0),
+            values: vec![value, value + 1.0],
+        }
+    }
+
+    // コントロールと摂動のメンバーを1つのメンバーの次元にまとめる。
+    #[test]
+    fn dataset() {
+        let buf = latlon_grid(2, 1, 36.0, 139.0, 1.0, 1.0);
+        let geometry = Section3::create(&buf).template().unwrap();
+        let t0 = Utc.ymd(2023, 6, 1).and_hms(6, 0, 0);
+        let t1 = t0 + Duration::hours(6);
+        let fields = [
+            field(Some(1), 50000.0, t0, 10.0),
+            field(Some(0), 85000.0, t0, 20.0),
+            field(Some(0), 50000.0, t1, 30.0),
+        ];
+        let netcdf = cf_dataset(&geometry, &fields).unwrap();
+
+        let dimensions: Vec<(&str, usize)> = netcdf
+            .dimensions
+            .iter()
+            .map(|x| (x.name.as_str(), x.length))
+            .collect();
+        assert_eq!(
+            dimensions,
+            [
+                ("time", 2),
+                ("member", 2),
+                ("isobaric", 2),
+                ("lat", 1),
+                ("lon", 2)
+            ]
+        );
+        let variable = netcdf
+            .variables
+            .iter()
+            .find(|x| x.name == "temperature")
+            .unwrap();
+        assert_eq!(variable.dimensions, [0, 1, 2, 3, 4]);
+        let values = match &variable.values {
+            Values::Float(values) => values,
+            _ => panic!(),
+        };
+        // 時刻、メンバー、レベル（昇順）の順に並ぶ。
+        let expected = [
+            [FILL_VALUE; 2],
+            [20.0, 21.0],
+            [10.0, 11.0],
+            [FILL_VALUE; 2],
+            [30.0, 31.0],
+            [FILL_VALUE; 2],
+            [FILL_VALUE; 2],
+            [FILL_VALUE; 2],
+        ]
+        .concat();
+        assert_eq!(values, &expected);
+    }
+
+    #[test]
+    fn invalid() {
+        let buf = latlon_grid(2, 1, 36.0, 139.0, 1.0, 1.0);
+        let geometry = Section3::create(&buf).template().unwrap();
+        let t0 = Utc.ymd(2023, 6, 1).and_hms(6, 0, 0);
+
+        // 時刻、メンバー、レベルの重複
+        let fields = [
+            field(Some(0), 50000.0, t0, 1.0),
+            field(Some(0), 50000.0, t0, 2.0),
+        ];
+        assert!(cf_dataset(&geometry, &fields).is_err());
+
+        // 格子点の数が合わない。
+        let mut fields = [field(None, 50000.0, t0, 1.0)];
+        fields[0].values.pop();
+        assert!(cf_dataset(&geometry, &fields).is_err());
+    }
+}
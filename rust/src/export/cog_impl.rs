@@ -0,0 +1,243 @@
+//! Cloud Optimized GeoTIFF implementation
+//! IFD をファイルの先頭にまとめ、画像データを最も粗い概観から順に置くので、
+//! 先頭を読めば HTTP の範囲指定で必要なタイルだけを読める。
+
+use std::str::FromStr;
+
+use crate::grib2::section::section3_template::GridTransform;
+
+use super::geotiff_impl::check_size;
+use super::geotiff_impl::float32_tags;
+use super::geotiff_impl::gdal_tags;
+use super::geotiff_impl::geo_tags;
+use super::geotiff_impl::north_up;
+use super::geotiff_impl::COMPRESSION;
+use super::geotiff_impl::NEW_SUBFILE_TYPE;
+use super::geotiff_impl::TILE_BYTE_COUNTS;
+use super::geotiff_impl::TILE_LENGTH;
+use super::geotiff_impl::TILE_OFFSETS;
+use super::geotiff_impl::TILE_WIDTH;
+use super::lzw_impl::lzw;
+use super::tiff_impl::tiff;
+use super::tiff_impl::Ifd;
+use super::tiff_impl::Tag;
+use super::zlib_impl::zlib;
+use super::Compression;
+
+const TILE_SIZE: usize = 256; // タイルの一辺の格子点数
+
+const SUBFILE_REDUCED_IMAGE: u32 = 1; // 概観
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "deflate" => Ok(Compression::Deflate),
+            "lzw" => Ok(Compression::Lzw),
+            _ => Err(format!("Unknown compression: {}", s)),
+        }
+    }
+}
+
+impl Compression {
+    // TIFF の Compression タグの値
+    fn code(&self) -> u16 {
+        match self {
+            Compression::Lzw => 5,
+            Compression::Deflate => 8,
+        }
+    }
+
+    fn compress(&self, src: &[u8]) -> Vec<u8> {
+        match self {
+            Compression::Lzw => lzw(src),
+            Compression::Deflate => zlib(src),
+        }
+    }
+}
+
+// 行ごと、左から順にタイルへ分けて圧縮する。右端と下端のタイルのはみ出した部分は NaN で埋める。
+fn tiles(values: &[f32], width: usize, height: usize, compression: Compression) -> Vec<Vec<u8>> {
+    let mut tiles = Vec::new();
+    for top in (0..height).step_by(TILE_SIZE) {
+        for left in (0..width).step_by(TILE_SIZE) {
+            let mut tile = Vec::with_capacity(TILE_SIZE * TILE_SIZE * 4);
+            for j in top..top + TILE_SIZE {
+                for i in left..left + TILE_SIZE {
+                    let value = if i < width && j < height {
+                        values[j * width + i]
+                    } else {
+                        f32::NAN
+                    };
+                    tile.extend(&value.to_le_bytes());
+                }
+            }
+            tiles.push(compression.compress(&tile));
+        }
+    }
+    tiles
+}
+
+// 縦横とも1つおきに間引く。
+fn decimate(values: &[f32], width: usize, height: usize) -> (Vec<f32>, usize, usize) {
+    let (w, h) = (width.div_ceil(2), height.div_ceil(2));
+    let mut dest = Vec::with_capacity(w * h);
+    for j in 0..h {
+        for i in 0..w {
+            dest.push(values[2 * j * width + 2 * i]);
+        }
+    }
+    (dest, w, h)
+}
+
+// タイル分けした画像の IFD
+fn tiled_ifd(
+    values: &[f32],
+    width: usize,
+    height: usize,
+    compression: Compression,
+    tags: Vec<Tag>,
+) -> Ifd {
+    let mut ifd = Ifd {
+        tags: float32_tags(width, height),
+        blocks: tiles(values, width, height, compression),
+        offsets_tag: TILE_OFFSETS,
+        byte_counts_tag: TILE_BYTE_COUNTS,
+    };
+    ifd.tags.push(Tag::short(COMPRESSION, compression.code()));
+    ifd.tags.push(Tag::short(TILE_WIDTH, TILE_SIZE as u16));
+    ifd.tags.push(Tag::short(TILE_LENGTH, TILE_SIZE as u16));
+    ifd.tags.extend(tags);
+    ifd
+}
+
+// 値を Float32 の Cloud Optimized GeoTIFF にする。
+// 全体が1タイルに収まるまで縦横を半分に間引いた概観を加える。metadata は GDAL のメタデータの名前と値の組
+pub(crate) fn cog(
+    values: &[f32],
+    width: usize,
+    height: usize,
+    transform: &GridTransform,
+    metadata: &[(String, String)],
+    compression: Compression,
+) -> Result<Vec<u8>, String> {
+    check_size(values, width, height)?;
+
+    let grid = north_up(values, width, height, transform);
+    let mut tags = geo_tags(&transform.crs, &grid);
+    tags.extend(gdal_tags(values, metadata));
+    let mut ifds = vec![tiled_ifd(&grid.values, width, height, compression, tags)];
+
+    let (mut values, mut width, mut height) = (grid.values, width, height);
+    while TILE_SIZE < width || TILE_SIZE < height {
+        let (v, w, h) = decimate(&values, width, height);
+        let mut tags = vec![Tag::long(NEW_SUBFILE_TYPE, SUBFILE_REDUCED_IMAGE)];
+        tags.extend(gdal_tags(&v, &[]));
+        ifds.push(tiled_ifd(&v, w, h, compression, tags));
+        values = v;
+        width = w;
+        height = h;
+    }
+
+    Ok(tiff(&ifds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::geotiff_impl::IMAGE_LENGTH;
+    use crate::export::geotiff_impl::IMAGE_WIDTH;
+    use crate::export::lzw_impl::tests::decode;
+    use crate::export::tiff_impl::tests::parse;
+    use crate::export::zlib_impl::tests::inflate;
+    use crate::grib2::section::section3_template::Crs;
+
+    // タイルを展開して画像に並べ直す。
+    fn read_image(tiles: &[&[u8]], width: usize, height: usize, compression: u32) -> Vec<f32> {
+        let columns = width.div_ceil(TILE_SIZE);
+        let mut dest = vec![0.0; width * height];
+        for (k, tile) in tiles.iter().enumerate() {
+            let raw = match compression {
+                5 => decode(tile).0,
+                _ => inflate(tile),
+            };
+            assert_eq!(raw.len(), TILE_SIZE * TILE_SIZE * 4);
+            let (top, left) = (k / columns * TILE_SIZE, k % columns * TILE_SIZE);
+            for (n, x) in raw.chunks_exact(4).enumerate() {
+                let value = f32::from_le_bytes([x[0], x[1], x[2], x[3]]);
+                let (i, j) = (left + n % TILE_SIZE, top + n / TILE_SIZE);
+                if i < width && j < height {
+                    dest[j * width + i] = value;
+                } else {
+                    // はみ出した部分は NaN
+                    assert!(value.is_nan());
+                }
+            }
+        }
+        dest
+    }
+
+    #[test]
+    fn tiles_and_overviews() {
+        let (width, height) = (600, 300);
+        let values: Vec<f32> = (0..width * height)
+            .map(|k| {
+                if k % 97 == 0 {
+                    f32::NAN
+                } else {
+                    k as f32 * 0.5
+                }
+            })
+            .collect();
+        let transform = GridTransform {
+            crs: Crs::Geographic,
+            x1: 100.0,
+            y1: 50.0,
+            dx: 0.1,
+            dy: -0.1,
+        };
+        for compression in [Compression::Deflate, Compression::Lzw] {
+            let src = cog(&values, width, height, &transform, &[], compression).unwrap();
+            let ifds = parse(&src);
+            // 600x300 → 300x150 → 150x75（1タイルに収まるまで）
+            let sizes: Vec<(u32, u32)> = ifds
+                .iter()
+                .map(|x| (x.integers(IMAGE_WIDTH)[0], x.integers(IMAGE_LENGTH)[0]))
+                .collect();
+            assert_eq!(sizes, [(600, 300), (300, 150), (150, 75)]);
+            assert!(ifds[0].integers(NEW_SUBFILE_TYPE).is_empty());
+            assert_eq!(ifds[1].integers(NEW_SUBFILE_TYPE), [SUBFILE_REDUCED_IMAGE]);
+
+            // 最も粗い概観のデータが先頭に並ぶ。
+            let first_tile = |k: usize| ifds[k].integers(TILE_OFFSETS)[0];
+            assert!(first_tile(2) < first_tile(1) && first_tile(1) < first_tile(0));
+            assert!(ifds.iter().all(|x| (x.offset as u32) < first_tile(2)));
+
+            let (mut expected, mut w, mut h) = (values.clone(), width, height);
+            for ifd in ifds.iter() {
+                assert_eq!(ifd.integers(COMPRESSION), [compression.code() as u32]);
+                let tiles = ifd.blocks(&src, TILE_OFFSETS, TILE_BYTE_COUNTS);
+                assert_eq!(tiles.len(), w.div_ceil(TILE_SIZE) * h.div_ceil(TILE_SIZE));
+                let image = read_image(&tiles, w, h, compression.code() as u32);
+                let same = image
+                    .iter()
+                    .zip(expected.iter())
+                    .all(|(a, b)| a == b || (a.is_nan() && b.is_nan()));
+                assert!(same);
+                let next = decimate(&expected, w, h);
+                expected = next.0;
+                w = next.1;
+                h = next.2;
+            }
+        }
+    }
+
+    #[test]
+    fn decimate_odd_size() {
+        let values: Vec<f32> = (0..15).map(|x| x as f32).collect();
+        let (dest, w, h) = decimate(&values, 5, 3);
+        assert_eq!((w, h), (3, 2));
+        assert_eq!(dest, [0.0, 2.0, 4.0, 10.0, 12.0, 14.0]);
+    }
+}
@@ -0,0 +1,358 @@
+//! GeoTIFF implementation
+
+use crate::grib2::section::section3_template::Crs;
+use crate::grib2::section::section3_template::GridTransform;
+
+use super::tiff_impl::tiff;
+use super::tiff_impl::Ifd;
+use super::tiff_impl::Tag;
+use super::tiff_impl::TagValue;
+
+// TIFF タグ
+pub(crate) const NEW_SUBFILE_TYPE: u16 = 254;
+pub(crate) const IMAGE_WIDTH: u16 = 256;
+pub(crate) const IMAGE_LENGTH: u16 = 257;
+pub(crate) const BITS_PER_SAMPLE: u16 = 258;
+pub(crate) const COMPRESSION: u16 = 259;
+pub(crate) const PHOTOMETRIC_INTERPRETATION: u16 = 262;
+const STRIP_OFFSETS: u16 = 273;
+pub(crate) const SAMPLES_PER_PIXEL: u16 = 277;
+const ROWS_PER_STRIP: u16 = 278;
+const STRIP_BYTE_COUNTS: u16 = 279;
+pub(crate) const PLANAR_CONFIGURATION: u16 = 284;
+pub(crate) const TILE_WIDTH: u16 = 322;
+pub(crate) const TILE_LENGTH: u16 = 323;
+pub(crate) const TILE_OFFSETS: u16 = 324;
+pub(crate) const TILE_BYTE_COUNTS: u16 = 325;
+pub(crate) const SAMPLE_FORMAT: u16 = 339;
+const MODEL_PIXEL_SCALE: u16 = 33550;
+const MODEL_TIEPOINT: u16 = 33922;
+const GEO_KEY_DIRECTORY: u16 = 34735;
+const GEO_DOUBLE_PARAMS: u16 = 34736;
+const GEO_ASCII_PARAMS: u16 = 34737;
+const GDAL_METADATA: u16 = 42112;
+const GDAL_NODATA: u16 = 42113;
+
+const STRIP_SIZE: usize = 8192; // ストリップ1つあたりのおよそのバイト数
+
+// GeoKey
+const GT_MODEL_TYPE: u16 = 1024;
+const GT_RASTER_TYPE: u16 = 1025;
+const GT_CITATION: u16 = 1026;
+const GEOGRAPHIC_TYPE: u16 = 2048;
+const GEOG_GEODETIC_DATUM: u16 = 2050;
+const GEOG_ANGULAR_UNITS: u16 = 2054;
+const GEOG_ELLIPSOID: u16 = 2056;
+const GEOG_SEMI_MAJOR_AXIS: u16 = 2057;
+const GEOG_SEMI_MINOR_AXIS: u16 = 2058;
+const PROJECTED_CS_TYPE: u16 = 3072;
+const PROJECTION: u16 = 3074;
+const PROJ_COORD_TRANS: u16 = 3075;
+const PROJ_LINEAR_UNITS: u16 = 3076;
+const PROJ_STD_PARALLEL1: u16 = 3078;
+const PROJ_STD_PARALLEL2: u16 = 3079;
+const PROJ_NAT_ORIGIN_LONG: u16 = 3080;
+const PROJ_NAT_ORIGIN_LAT: u16 = 3081;
+const PROJ_FALSE_EASTING: u16 = 3082;
+const PROJ_FALSE_NORTHING: u16 = 3083;
+const PROJ_FALSE_ORIGIN_LONG: u16 = 3084;
+const PROJ_FALSE_ORIGIN_LAT: u16 = 3085;
+const PROJ_FALSE_ORIGIN_EASTING: u16 = 3086;
+const PROJ_FALSE_ORIGIN_NORTHING: u16 = 3087;
+const PROJ_SCALE_AT_NAT_ORIGIN: u16 = 3092;
+const PROJ_STRAIGHT_VERT_POLE_LONG: u16 = 3095;
+
+const MODEL_TYPE_PROJECTED: u16 = 1;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+const RASTER_PIXEL_IS_AREA: u16 = 1;
+const USER_DEFINED: u16 = 32767;
+const GCS_WGS_84: u16 = 4326;
+const ANGULAR_DEGREE: u16 = 9102;
+const LINEAR_METER: u16 = 9001;
+const CT_MERCATOR: u16 = 7;
+const CT_LAMBERT_CONF_CONIC_2SP: u16 = 11;
+const CT_POLAR_STEREOGRAPHIC: u16 = 15;
+
+// 北が上、西が左になるよう並べ替えた格子
+pub(crate) struct NorthUp {
+    pub(crate) values: Vec<f32>,
+    pub(crate) left: f64, // 左上のセルの左上隅の座標
+    pub(crate) top: f64,
+    pub(crate) scale_x: f64, // セルの大きさ
+    pub(crate) scale_y: f64,
+}
+
+// 格子を北が上、西が左になるよう並べ替える。
+pub(crate) fn north_up(
+    values: &[f32],
+    width: usize,
+    height: usize,
+    transform: &GridTransform,
+) -> NorthUp {
+    let flip_x = transform.dx < 0.0;
+    let flip_y = 0.0 < transform.dy;
+
+    let mut dest = Vec::with_capacity(values.len());
+    for j in 0..height {
+        let sj = if flip_y { height - 1 - j } else { j };
+        for i in 0..width {
+            let si = if flip_x { width - 1 - i } else { i };
+            dest.push(values[sj * width + si]);
+        }
+    }
+
+    // 最初の行と列の格子点の座標から、セルの隅の座標を求める。
+    let (scale_x, scale_y) = (transform.dx.abs(), transform.dy.abs());
+    let x = transform.x1
+        + if flip_x {
+            (width - 1) as f64 * transform.dx
+        } else {
+            0.0
+        };
+    let y = transform.y1
+        + if flip_y {
+            (height - 1) as f64 * transform.dy
+        } else {
+            0.0
+        };
+    NorthUp {
+        values: dest,
+        left: x - scale_x / 2.0,
+        top: y + scale_y / 2.0,
+        scale_x,
+        scale_y,
+    }
+}
+
+// GeoKey の一覧から GeoKeyDirectory, GeoDoubleParams, GeoAsciiParams のタグを作る。
+enum GeoKeyValue {
+    Short(u16),
+    Double(f64),
+    Ascii(String),
+}
+
+fn geo_key_tags(mut keys: Vec<(u16, GeoKeyValue)>) -> Vec<Tag> {
+    keys.sort_by_key(|(key, _)| *key);
+
+    let mut directory = vec![1, 1, 0, keys.len() as u16];
+    let mut doubles = Vec::new();
+    let mut ascii = String::new();
+    for (key, value) in keys {
+        match value {
+            GeoKeyValue::Short(x) => directory.extend(&[key, 0, 1, x]),
+            GeoKeyValue::Double(x) => {
+                directory.extend(&[key, GEO_DOUBLE_PARAMS, 1, doubles.len() as u16]);
+                doubles.push(x);
+            }
+            GeoKeyValue::Ascii(x) => {
+                let text = format!("{}|", x);
+                directory.extend(&[key, GEO_ASCII_PARAMS, text.len() as u16, ascii.len() as u16]);
+                ascii += &text;
+            }
+        }
+    }
+
+    let mut tags = vec![Tag {
+        code: GEO_KEY_DIRECTORY,
+        value: TagValue::Short(directory),
+    }];
+    if !doubles.is_empty() {
+        tags.push(Tag {
+            code: GEO_DOUBLE_PARAMS,
+            value: TagValue::Double(doubles),
+        });
+    }
+    if !ascii.is_empty() {
+        tags.push(Tag {
+            code: GEO_ASCII_PARAMS,
+            value: TagValue::Ascii(ascii),
+        });
+    }
+    tags
+}
+
+// 座標参照系の GeoKey
+fn crs_keys(crs: &Crs) -> Vec<(u16, GeoKeyValue)> {
+    use GeoKeyValue::{Ascii, Double, Short};
+
+    let (radius, coord_trans, name, parameters) = match *crs {
+        Crs::Geographic => {
+            return vec![
+                (GT_MODEL_TYPE, Short(MODEL_TYPE_GEOGRAPHIC)),
+                (GT_RASTER_TYPE, Short(RASTER_PIXEL_IS_AREA)),
+                (GEOGRAPHIC_TYPE, Short(GCS_WGS_84)),
+                (GEOG_ANGULAR_UNITS, Short(ANGULAR_DEGREE)),
+            ];
+        }
+        Crs::Mercator { radius, lon0, lad } => (
+            radius,
+            CT_MERCATOR,
+            "Mercator",
+            vec![
+                (PROJ_STD_PARALLEL1, lad),
+                (PROJ_NAT_ORIGIN_LONG, lon0),
+                (PROJ_FALSE_EASTING, 0.0),
+                (PROJ_FALSE_NORTHING, 0.0),
+            ],
+        ),
+        Crs::PolarStereographic { radius, lov, lad } => (
+            radius,
+            CT_POLAR_STEREOGRAPHIC,
+            "Polar stereographic",
+            vec![
+                (PROJ_STRAIGHT_VERT_POLE_LONG, lov),
+                (PROJ_NAT_ORIGIN_LAT, lad),
+                (PROJ_SCALE_AT_NAT_ORIGIN, 1.0),
+                (PROJ_FALSE_EASTING, 0.0),
+                (PROJ_FALSE_NORTHING, 0.0),
+            ],
+        ),
+        Crs::LambertConformal {
+            radius,
+            lov,
+            latin1,
+            latin2,
+            false_northing,
+        } => (
+            radius,
+            CT_LAMBERT_CONF_CONIC_2SP,
+            "Lambert conformal conic",
+            vec![
+                (PROJ_STD_PARALLEL1, latin1),
+                (PROJ_STD_PARALLEL2, latin2),
+                (PROJ_FALSE_ORIGIN_LONG, lov),
+                (PROJ_FALSE_ORIGIN_LAT, latin1),
+                (PROJ_FALSE_ORIGIN_EASTING, 0.0),
+                (PROJ_FALSE_ORIGIN_NORTHING, false_northing),
+            ],
+        ),
+    };
+
+    let mut keys = vec![
+        (GT_MODEL_TYPE, Short(MODEL_TYPE_PROJECTED)),
+        (GT_RASTER_TYPE, Short(RASTER_PIXEL_IS_AREA)),
+        (GT_CITATION, Ascii(format!("{} on sphere", name))),
+        (GEOGRAPHIC_TYPE, Short(USER_DEFINED)),
+        (GEOG_GEODETIC_DATUM, Short(USER_DEFINED)),
+        (GEOG_ANGULAR_UNITS, Short(ANGULAR_DEGREE)),
+        (GEOG_ELLIPSOID, Short(USER_DEFINED)),
+        (GEOG_SEMI_MAJOR_AXIS, Double(radius)),
+        (GEOG_SEMI_MINOR_AXIS, Double(radius)),
+        (PROJECTED_CS_TYPE, Short(USER_DEFINED)),
+        (PROJECTION, Short(USER_DEFINED)),
+        (PROJ_COORD_TRANS, Short(coord_trans)),
+        (PROJ_LINEAR_UNITS, Short(LINEAR_METER)),
+    ];
+    keys.extend(parameters.into_iter().map(|(key, x)| (key, Double(x))));
+    keys
+}
+
+// 座標参照系と位置のタグ
+pub(crate) fn geo_tags(crs: &Crs, grid: &NorthUp) -> Vec<Tag> {
+    let mut tags = vec![
+        Tag {
+            code: MODEL_PIXEL_SCALE,
+            value: TagValue::Double(vec![grid.scale_x, grid.scale_y, 0.0]),
+        },
+        Tag {
+            code: MODEL_TIEPOINT,
+            value: TagValue::Double(vec![0.0, 0.0, 0.0, grid.left, grid.top, 0.0]),
+        },
+    ];
+    tags.extend(geo_key_tags(crs_keys(crs)));
+    tags
+}
+
+fn escape_xml(src: &str) -> String {
+    src.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// 欠測値と GDAL のメタデータのタグ
+pub(crate) fn gdal_tags(values: &[f32], metadata: &[(String, String)]) -> Vec<Tag> {
+    let mut tags = Vec::new();
+    if !metadata.is_empty() {
+        let items: Vec<String> = metadata
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "<Item name=\"{}\">{}</Item>",
+                    escape_xml(name),
+                    escape_xml(value)
+                )
+            })
+            .collect();
+        tags.push(Tag {
+            code: GDAL_METADATA,
+            value: TagValue::Ascii(format!("<GDALMetadata>{}</GDALMetadata>", items.concat())),
+        });
+    }
+    // ビットマップで欠けた格子点は NaN とする。
+    if values.iter().any(|x| x.is_nan()) {
+        tags.push(Tag {
+            code: GDAL_NODATA,
+            value: TagValue::Ascii(String::from("nan")),
+        });
+    }
+    tags
+}
+
+// 1バンド Float32 の画像の基本的なタグ
+pub(crate) fn float32_tags(width: usize, height: usize) -> Vec<Tag> {
+    vec![
+        Tag::long(IMAGE_WIDTH, width as u32),
+        Tag::long(IMAGE_LENGTH, height as u32),
+        Tag::short(BITS_PER_SAMPLE, 32),
+        Tag::short(PHOTOMETRIC_INTERPRETATION, 1), // BlackIsZero
+        Tag::short(SAMPLES_PER_PIXEL, 1),
+        Tag::short(PLANAR_CONFIGURATION, 1),
+        Tag::short(SAMPLE_FORMAT, 3), // IEEE 浮動小数点数
+    ]
+}
+
+// 値の数が格子点数と合っているか確かめる。
+pub(crate) fn check_size(values: &[f32], width: usize, height: usize) -> Result<(), String> {
+    if width == 0 || height == 0 || values.len() != width * height {
+        return Err(format!(
+            "Invalid grid size: {}x{} ({} values)",
+            width,
+            height,
+            values.len()
+        ));
+    }
+    Ok(())
+}
+
+// 値を Float32 の GeoTIFF にする。metadata は GDAL のメタデータの名前と値の組
+pub(crate) fn geotiff(
+    values: &[f32],
+    width: usize,
+    height: usize,
+    transform: &GridTransform,
+    metadata: &[(String, String)],
+) -> Result<Vec<u8>, String> {
+    check_size(values, width, height)?;
+
+    let grid = north_up(values, width, height, transform);
+    let rows_per_strip = (STRIP_SIZE / (width * 4)).max(1);
+    let blocks = grid
+        .values
+        .chunks(rows_per_strip * width)
+        .map(|x| x.iter().flat_map(|v| v.to_le_bytes()).collect())
+        .collect();
+
+    let mut tags = float32_tags(width, height);
+    tags.push(Tag::short(COMPRESSION, 1));
+    tags.push(Tag::long(ROWS_PER_STRIP, rows_per_strip as u32));
+    tags.extend(geo_tags(&transform.crs, &grid));
+    tags.extend(gdal_tags(values, metadata));
+
+    Ok(tiff(&[Ifd {
+        tags,
+        blocks,
+        offsets_tag: STRIP_OFFSETS,
+        byte_counts_tag: STRIP_BYTE_COUNTS,
+    }]))
+}
@@ -0,0 +1,168 @@
+//! LZW implementation
+//! TIFF の LZW 圧縮（上位ビットから詰め、符号長を1つ早く増やす）
+
+use std::collections::HashMap;
+
+const CLEAR_CODE: u16 = 256;
+const END_OF_INFORMATION: u16 = 257;
+const FIRST_CODE: u16 = 258;
+const MIN_BITS: u32 = 9;
+const MAX_CODE: u16 = 4093; // 辞書をこれより大きくしない
+
+// 上位ビットから順に詰める。
+struct BitWriter {
+    dest: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, bits: u32) {
+        self.buffer = (self.buffer << bits) | code as u32;
+        self.count += bits;
+        while 8 <= self.count {
+            self.count -= 8;
+            self.dest.push((self.buffer >> self.count) as u8);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if 0 < self.count {
+            self.dest.push((self.buffer << (8 - self.count)) as u8);
+        }
+        self.dest
+    }
+}
+
+// 符号を書く。符号を1つ書くごとに辞書が1つ増えるものとして次の符号長を決め、
+// 辞書が一杯になれば初期化して true を返す。
+fn emit(writer: &mut BitWriter, code: u16, next: &mut u16, bits: &mut u32) -> bool {
+    writer.write(code, *bits);
+    *next += 1;
+    if *next == MAX_CODE {
+        writer.write(CLEAR_CODE, *bits);
+        *next = FIRST_CODE;
+        *bits = MIN_BITS;
+        return true;
+    }
+    if (1 << *bits) - 1 < *next {
+        *bits += 1;
+    }
+    false
+}
+
+pub(crate) fn lzw(src: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        dest: Vec::with_capacity(src.len() / 2),
+        buffer: 0,
+        count: 0,
+    };
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut bits = MIN_BITS;
+    let mut next = FIRST_CODE;
+    writer.write(CLEAR_CODE, bits);
+
+    if let Some((&first, rest)) = src.split_first() {
+        let mut prefix = first as u16;
+        for &x in rest {
+            if let Some(&code) = dictionary.get(&(prefix, x)) {
+                prefix = code;
+                continue;
+            }
+            let code = next;
+            if emit(&mut writer, prefix, &mut next, &mut bits) {
+                dictionary.clear();
+            } else {
+                dictionary.insert((prefix, x), code);
+            }
+            prefix = x as u16;
+        }
+        emit(&mut writer, prefix, &mut next, &mut bits);
+    }
+    writer.write(END_OF_INFORMATION, bits);
+    writer.finish()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::export::zlib_impl::tests::noise;
+
+    // TIFF 6.0 の LZW の検証用の展開
+    // 符号は上位ビットから詰め、辞書の次の番号が 2^符号長 - 1 に達した時点で符号長を増やす（early change）。
+    // 展開したデータと、Clear 符号の数を返す。
+    pub(crate) fn decode(src: &[u8]) -> (Vec<u8>, usize) {
+        let mut position = 0;
+        let mut read = |bits: u32| {
+            let mut code = 0u16;
+            for _ in 0..bits {
+                let byte = src[position / 8];
+                code = (code << 1) | ((byte >> (7 - position % 8)) & 1) as u16;
+                position += 1;
+            }
+            code
+        };
+        let mut dest = Vec::new();
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut previous: Option<Vec<u8>> = None;
+        let mut bits = MIN_BITS;
+        let mut clears = 0;
+        loop {
+            let code = read(bits);
+            if code == END_OF_INFORMATION {
+                return (dest, clears);
+            }
+            if code == CLEAR_CODE {
+                table = (0..=255u8).map(|x| vec![x]).collect();
+                table.extend(vec![Vec::new(), Vec::new()]);
+                bits = MIN_BITS;
+                previous = None;
+                clears += 1;
+                continue;
+            }
+            let entry = match previous {
+                None => table[code as usize].clone(),
+                Some(previous) => {
+                    let entry = if (code as usize) < table.len() {
+                        table[code as usize].clone()
+                    } else {
+                        assert_eq!(code as usize, table.len(), "Invalid code");
+                        [&previous[..], &previous[..1]].concat()
+                    };
+                    table.push([&previous[..], &entry[..1]].concat());
+                    assert!(table.len() <= 4096, "Table overflow");
+                    entry
+                }
+            };
+            dest.extend(&entry);
+            previous = Some(entry);
+            if (1 << bits) - 1 <= table.len() && bits < 12 {
+                bits += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let cases: Vec<Vec<u8>> = vec![
+            Vec::new(),
+            vec![7],
+            b"TOBEORNOTTOBEORTOBEORNOT#".to_vec(),
+            vec![0; 100_000],
+            (0..60_000).map(|x| (x % 251) as u8).collect(),
+        ];
+        for src in cases.iter() {
+            let (dest, _) = decode(&lzw(src));
+            assert_eq!(&dest, src);
+        }
+    }
+
+    // 一致の少ないデータでは辞書が何度も一杯になり、符号長が 9 から 12 まで増えては Clear で戻る。
+    #[test]
+    fn clear_when_full() {
+        let src = noise(100_000, 3);
+        let (dest, clears) = decode(&lzw(&src));
+        assert_eq!(dest, src);
+        assert!(10 < clears);
+    }
+}
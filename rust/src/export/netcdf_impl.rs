@@ -0,0 +1,389 @@
+//! NetCDF implementation
+//! NetCDF-3 の classic 形式（大きければ 64-bit offset 形式）を書き出す。値はビッグエンディアン
+
+const NC_DIMENSION: u32 = 0x0a;
+const NC_VARIABLE: u32 = 0x0b;
+const NC_ATTRIBUTE: u32 = 0x0c;
+
+const NC_CHAR: u32 = 2;
+const NC_INT: u32 = 4;
+const NC_FLOAT: u32 = 5;
+const NC_DOUBLE: u32 = 6;
+
+// 属性または変数の値
+#[derive(Clone, Debug)]
+pub(crate) enum Values {
+    Char(String),
+    Int(Vec<i32>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Dimension {
+    pub(crate) name: String,
+    pub(crate) length: usize,
+}
+
+// 変数。dimensions は次元の番号で、空ならスカラー
+#[derive(Clone, Debug)]
+pub(crate) struct Variable {
+    pub(crate) name: String,
+    pub(crate) dimensions: Vec<usize>,
+    pub(crate) attributes: Vec<(String, Values)>,
+    pub(crate) values: Values,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct NetCdf {
+    pub(crate) dimensions: Vec<Dimension>,
+    pub(crate) attributes: Vec<(String, Values)>,
+    pub(crate) variables: Vec<Variable>,
+}
+
+// 4バイト境界に合わせる。
+fn pad(dest: &mut Vec<u8>) {
+    dest.resize(dest.len().next_multiple_of(4), 0);
+}
+
+fn write_name(dest: &mut Vec<u8>, name: &str) {
+    dest.extend(&(name.len() as u32).to_be_bytes());
+    dest.extend(name.as_bytes());
+    pad(dest);
+}
+
+impl Values {
+    fn nc_type(&self) -> u32 {
+        match self {
+            Values::Char(_) => NC_CHAR,
+            Values::Int(_) => NC_INT,
+            Values::Float(_) => NC_FLOAT,
+            Values::Double(_) => NC_DOUBLE,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Values::Char(x) => x.len(),
+            Values::Int(x) => x.len(),
+            Values::Float(x) => x.len(),
+            Values::Double(x) => x.len(),
+        }
+    }
+
+    // 4バイト境界に合わせたバイト数
+    fn size(&self) -> usize {
+        let size = match self {
+            Values::Char(x) => x.len(),
+            Values::Int(x) => x.len() * 4,
+            Values::Float(x) => x.len() * 4,
+            Values::Double(x) => x.len() * 8,
+        };
+        size.next_multiple_of(4)
+    }
+
+    fn write(&self, dest: &mut Vec<u8>) {
+        match self {
+            Values::Char(x) => dest.extend(x.as_bytes()),
+            Values::Int(x) => x.iter().for_each(|v| dest.extend(&v.to_be_bytes())),
+            Values::Float(x) => x.iter().for_each(|v| dest.extend(&v.to_be_bytes())),
+            Values::Double(x) => x.iter().for_each(|v| dest.extend(&v.to_be_bytes())),
+        }
+        pad(dest);
+    }
+}
+
+fn write_attributes(dest: &mut Vec<u8>, attributes: &[(String, Values)]) {
+    if attributes.is_empty() {
+        dest.extend(&[0; 8]); // ABSENT
+        return;
+    }
+    dest.extend(&NC_ATTRIBUTE.to_be_bytes());
+    dest.extend(&(attributes.len() as u32).to_be_bytes());
+    for (name, values) in attributes.iter() {
+        write_name(dest, name);
+        dest.extend(&values.nc_type().to_be_bytes());
+        dest.extend(&(values.len() as u32).to_be_bytes());
+        values.write(dest);
+    }
+}
+
+impl NetCdf {
+    // ヘッダー。begins は各変数の値の位置、offset_size は位置のバイト数（4 または 8）
+    fn write_header(&self, dest: &mut Vec<u8>, begins: &[usize], offset_size: usize) {
+        dest.extend(b"CDF");
+        dest.push(if offset_size == 8 { 2 } else { 1 });
+        dest.extend(&0u32.to_be_bytes()); // numrecs（レコード次元は使わない）
+
+        if self.dimensions.is_empty() {
+            dest.extend(&[0; 8]);
+        } else {
+            dest.extend(&NC_DIMENSION.to_be_bytes());
+            dest.extend(&(self.dimensions.len() as u32).to_be_bytes());
+            for dimension in self.dimensions.iter() {
+                write_name(dest, &dimension.name);
+                dest.extend(&(dimension.length as u32).to_be_bytes());
+            }
+        }
+
+        write_attributes(dest, &self.attributes);
+
+        if self.variables.is_empty() {
+            dest.extend(&[0; 8]);
+            return;
+        }
+        dest.extend(&NC_VARIABLE.to_be_bytes());
+        dest.extend(&(self.variables.len() as u32).to_be_bytes());
+        for (variable, begin) in self.variables.iter().zip(begins.iter()) {
+            write_name(dest, &variable.name);
+            dest.extend(&(variable.dimensions.len() as u32).to_be_bytes());
+            for id in variable.dimensions.iter() {
+                dest.extend(&(*id as u32).to_be_bytes());
+            }
+            write_attributes(dest, &variable.attributes);
+            dest.extend(&variable.values.nc_type().to_be_bytes());
+            let size = variable.values.size().min(u32::MAX as usize);
+            dest.extend(&(size as u32).to_be_bytes());
+            if offset_size == 8 {
+                dest.extend(&(*begin as u64).to_be_bytes());
+            } else {
+                dest.extend(&(*begin as u32).to_be_bytes());
+            }
+        }
+    }
+
+    // ヘッダーの後ろに変数の値を順に並べる。
+    fn begins(&self, header_size: usize) -> Vec<usize> {
+        let mut offset = header_size;
+        self.variables
+            .iter()
+            .map(|x| {
+                let begin = offset;
+                offset += x.values.size();
+                begin
+            })
+            .collect()
+    }
+
+    pub(crate) fn write(&self) -> Result<Vec<u8>, String> {
+        for variable in self.variables.iter() {
+            let count: usize = variable
+                .dimensions
+                .iter()
+                .map(|x| self.dimensions[*x].length)
+                .product();
+            if variable.values.len() != count {
+                return Err(format!(
+                    "Invalid value count of {}: {} (expected {})",
+                    variable.name,
+                    variable.values.len(),
+                    count
+                ));
+            }
+        }
+
+        // 最後の変数の位置が 32 ビットに収まらなければ 64-bit offset 形式にする。
+        let mut header = Vec::new();
+        let mut offset_size = 4;
+        self.write_header(&mut header, &vec![0; self.variables.len()], offset_size);
+        let begins = self.begins(header.len());
+        if begins.last().is_some_and(|x| i32::MAX as usize <= *x) {
+            offset_size = 8;
+            header.clear();
+            self.write_header(&mut header, &begins, offset_size);
+        }
+        let begins = self.begins(header.len());
+
+        let mut dest = Vec::with_capacity(header.len());
+        self.write_header(&mut dest, &begins, offset_size);
+        for variable in self.variables.iter() {
+            variable.values.write(&mut dest);
+        }
+        Ok(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NetCDF-3 の classic 形式のヘッダーを読む検証用の読み取り
+    struct Reader<'a> {
+        src: &'a [u8],
+        position: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn u32(&mut self) -> u32 {
+            let p = self.position;
+            self.position += 4;
+            u32::from_be_bytes([
+                self.src[p],
+                self.src[p + 1],
+                self.src[p + 2],
+                self.src[p + 3],
+            ])
+        }
+
+        fn name(&mut self) -> String {
+            let length = self.u32() as usize;
+            let name = String::from_utf8(self.src[self.position..self.position + length].to_vec());
+            self.position += length.next_multiple_of(4);
+            name.unwrap()
+        }
+
+        // 値を nc_type に応じて読む。
+        fn values(&mut self, nc_type: u32, count: usize) -> Values {
+            let start = self.position;
+            let bytes = |size: usize| self.src[start..start + count * size].chunks_exact(size);
+            let values = match nc_type {
+                NC_CHAR => Values::Char(
+                    String::from_utf8(self.src[start..start + count].to_vec()).unwrap(),
+                ),
+                NC_INT => Values::Int(
+                    bytes(4)
+                        .map(|x| i32::from_be_bytes([x[0], x[1], x[2], x[3]]))
+                        .collect(),
+                ),
+                NC_FLOAT => Values::Float(
+                    bytes(4)
+                        .map(|x| f32::from_be_bytes([x[0], x[1], x[2], x[3]]))
+                        .collect(),
+                ),
+                NC_DOUBLE => Values::Double(
+                    bytes(8)
+                        .map(|x| {
+                            let mut b = [0; 8];
+                            b.copy_from_slice(x);
+                            f64::from_be_bytes(b)
+                        })
+                        .collect(),
+                ),
+                x => panic!("Unexpected type: {}", x),
+            };
+            self.position += values.size();
+            values
+        }
+
+        fn attributes(&mut self) -> Vec<(String, Values)> {
+            let tag = self.u32();
+            let count = self.u32() as usize;
+            assert!(tag == NC_ATTRIBUTE || (tag == 0 && count == 0));
+            (0..count)
+                .map(|_| {
+                    let name = self.name();
+                    let nc_type = self.u32();
+                    let n = self.u32() as usize;
+                    (name, self.values(nc_type, n))
+                })
+                .collect()
+        }
+    }
+
+    fn dataset() -> NetCdf {
+        NetCdf {
+            dimensions: vec![
+                Dimension {
+                    name: String::from("y"),
+                    length: 2,
+                },
+                Dimension {
+                    name: String::from("x"),
+                    length: 3,
+                },
+            ],
+            attributes: vec![(
+                String::from("Conventions"),
+                Values::Char(String::from("CF-1.8")),
+            )],
+            variables: vec![
+                Variable {
+                    name: String::from("crs"),
+                    dimensions: Vec::new(),
+                    attributes: Vec::new(),
+                    values: Values::Int(vec![0]),
+                },
+                Variable {
+                    name: String::from("x"),
+                    dimensions: vec![1],
+                    attributes: vec![(String::from("units"), Values::Char(String::from("m")))],
+                    values: Values::Double(vec![0.5, 1.5, 2.5]),
+                },
+                Variable {
+                    name: String::from("temperature"),
+                    dimensions: vec![0, 1],
+                    attributes: vec![
+                        (String::from("units"), Values::Char(String::from("K"))),
+                        (String::from("_FillValue"), Values::Float(vec![-1.0])),
+                    ],
+                    values: Values::Float(vec![1.0, 2.0, 3.0, 4.0, 5.0, -1.0]),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn header_and_values() {
+        let src = dataset().write().unwrap();
+        let mut reader = Reader {
+            src: &src,
+            position: 0,
+        };
+        assert_eq!(&src[..4], b"CDF\x01");
+        reader.position = 4;
+        assert_eq!(reader.u32(), 0); // numrecs
+
+        assert_eq!(reader.u32(), NC_DIMENSION);
+        assert_eq!(reader.u32(), 2);
+        let dimensions: Vec<(String, u32)> =
+            (0..2).map(|_| (reader.name(), reader.u32())).collect();
+        assert_eq!(dimensions, [(String::from("y"), 2), (String::from("x"), 3)]);
+
+        let attributes = reader.attributes();
+        assert_eq!(attributes.len(), 1);
+        assert!(matches!(&attributes[0].1, Values::Char(x) if x == "CF-1.8"));
+
+        assert_eq!(reader.u32(), NC_VARIABLE);
+        assert_eq!(reader.u32(), 3);
+        let mut variables = Vec::new();
+        for _ in 0..3 {
+            let name = reader.name();
+            let rank = reader.u32() as usize;
+            let dimensions: Vec<u32> = (0..rank).map(|_| reader.u32()).collect();
+            let attributes = reader.attributes();
+            let nc_type = reader.u32();
+            let size = reader.u32() as usize;
+            let begin = reader.u32() as usize;
+            variables.push((name, dimensions, attributes, nc_type, size, begin));
+        }
+        let header_size = reader.position;
+
+        let expected = dataset();
+        let mut offset = header_size;
+        for ((name, dimensions, attributes, nc_type, size, begin), variable) in
+            variables.iter().zip(expected.variables.iter())
+        {
+            assert_eq!(name, &variable.name);
+            let ids: Vec<u32> = variable.dimensions.iter().map(|x| *x as u32).collect();
+            assert_eq!(dimensions, &ids);
+            assert_eq!(attributes.len(), variable.attributes.len());
+            assert_eq!(*nc_type, variable.values.nc_type());
+            assert_eq!(*size, variable.values.size());
+            // 値はヘッダーの直後から変数の順に隙間なく並ぶ。
+            assert_eq!(*begin, offset);
+            offset += size;
+
+            reader.position = *begin;
+            let values = reader.values(*nc_type, variable.values.len());
+            assert_eq!(format!("{:?}", values), format!("{:?}", variable.values));
+        }
+        assert_eq!(offset, src.len());
+    }
+
+    #[test]
+    fn invalid_count() {
+        let mut dataset = dataset();
+        dataset.variables[1].values = Values::Double(vec![0.0; 2]);
+        assert!(dataset.write().is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! PNG implementation
+
+use super::zlib_impl::crc32;
+use super::zlib_impl::update_crc32;
+use super::zlib_impl::zlib;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+const BYTES_PER_PIXEL: usize = 4; // RGBA 8bit
+
+fn write_chunk(dest: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    dest.extend(&(data.len() as u32).to_be_bytes());
+    dest.extend(chunk_type);
+    dest.extend(data);
+    dest.extend(&update_crc32(crc32(chunk_type), data).to_be_bytes());
+}
+
+// Paeth 予測子
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// 行にフィルターをかける。 row は現在の行、prior は1つ上の行（最初の行なら 0 埋め）
+fn filter(filter_type: u8, row: &[u8], prior: &[u8]) -> Vec<u8> {
+    (0..row.len())
+        .map(|i| {
+            let a = if BYTES_PER_PIXEL <= i {
+                row[i - BYTES_PER_PIXEL]
+            } else {
+                0
+            };
+            let b = prior[i];
+            let c = if BYTES_PER_PIXEL <= i {
+                prior[i - BYTES_PER_PIXEL]
+            } else {
+                0
+            };
+            let predictor = match filter_type {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                _ => paeth(a, b, c),
+            };
+            row[i].wrapping_sub(predictor)
+        })
+        .collect()
+}
+
+// 行ごとに差分の絶対値の和が最小になるフィルターを選んで並べる。
+fn filter_rows(rgba: &[u8], width: usize) -> Vec<u8> {
+    let stride = width * BYTES_PER_PIXEL;
+    let zeros = vec![0u8; stride];
+    let mut dest = Vec::with_capacity(rgba.len() + rgba.len() / stride.max(1));
+    for (j, row) in rgba.chunks_exact(stride).enumerate() {
+        let prior = if j == 0 {
+            &zeros[..]
+        } else {
+            &rgba[(j - 1) * stride..j * stride]
+        };
+        let (filter_type, filtered) = (0..=4)
+            .map(|t| (t, filter(t, row, prior)))
+            .min_by_key(|(_, x)| {
+                x.iter()
+                    .map(|v| (*v as i8).unsigned_abs() as usize)
+                    .sum::<usize>()
+            })
+            .unwrap();
+        dest.push(filter_type);
+        dest.extend(filtered);
+    }
+    dest
+}
+
+// 文字列のメタデータのチャンク。Latin-1 で表せれば tEXt、そうでなければ UTF-8 の iTXt にする。
+fn write_text(dest: &mut Vec<u8>, keyword: &str, text: &str) {
+    let mut data: Vec<u8> = keyword.bytes().collect();
+    data.push(0);
+    if text.chars().all(|c| (c as u32) < 256) {
+        data.extend(text.chars().map(|c| c as u8));
+        write_chunk(dest, b"tEXt", &data);
+    } else {
+        data.extend(&[0, 0, 0, 0]); // 非圧縮、圧縮方式、言語タグ、翻訳したキーワード
+        data.extend(text.as_bytes());
+        write_chunk(dest, b"iTXt", &data);
+    }
+}
+
+// RGBA 8bit の画像を PNG にする。texts はキーワードと文字列の組
+pub(crate) fn png(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    texts: &[(&str, String)],
+) -> Result<Vec<u8>, String> {
+    if width == 0 || height == 0 || rgba.len() != width * height * BYTES_PER_PIXEL {
+        return Err(format!(
+            "Invalid image size: {}x{} ({} bytes)",
+            width,
+            height,
+            rgba.len()
+        ));
+    }
+
+    let mut dest = SIGNATURE.to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(&(width as u32).to_be_bytes());
+    header.extend(&(height as u32).to_be_bytes());
+    header.extend(&[8, 6, 0, 0, 0]); // ビット深度 8、RGBA、deflate、フィルター方式 0、インターレースなし
+    write_chunk(&mut dest, b"IHDR", &header);
+
+    for (keyword, text) in texts {
+        write_text(&mut dest, keyword, text);
+    }
+
+    write_chunk(&mut dest, b"IDAT", &zlib(&filter_rows(rgba, width)));
+    write_chunk(&mut dest, b"IEND", &[]);
+    Ok(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::zlib_impl::tests::inflate;
+    use crate::export::zlib_impl::tests::noise;
+
+    // チャンクの種類とデータの並び。CRC も確かめる。
+    fn chunks(src: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        assert_eq!(src[..8], SIGNATURE);
+        let mut chunks = Vec::new();
+        let mut position = 8;
+        while position < src.len() {
+            let length = u32::from_be_bytes([
+                src[position],
+                src[position + 1],
+                src[position + 2],
+                src[position + 3],
+            ]) as usize;
+            let body = &src[position + 4..position + 8 + length];
+            let crc = &src[position + 8 + length..position + 12 + length];
+            assert_eq!(crc, crc32(body).to_be_bytes());
+            let mut chunk_type = [0; 4];
+            chunk_type.copy_from_slice(&body[..4]);
+            chunks.push((chunk_type, body[4..].to_vec()));
+            position += 12 + length;
+        }
+        chunks
+    }
+
+    // フィルターを外して RGBA に戻す。
+    fn unfilter(src: &[u8], width: usize) -> Vec<u8> {
+        let stride = width * BYTES_PER_PIXEL;
+        let mut dest: Vec<u8> = Vec::new();
+        for (j, row) in src.chunks_exact(stride + 1).enumerate() {
+            for i in 0..stride {
+                let a = if BYTES_PER_PIXEL <= i {
+                    dest[j * stride + i - BYTES_PER_PIXEL]
+                } else {
+                    0
+                };
+                let b = if 0 < j { dest[(j - 1) * stride + i] } else { 0 };
+                let c = if 0 < j && BYTES_PER_PIXEL <= i {
+                    dest[(j - 1) * stride + i - BYTES_PER_PIXEL]
+                } else {
+                    0
+                };
+                let predictor = match row[0] {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    4 => paeth(a, b, c),
+                    x => panic!("Invalid filter type: {}", x),
+                };
+                dest.push(row[1 + i].wrapping_add(predictor));
+            }
+        }
+        dest
+    }
+
+    #[test]
+    fn round_trip() {
+        let (width, height) = (37, 23);
+        // 滑らかな部分と乱数の部分を混ぜて、行ごとに異なるフィルターが選ばれるようにする。
+        let mut rgba = noise(width * height * BYTES_PER_PIXEL, 4);
+        for (k, x) in rgba
+            .iter_mut()
+            .enumerate()
+            .take(width * 10 * BYTES_PER_PIXEL)
+        {
+            *x = (k / 7) as u8;
+        }
+        let texts = [
+            ("Title", String::from("Temperature")),
+            ("Comment", String::from("地上 2 m")),
+        ];
+        let chunks = chunks(&png(&rgba, width, height, &texts).unwrap());
+
+        let names: Vec<&[u8]> = chunks.iter().map(|(x, _)| &x[..]).collect();
+        assert_eq!(names, [&b"IHDR"[..], b"tEXt", b"iTXt", b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1[..8], [0, 0, 0, 37, 0, 0, 0, 23]);
+        assert_eq!(chunks[1].1, b"Title\0Temperature");
+        assert_eq!(chunks[2].1, "Comment\0\0\0\0\0地上 2 m".as_bytes());
+
+        let filtered = inflate(&chunks[3].1);
+        assert_eq!(filtered.len(), (width * BYTES_PER_PIXEL + 1) * height);
+        assert_eq!(unfilter(&filtered, width), rgba);
+    }
+
+    #[test]
+    fn invalid_size() {
+        assert!(png(&[0; 12], 2, 2, &[]).is_err());
+        assert!(png(&[], 0, 0, &[]).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! Point export implementation
+//! 格子点ごとに経度、緯度、値を1行にした CSV または NDJSON を書き出す。
+
+use std::str::FromStr;
+
+use crate::grib2::section::section3_template::TemplateNumber as Section3TemplateNumber;
+use crate::grib2::section::Bounds;
+
+use super::PointFormat;
+
+const MICRO: f64 = 1e-6;
+const FULL_CIRCLE: isize = 360_000_000; // 10^-6 度
+
+impl FromStr for PointFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(PointFormat::Csv),
+            "ndjson" => Ok(PointFormat::Ndjson),
+            _ => Err(format!("Unknown point format: {}", s)),
+        }
+    }
+}
+
+// 緯度 [度] が範囲 [10^-6 度] に含まれれば true を返す。
+fn contains_latitude(viewport: &Bounds, lat: f64) -> bool {
+    let lat = (lat / MICRO).round() as isize;
+    viewport.bottom <= lat && lat <= viewport.top
+}
+
+// 経度 [度] が範囲 [10^-6 度] に含まれれば true を返す。left > right なら日付変更線をまたぐ範囲とする。
+fn contains_longitude(viewport: &Bounds, lon: f64) -> bool {
+    if FULL_CIRCLE <= viewport.right - viewport.left {
+        return true;
+    }
+    let lon = (lon / MICRO).round() as isize;
+    let width = (viewport.right - viewport.left).rem_euclid(FULL_CIRCLE);
+    (lon - viewport.left).rem_euclid(FULL_CIRCLE) <= width
+}
+
+// 10^-6 度に丸めて表す。10^-6 は2進数で正確に表せないため、10^6 で割って戻す。
+fn degrees(value: f64) -> f64 {
+    (value * 1e6).round() / 1e6
+}
+
+// CSV の1項目。区切り文字、引用符、改行を含めば引用符で囲む。
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+// 範囲に含まれる格子点を stride 格子おきに1行ずつ書き出す。
+// skip_missing が true なら欠測の格子点を除き、false なら値を空（NDJSON では null）にする。
+// metadata は全ての行に加える項目の名前と値
+pub(crate) fn points(
+    geometry: &Section3TemplateNumber,
+    values: &[f32],
+    viewport: &Bounds,
+    stride: usize,
+    skip_missing: bool,
+    metadata: &[(String, String)],
+    format: PointFormat,
+) -> String {
+    let stride = stride.max(1);
+    let mut dest = String::new();
+    if format == PointFormat::Csv {
+        let mut header = vec![
+            String::from("lon"),
+            String::from("lat"),
+            String::from("value"),
+        ];
+        header.extend(metadata.iter().map(|(name, _)| csv_field(name)));
+        dest += &header.join(",");
+        dest.push('\n');
+    }
+    let csv_metadata: String = metadata
+        .iter()
+        .map(|(_, value)| format!(",{}", csv_field(value)))
+        .collect();
+    let json_metadata: String = metadata
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                ",{}:{}",
+                serde_json::to_string(name).unwrap(),
+                serde_json::to_string(value).unwrap()
+            )
+        })
+        .collect();
+
+    let ni = geometry.ni();
+    let mut rows: Vec<usize> = (0..geometry.nj()).step_by(stride).collect();
+    let mut columns: Vec<usize> = (0..ni).step_by(stride).collect();
+    // 経緯度格子では緯度が行だけ、経度が列だけで決まるので、範囲に含まれる行と列を先に選ぶ。
+    if let Section3TemplateNumber::T0(_) = geometry {
+        rows.retain(|j| contains_latitude(viewport, geometry.ij_to_lonlat(0.0, *j as f64).1));
+        columns.retain(|i| contains_longitude(viewport, geometry.ij_to_lonlat(*i as f64, 0.0).0));
+    }
+
+    for j in rows.into_iter() {
+        for i in columns.iter().copied() {
+            let value = match values.get(j * ni + i) {
+                Some(value) if !value.is_nan() => Some(*value),
+                _ if skip_missing => continue,
+                _ => None,
+            };
+            let (lon, lat) = geometry.ij_to_lonlat(i as f64, j as f64);
+            if !contains_latitude(viewport, lat) || !contains_longitude(viewport, lon) {
+                continue;
+            }
+
+            let (lon, lat) = (degrees(lon), degrees(lat));
+            match format {
+                PointFormat::Csv => {
+                    let value = value.map_or_else(String::new, |x| x.to_string());
+                    dest += &format!("{},{},{}{}\n", lon, lat, value, csv_metadata);
+                }
+                PointFormat::Ndjson => {
+                    let value = value.map_or_else(|| String::from("null"), |x| x.to_string());
+                    dest += &format!(
+                        "{{\"lon\":{},\"lat\":{},\"value\":{}{}}}\n",
+                        lon, lat, value, json_metadata
+                    );
+                }
+            }
+        }
+    }
+    dest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grib2::section::Section3;
+    use crate::grib2::utils_impl::tests::latlon_grid;
+
+    fn bounds(left: f64, bottom: f64, right: f64, top: f64) -> Bounds {
+        let micro = |x: f64| (x / MICRO).round() as isize;
+        Bounds {
+            left: micro(left),
+            bottom: micro(bottom),
+            right: micro(right),
+            top: micro(top),
+        }
+    }
+
+    // 北緯36度、東経139度から 1 度間隔の 3 x 2 の格子。(2, 0) は欠測
+    fn export(viewport: &Bounds, stride: usize, skip_missing: bool, format: PointFormat) -> String {
+        let buf = latlon_grid(3, 2, 36.0, 139.0, 1.0, 1.0);
+        let geometry = Section3::create(&buf).template().unwrap();
+        let values = [1.0, 2.0, f32::NAN, 4.0, 5.5, 6.0];
+        let metadata = [(String::from("GRIB_UNIT"), String::from("K"))];
+        points(
+            &geometry,
+            &values,
+            viewport,
+            stride,
+            skip_missing,
+            &metadata,
+            format,
+        )
+    }
+
+    #[test]
+    fn csv() {
+        let all = bounds(138.0, 34.0, 142.0, 37.0);
+        assert_eq!(
+            export(&all, 1, false, PointFormat::Csv),
+            "lon,lat,value,GRIB_UNIT\n\
+             139,36,1,K\n140,36,2,K\n141,36,,K\n139,35,4,K\n140,35,5.5,K\n141,35,6,K\n"
+        );
+        assert_eq!(
+            export(&all, 1, true, PointFormat::Csv),
+            "lon,lat,value,GRIB_UNIT\n139,36,1,K\n140,36,2,K\n139,35,4,K\n140,35,5.5,K\n141,35,6,K\n"
+        );
+    }
+
+    #[test]
+    fn ndjson() {
+        let viewport = bounds(140.5, 35.5, 141.0, 36.0);
+        assert_eq!(
+            export(&viewport, 1, false, PointFormat::Ndjson),
+            "{\"lon\":141,\"lat\":36,\"value\":null,\"GRIB_UNIT\":\"K\"}\n"
+        );
+        assert_eq!(export(&viewport, 1, true, PointFormat::Ndjson), "");
+    }
+
+    // 範囲の端の格子点を含め、stride 格子おきに間引く。
+    #[test]
+    fn bbox_and_stride() {
+        let viewport = bounds(140.0, 35.0, 141.0, 35.5);
+        assert_eq!(
+            export(&viewport, 1, false, PointFormat::Csv),
+            "lon,lat,value,GRIB_UNIT\n140,35,5.5,K\n141,35,6,K\n"
+        );
+        let all = bounds(138.0, 34.0, 142.0, 37.0);
+        assert_eq!(
+            export(&all, 2, false, PointFormat::Csv),
+            "lon,lat,value,GRIB_UNIT\n139,36,1,K\n141,36,,K\n"
+        );
+        assert_eq!(
+            export(&all, 0, true, PointFormat::Csv).lines().count(),
+            1 + 5
+        );
+    }
+
+    // 西端が東端より大きい範囲は日付変更線をまたぐ。
+    #[test]
+    fn antimeridian() {
+        let buf = latlon_grid(4, 1, 0.0, 170.0, 10.0, 10.0);
+        let geometry = Section3::create(&buf).template().unwrap();
+        let csv = points(
+            &geometry,
+            &[1.0, 2.0, 3.0, 4.0],
+            &bounds(175.0, -1.0, -165.0, 1.0),
+            1,
+            false,
+            &[],
+            PointFormat::Csv,
+        );
+        assert_eq!(csv, "lon,lat,value\n-180,0,2\n-170,0,3\n");
+    }
+
+    // 区切り文字や引用符を含む項目は引用符で囲む。
+    #[test]
+    fn quoting() {
+        assert_eq!(csv_field("2 m"), "2 m");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert!("tsv".parse::<PointFormat>().is_err());
+    }
+}
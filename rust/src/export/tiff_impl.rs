@@ -0,0 +1,323 @@
+//! TIFF implementation
+//! リトルエンディアンの TIFF を書き出す。全ての IFD をファイルの先頭にまとめ、画像データはその後ろに置く。
+
+// タグの値
+#[derive(Clone, Debug)]
+pub(crate) enum TagValue {
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Double(Vec<f64>),
+    Ascii(String),
+}
+
+// IFD のタグ
+#[derive(Clone, Debug)]
+pub(crate) struct Tag {
+    pub(crate) code: u16,
+    pub(crate) value: TagValue,
+}
+
+// 1つの画像。blocks はストリップまたはタイルのデータで、位置とバイト数を offsets_tag, byte_counts_tag に書き込む。
+#[derive(Clone, Debug)]
+pub(crate) struct Ifd {
+    pub(crate) tags: Vec<Tag>,
+    pub(crate) blocks: Vec<Vec<u8>>,
+    pub(crate) offsets_tag: u16,
+    pub(crate) byte_counts_tag: u16,
+}
+
+impl TagValue {
+    // (型, 値の数, バイト列)
+    fn encode(&self) -> (u16, u32, Vec<u8>) {
+        match self {
+            TagValue::Short(x) => (
+                3,
+                x.len() as u32,
+                x.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            ),
+            TagValue::Long(x) => (
+                4,
+                x.len() as u32,
+                x.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            ),
+            TagValue::Double(x) => (
+                12,
+                x.len() as u32,
+                x.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            ),
+            TagValue::Ascii(x) => {
+                let mut bytes: Vec<u8> = x.bytes().collect();
+                bytes.push(0);
+                (2, bytes.len() as u32, bytes)
+            }
+        }
+    }
+}
+
+impl Tag {
+    pub(crate) fn short(code: u16, value: u16) -> Self {
+        Self {
+            code,
+            value: TagValue::Short(vec![value]),
+        }
+    }
+
+    pub(crate) fn long(code: u16, value: u32) -> Self {
+        Self {
+            code,
+            value: TagValue::Long(vec![value]),
+        }
+    }
+}
+
+// ワード境界に合わせる。
+fn align(offset: usize) -> usize {
+    offset + offset % 2
+}
+
+impl Ifd {
+    // 位置とバイト数を加えたタグを番号の順に並べる。
+    fn sorted_tags(&self, offsets: Vec<u32>) -> Vec<Tag> {
+        let byte_counts = self.blocks.iter().map(|x| x.len() as u32).collect();
+        let mut tags = self.tags.clone();
+        tags.push(Tag {
+            code: self.offsets_tag,
+            value: TagValue::Long(offsets),
+        });
+        tags.push(Tag {
+            code: self.byte_counts_tag,
+            value: TagValue::Long(byte_counts),
+        });
+        tags.sort_by_key(|x| x.code);
+        tags
+    }
+
+    // IFD と4バイトに収まらない値の合計バイト数
+    fn size(&self) -> usize {
+        let tags = self.sorted_tags(vec![0; self.blocks.len()]);
+        let values: usize = tags
+            .iter()
+            .map(|x| x.value.encode().2.len())
+            .filter(|x| 4 < *x)
+            .map(align)
+            .sum();
+        2 + tags.len() * 12 + 4 + values
+    }
+
+    // IFD を書き出す。next は次の IFD の位置（なければ 0）
+    fn write(&self, dest: &mut Vec<u8>, offsets: Vec<u32>, next: u32) {
+        let tags = self.sorted_tags(offsets);
+        let start = dest.len();
+        let mut overflow_offset = start + 2 + tags.len() * 12 + 4;
+        let mut overflow = Vec::new();
+
+        dest.extend(&(tags.len() as u16).to_le_bytes());
+        for tag in tags.iter() {
+            let (field_type, count, bytes) = tag.value.encode();
+            dest.extend(&tag.code.to_le_bytes());
+            dest.extend(&field_type.to_le_bytes());
+            dest.extend(&count.to_le_bytes());
+            if bytes.len() <= 4 {
+                let mut value = bytes.clone();
+                value.resize(4, 0);
+                dest.extend(value);
+            } else {
+                dest.extend(&(overflow_offset as u32).to_le_bytes());
+                overflow_offset += align(bytes.len());
+                overflow.extend(&bytes);
+                if bytes.len() % 2 == 1 {
+                    overflow.push(0);
+                }
+            }
+        }
+        dest.extend(&next.to_le_bytes());
+        dest.extend(overflow);
+    }
+}
+
+// TIFF を書き出す。画像データは IFD の逆順（最後の IFD のデータが先頭）に並べる。
+pub(crate) fn tiff(ifds: &[Ifd]) -> Vec<u8> {
+    const HEADER_SIZE: usize = 8;
+
+    // IFD の位置
+    let mut ifd_offsets = Vec::with_capacity(ifds.len());
+    let mut offset = HEADER_SIZE;
+    for ifd in ifds {
+        ifd_offsets.push(offset);
+        offset = align(offset + ifd.size());
+    }
+
+    // 画像データの位置
+    let data_offset = offset;
+    let mut block_offsets = vec![Vec::new(); ifds.len()];
+    for (k, ifd) in ifds.iter().enumerate().rev() {
+        for block in ifd.blocks.iter() {
+            block_offsets[k].push(offset as u32);
+            offset += block.len();
+        }
+    }
+
+    let mut dest = Vec::with_capacity(offset);
+    dest.extend(b"II");
+    dest.extend(&42u16.to_le_bytes());
+    dest.extend(&(HEADER_SIZE as u32).to_le_bytes());
+    for (k, ifd) in ifds.iter().enumerate() {
+        let next = ifd_offsets.get(k + 1).copied().unwrap_or(0) as u32;
+        dest.resize(ifd_offsets[k], 0);
+        ifd.write(&mut dest, block_offsets[k].clone(), next);
+    }
+    dest.resize(data_offset, 0);
+    for ifd in ifds.iter().rev() {
+        for block in ifd.blocks.iter() {
+            dest.extend(block);
+        }
+    }
+    dest
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn u16_le(src: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes([src[offset], src[offset + 1]])
+    }
+
+    fn u32_le(src: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes([
+            src[offset],
+            src[offset + 1],
+            src[offset + 2],
+            src[offset + 3],
+        ])
+    }
+
+    // 検証用に読み取った IFD。タグは書かれた順に並ぶ。
+    pub(crate) struct ParsedIfd {
+        pub(crate) offset: usize,
+        pub(crate) tags: Vec<(u16, TagValue)>,
+    }
+
+    impl ParsedIfd {
+        pub(crate) fn get(&self, code: u16) -> Option<&TagValue> {
+            self.tags.iter().find(|(x, _)| *x == code).map(|(_, x)| x)
+        }
+
+        // SHORT または LONG の値
+        pub(crate) fn integers(&self, code: u16) -> Vec<u32> {
+            match self.get(code) {
+                Some(TagValue::Short(x)) => x.iter().map(|v| *v as u32).collect(),
+                Some(TagValue::Long(x)) => x.clone(),
+                _ => Vec::new(),
+            }
+        }
+
+        // offsets_tag と byte_counts_tag が指すデータ
+        pub(crate) fn blocks<'a>(
+            &self,
+            src: &'a [u8],
+            offsets_tag: u16,
+            byte_counts_tag: u16,
+        ) -> Vec<&'a [u8]> {
+            self.integers(offsets_tag)
+                .iter()
+                .zip(self.integers(byte_counts_tag))
+                .map(|(offset, count)| &src[*offset as usize..(offset + count) as usize])
+                .collect()
+        }
+    }
+
+    // リトルエンディアンの TIFF の IFD を先頭から順に読む。
+    pub(crate) fn parse(src: &[u8]) -> Vec<ParsedIfd> {
+        assert_eq!(&src[..4], b"II*\0");
+        let mut ifds = Vec::new();
+        let mut offset = u32_le(src, 4) as usize;
+        while offset != 0 {
+            assert_eq!(offset % 2, 0);
+            let count = u16_le(src, offset) as usize;
+            let mut tags = Vec::new();
+            for k in 0..count {
+                let entry = offset + 2 + k * 12;
+                let (code, field_type) = (u16_le(src, entry), u16_le(src, entry + 2));
+                let n = u32_le(src, entry + 4) as usize;
+                let size = match field_type {
+                    2 => 1,
+                    3 => 2,
+                    4 => 4,
+                    12 => 8,
+                    x => panic!("Unexpected type: {}", x),
+                };
+                let start = if n * size <= 4 {
+                    entry + 8
+                } else {
+                    u32_le(src, entry + 8) as usize
+                };
+                let bytes = &src[start..start + n * size];
+                let value = match field_type {
+                    2 => {
+                        assert_eq!(bytes[n - 1], 0);
+                        TagValue::Ascii(String::from_utf8(bytes[..n - 1].to_vec()).unwrap())
+                    }
+                    3 => TagValue::Short((0..n).map(|i| u16_le(bytes, i * 2)).collect()),
+                    4 => TagValue::Long((0..n).map(|i| u32_le(bytes, i * 4)).collect()),
+                    _ => TagValue::Double(
+                        bytes
+                            .chunks_exact(8)
+                            .map(|x| {
+                                let mut b = [0; 8];
+                                b.copy_from_slice(x);
+                                f64::from_le_bytes(b)
+                            })
+                            .collect(),
+                    ),
+                };
+                tags.push((code, value));
+            }
+            ifds.push(ParsedIfd { offset, tags });
+            offset = u32_le(src, offset + 2 + count * 12) as usize;
+        }
+        ifds
+    }
+
+    #[test]
+    fn layout() {
+        let ifd = |blocks: Vec<Vec<u8>>, text: &str| Ifd {
+            tags: vec![
+                Tag {
+                    code: 270,
+                    value: TagValue::Ascii(String::from(text)),
+                },
+                Tag::short(256, 3),
+                Tag {
+                    code: 33550,
+                    value: TagValue::Double(vec![0.5, -0.25, 0.0]),
+                },
+            ],
+            blocks,
+            offsets_tag: 273,
+            byte_counts_tag: 279,
+        };
+        let src = tiff(&[
+            ifd(vec![vec![1, 2, 3], vec![4, 5]], "first"),
+            ifd(vec![vec![6; 7]], "second image"),
+        ]);
+        let ifds = parse(&src);
+        assert_eq!(ifds.len(), 2);
+
+        // タグは番号の順に並び、位置とバイト数が加わる。
+        let codes: Vec<u16> = ifds[0].tags.iter().map(|(x, _)| *x).collect();
+        assert_eq!(codes, [256, 270, 273, 279, 33550]);
+        assert!(matches!(ifds[1].get(270), Some(TagValue::Ascii(x)) if x == "second image"));
+        assert!(matches!(ifds[0].get(33550), Some(TagValue::Double(x)) if x == &[0.5, -0.25, 0.0]));
+
+        // IFD が全て先頭にあり、データは最後の IFD のものから並ぶ。
+        let first = ifds[0].blocks(&src, 273, 279);
+        let second = ifds[1].blocks(&src, 273, 279);
+        assert_eq!(first, [&[1, 2, 3][..], &[4, 5]]);
+        assert_eq!(second, [&[6; 7][..]]);
+        let data_start = ifds[1].integers(273)[0] as usize;
+        assert!(ifds.iter().all(|x| x.offset < data_start));
+        assert!(data_start < ifds[0].integers(273)[0] as usize);
+        assert_eq!(src.len(), ifds[0].integers(273)[1] as usize + 2);
+    }
+}
@@ -0,0 +1,327 @@
+//! Zarr implementation
+//! データセットを Zarr v2 のディレクトリストアのファイルにする。
+//! xarray の規約に従って次元名を _ARRAY_DIMENSIONS 属性に記し、全メタデータを .zmetadata にまとめる。
+
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+
+use super::netcdf_impl::NetCdf;
+use super::netcdf_impl::Values;
+use super::zlib_impl::zlib;
+
+const CHUNK_SIZE: usize = 512; // 水平方向のチャンクの一辺の格子点数の上限
+
+fn json_values(values: &Values) -> Value {
+    let mut array: Vec<Value> = match values {
+        Values::Char(x) => return Value::String(x.clone()),
+        Values::Int(x) => x.iter().map(|v| json!(v)).collect(),
+        Values::Float(x) => x.iter().map(|v| json!(v)).collect(),
+        Values::Double(x) => x.iter().map(|v| json!(v)).collect(),
+    };
+    match array.len() {
+        1 => array.remove(0),
+        _ => Value::Array(array),
+    }
+}
+
+// (dtype, 値の要素ごとのバイト列, 欠損値)
+fn encode(values: &Values, fill_value: Option<&Values>) -> (&'static str, Vec<u8>, Value) {
+    let fill = fill_value.map_or(Value::Null, json_values);
+    match values {
+        Values::Char(x) => ("|u1", x.as_bytes().to_vec(), fill),
+        Values::Int(x) => (
+            "<i4",
+            x.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            fill,
+        ),
+        Values::Float(x) => (
+            "<f4",
+            x.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            fill,
+        ),
+        Values::Double(x) => (
+            "<f8",
+            x.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            fill,
+        ),
+    }
+}
+
+// 欠損値の要素のバイト列。欠損値がなければ 0
+fn fill_bytes(values: &Values, fill_value: Option<&Values>) -> Vec<u8> {
+    match (values, fill_value) {
+        (Values::Char(_), _) => vec![0],
+        (Values::Int(_), Some(Values::Int(x))) => x[0].to_le_bytes().to_vec(),
+        (Values::Float(_), Some(Values::Float(x))) => x[0].to_le_bytes().to_vec(),
+        (Values::Double(_), Some(Values::Double(x))) => x[0].to_le_bytes().to_vec(),
+        (Values::Double(_), _) => vec![0; 8],
+        _ => vec![0; 4],
+    }
+}
+
+// 次元の長さからチャンクの大きさを決める。2次元以上なら最後の2次元（y, x）を分け、それより前は1ずつにする。
+fn chunk_shape(shape: &[usize]) -> Vec<usize> {
+    let n = shape.len();
+    shape
+        .iter()
+        .enumerate()
+        .map(|(k, length)| match n - k {
+            1 | 2 if 2 <= n => (*length).clamp(1, CHUNK_SIZE),
+            1 => (*length).max(1),
+            _ => 1,
+        })
+        .collect()
+}
+
+// 多次元の位置を1つ進める。最後まで進めば false を返す。
+fn next(position: &mut [usize], counts: &[usize]) -> bool {
+    for k in (0..position.len()).rev() {
+        position[k] += 1;
+        if position[k] < counts[k] {
+            return true;
+        }
+        position[k] = 0;
+    }
+    false
+}
+
+// C 順の値をチャンクに分けて圧縮する。はみ出した部分は欠損値で埋める。
+fn chunks(data: &[u8], fill: &[u8], shape: &[usize], chunk: &[usize]) -> Vec<(String, Vec<u8>)> {
+    let item = fill.len();
+    if shape.is_empty() {
+        return vec![(String::from("0"), zlib(data))];
+    }
+
+    let n = shape.len();
+    let counts: Vec<usize> = shape
+        .iter()
+        .zip(chunk.iter())
+        .map(|(s, c)| (*s).max(1).div_ceil(*c))
+        .collect();
+    let mut strides = vec![1; n];
+    for k in (0..n - 1).rev() {
+        strides[k] = strides[k + 1] * shape[k + 1];
+    }
+
+    let mut dest = Vec::new();
+    let mut index = vec![0; n];
+    loop {
+        // チャンクの中を行（最後の次元）ごとに写す。
+        let mut buffer = Vec::with_capacity(chunk.iter().product::<usize>() * item);
+        let rows: Vec<usize> = chunk[..n - 1].to_vec();
+        let mut row = vec![0; n - 1];
+        loop {
+            let mut offset = Some(0);
+            for k in 0..n - 1 {
+                let p = index[k] * chunk[k] + row[k];
+                offset = match offset {
+                    Some(o) if p < shape[k] => Some(o + p * strides[k]),
+                    _ => None,
+                };
+            }
+            let start = index[n - 1] * chunk[n - 1];
+            let length = chunk[n - 1].min(shape[n - 1].saturating_sub(start));
+            if let Some(offset) = offset {
+                let from = (offset + start) * item;
+                buffer.extend(&data[from..from + length * item]);
+            } else {
+                (0..length).for_each(|_| buffer.extend(fill));
+            }
+            (length..chunk[n - 1]).for_each(|_| buffer.extend(fill));
+            if !next(&mut row, &rows) {
+                break;
+            }
+        }
+
+        let key: Vec<String> = index.iter().map(|x| x.to_string()).collect();
+        dest.push((key.join("."), zlib(&buffer)));
+        if !next(&mut index, &counts) {
+            break;
+        }
+    }
+    dest
+}
+
+fn json_bytes(value: &Value) -> Vec<u8> {
+    serde_json::to_vec_pretty(value).unwrap()
+}
+
+// データセットを Zarr v2 のストアのファイル（ストアからの相対パスと内容）にする。
+pub(crate) fn zarr(dataset: &NetCdf) -> Vec<(String, Vec<u8>)> {
+    let mut metadata = Map::new();
+    let mut files = Vec::new();
+
+    let zgroup = json!({ "zarr_format": 2 });
+    let zattrs: Map<String, Value> = dataset
+        .attributes
+        .iter()
+        .map(|(name, values)| (name.clone(), json_values(values)))
+        .collect();
+    metadata.insert(String::from(".zgroup"), zgroup.clone());
+    metadata.insert(String::from(".zattrs"), Value::Object(zattrs.clone()));
+    files.push((String::from(".zgroup"), json_bytes(&zgroup)));
+    files.push((String::from(".zattrs"), json_bytes(&Value::Object(zattrs))));
+
+    for variable in dataset.variables.iter() {
+        let shape: Vec<usize> = variable
+            .dimensions
+            .iter()
+            .map(|x| dataset.dimensions[*x].length)
+            .collect();
+        let chunk = chunk_shape(&shape);
+        let fill_value = variable
+            .attributes
+            .iter()
+            .find(|(name, _)| name == "_FillValue")
+            .map(|(_, values)| values);
+        let (dtype, data, fill) = encode(&variable.values, fill_value);
+
+        let zarray = json!({
+            "zarr_format": 2,
+            "shape": shape,
+            "chunks": chunk,
+            "dtype": dtype,
+            // zlib_impl の圧縮は zlib の圧縮レベルに対応しないため level は記さない。
+            "compressor": { "id": "zlib" },
+            "fill_value": fill,
+            "order": "C",
+            "filters": null,
+            "dimension_separator": ".",
+        });
+        // 欠損値は .zarray の fill_value に記す。
+        let mut zattrs: Map<String, Value> = variable
+            .attributes
+            .iter()
+            .filter(|(name, _)| name != "_FillValue")
+            .map(|(name, values)| (name.clone(), json_values(values)))
+            .collect();
+        let dimensions: Vec<&str> = variable
+            .dimensions
+            .iter()
+            .map(|x| dataset.dimensions[*x].name.as_str())
+            .collect();
+        zattrs.insert(String::from("_ARRAY_DIMENSIONS"), json!(dimensions));
+
+        let zarray_path = format!("{}/.zarray", variable.name);
+        let zattrs_path = format!("{}/.zattrs", variable.name);
+        files.push((zarray_path.clone(), json_bytes(&zarray)));
+        files.push((
+            zattrs_path.clone(),
+            json_bytes(&Value::Object(zattrs.clone())),
+        ));
+        metadata.insert(zarray_path, zarray);
+        metadata.insert(zattrs_path, Value::Object(zattrs));
+
+        let fill = fill_bytes(&variable.values, fill_value);
+        for (key, chunk) in chunks(&data, &fill, &shape, &chunk) {
+            files.push((format!("{}/{}", variable.name, key), chunk));
+        }
+    }
+
+    let zmetadata = json!({
+        "metadata": Value::Object(metadata),
+        "zarr_consolidated_format": 1,
+    });
+    files.push((String::from(".zmetadata"), json_bytes(&zmetadata)));
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::netcdf_impl::Dimension;
+    use crate::export::netcdf_impl::Variable;
+    use crate::export::zlib_impl::tests::inflate;
+
+    fn dataset(shape: (usize, usize, usize)) -> NetCdf {
+        let (t, y, x) = shape;
+        NetCdf {
+            dimensions: [("time", t), ("y", y), ("x", x)]
+                .iter()
+                .map(|(name, length)| Dimension {
+                    name: String::from(*name),
+                    length: *length,
+                })
+                .collect(),
+            attributes: vec![(String::from("title"), Values::Char(String::from("test")))],
+            variables: vec![
+                Variable {
+                    name: String::from("time"),
+                    dimensions: vec![0],
+                    attributes: Vec::new(),
+                    values: Values::Double((0..t).map(|k| k as f64 * 3600.0).collect()),
+                },
+                Variable {
+                    name: String::from("t"),
+                    dimensions: vec![0, 1, 2],
+                    attributes: vec![
+                        (String::from("units"), Values::Char(String::from("K"))),
+                        (String::from("_FillValue"), Values::Float(vec![-9.0])),
+                    ],
+                    values: Values::Float((0..t * y * x).map(|k| k as f32).collect()),
+                },
+            ],
+        }
+    }
+
+    fn file<'a>(files: &'a [(String, Vec<u8>)], path: &str) -> &'a [u8] {
+        &files.iter().find(|(x, _)| x == path).unwrap().1
+    }
+
+    #[test]
+    fn metadata() {
+        let files = zarr(&dataset((2, 700, 600)));
+        let json = |path: &str| -> Value { serde_json::from_slice(file(&files, path)).unwrap() };
+
+        assert_eq!(json(".zgroup"), json!({ "zarr_format": 2 }));
+        assert_eq!(json(".zattrs"), json!({ "title": "test" }));
+        let zarray = json("t/.zarray");
+        assert_eq!(zarray["shape"], json!([2, 700, 600]));
+        assert_eq!(zarray["chunks"], json!([1, 512, 512]));
+        assert_eq!(zarray["dtype"], "<f4");
+        assert_eq!(zarray["fill_value"], json!(-9.0));
+        assert_eq!(zarray["compressor"], json!({ "id": "zlib" }));
+        let zattrs = json("t/.zattrs");
+        assert_eq!(zattrs["_ARRAY_DIMENSIONS"], json!(["time", "y", "x"]));
+        assert!(zattrs.get("_FillValue").is_none());
+
+        // .zmetadata は各 .zarray, .zattrs と同じ内容をまとめる。
+        let zmetadata = json(".zmetadata");
+        assert_eq!(zmetadata["metadata"]["t/.zarray"], zarray);
+        assert_eq!(zmetadata["metadata"]["time/.zattrs"], json("time/.zattrs"));
+    }
+
+    // チャンクを展開して元の配列に戻す。はみ出した部分は欠損値で埋まる。
+    #[test]
+    fn chunks_round_trip() {
+        let (t, y, x) = (2, 700, 600);
+        let files = zarr(&dataset((t, y, x)));
+        let mut dest = vec![f32::NAN; t * y * x];
+        let mut count = 0;
+        for (path, content) in files.iter() {
+            let key = match path.strip_prefix("t/") {
+                Some(key) if !key.starts_with('.') => key,
+                _ => continue,
+            };
+            let index: Vec<usize> = key.split('.').map(|x| x.parse().unwrap()).collect();
+            let raw = inflate(content);
+            assert_eq!(raw.len(), 512 * 512 * 4);
+            for (n, bytes) in raw.chunks_exact(4).enumerate() {
+                let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                let (j, i) = (index[1] * 512 + n / 512, index[2] * 512 + n % 512);
+                if j < y && i < x {
+                    dest[(index[0] * y + j) * x + i] = value;
+                } else {
+                    assert_eq!(value, -9.0);
+                }
+            }
+            count += 1;
+        }
+        assert_eq!(count, 2 * 2 * 2);
+        assert!(dest.iter().enumerate().all(|(k, v)| *v == k as f32));
+
+        let time = inflate(file(&files, "time/0"));
+        assert_eq!(time[8..16], 3600f64.to_le_bytes());
+    }
+}
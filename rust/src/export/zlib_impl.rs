@@ -0,0 +1,440 @@
+//! zlib (RFC 1950) / deflate (RFC 1951) implementation
+//! 固定ハフマン符号と LZ77 による圧縮だけを行う。
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64; // 一致を探す候補の数の上限
+const HASH_BITS: usize = 15;
+
+// 長さ符号 257-285 の基準値と拡張ビット数
+const LENGTH_BASES: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+// 距離符号 0-29 の基準値と拡張ビット数
+const DISTANCE_BASES: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+// CRC-32 (ISO 3309)
+pub(crate) fn crc32(src: &[u8]) -> u32 {
+    update_crc32(0, src)
+}
+
+// 続きのデータで CRC-32 を更新する。
+pub(crate) fn update_crc32(crc: u32, src: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in src {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// Adler-32
+fn adler32(src: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in src.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+// 下位ビットから詰めて書き出す。
+struct BitWriter {
+    buf: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= (value as u64) << self.count;
+        self.count += count;
+        while 8 <= self.count {
+            self.buf.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // ハフマン符号は上位ビットから書き出す。
+    fn write_code(&mut self, code: u32, count: u32) {
+        self.write(code.reverse_bits() >> (32 - count), count);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if 0 < self.count {
+            self.buf.push(self.bits as u8);
+        }
+        self.buf
+    }
+}
+
+// 固定ハフマン符号でリテラルまたは長さの記号を書き出す。
+fn write_symbol(writer: &mut BitWriter, symbol: usize) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol as u32, 8),
+        144..=255 => writer.write_code(0x190 + (symbol - 144) as u32, 9),
+        256..=279 => writer.write_code((symbol - 256) as u32, 7),
+        _ => writer.write_code(0xC0 + (symbol - 280) as u32, 8),
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let i = LENGTH_BASES.partition_point(|x| *x <= length) - 1;
+    write_symbol(writer, 257 + i);
+    writer.write((length - LENGTH_BASES[i]) as u32, LENGTH_EXTRA_BITS[i]);
+
+    let i = DISTANCE_BASES.partition_point(|x| *x <= distance) - 1;
+    writer.write_code(i as u32, 5);
+    writer.write(
+        (distance - DISTANCE_BASES[i]) as u32,
+        DISTANCE_EXTRA_BITS[i],
+    );
+}
+
+fn hash(src: &[u8]) -> usize {
+    let x = (src[0] as usize) << 16 | (src[1] as usize) << 8 | src[2] as usize;
+    (x.wrapping_mul(2_654_435_761) >> 8) & ((1 << HASH_BITS) - 1)
+}
+
+// 位置 i をハッシュの連鎖に加える。
+fn insert(src: &[u8], i: usize, head: &mut [usize], prev: &mut [usize]) {
+    if i + MIN_MATCH <= src.len() {
+        let h = hash(&src[i..]);
+        prev[i % WINDOW_SIZE] = head[h];
+        head[h] = i + 1;
+    }
+}
+
+// deflate で圧縮する。
+pub(crate) fn deflate(src: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+    writer.write(1, 1); // BFINAL
+    writer.write(1, 2); // BTYPE = 固定ハフマン符号
+
+    // head[h] はハッシュ h の最新の位置 + 1、prev[i % WINDOW_SIZE] は同じハッシュの1つ前の位置 + 1
+    let mut head = vec![0usize; 1 << HASH_BITS];
+    let mut prev = vec![0usize; WINDOW_SIZE];
+
+    let mut i = 0;
+    while i < src.len() {
+        // 最も長い一致を探す。
+        let mut best = (0, 0);
+        if i + MIN_MATCH <= src.len() {
+            let limit = MAX_MATCH.min(src.len() - i);
+            let mut candidate = head[hash(&src[i..])];
+            let mut chain = 0;
+            while 0 < candidate && chain < MAX_CHAIN {
+                let j = candidate - 1;
+                if WINDOW_SIZE <= i - j {
+                    break;
+                }
+                let length = src[j..]
+                    .iter()
+                    .zip(&src[i..i + limit])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if best.0 < length {
+                    best = (length, i - j);
+                    if length == limit {
+                        break;
+                    }
+                }
+                candidate = prev[j % WINDOW_SIZE];
+                chain += 1;
+            }
+        }
+
+        if MIN_MATCH <= best.0 {
+            write_match(&mut writer, best.0, best.1);
+            for k in i..i + best.0 {
+                insert(src, k, &mut head, &mut prev);
+            }
+            i += best.0;
+        } else {
+            write_symbol(&mut writer, src[i] as usize);
+            insert(src, i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+    write_symbol(&mut writer, 256); // ブロックの終わり
+    writer.finish()
+}
+
+// zlib 形式で圧縮する。
+pub(crate) fn zlib(src: &[u8]) -> Vec<u8> {
+    // CMF = deflate, 32K ウィンドウ、FLG = 既定の圧縮レベル（CMF * 256 + FLG は 31 の倍数）
+    let mut dest = vec![0x78, 0x9C];
+    dest.extend(deflate(src));
+    dest.extend(&adler32(src).to_be_bytes());
+    dest
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // 下位ビットから読む。
+    struct BitReader<'a> {
+        src: &'a [u8],
+        position: usize, // ビット単位
+    }
+
+    impl<'a> BitReader<'a> {
+        fn bit(&mut self) -> u32 {
+            let byte = self.src[self.position / 8];
+            let bit = (byte >> (self.position % 8)) & 1;
+            self.position += 1;
+            bit as u32
+        }
+
+        fn bits(&mut self, count: u32) -> usize {
+            (0..count).fold(0, |x, k| x | (self.bit() as usize) << k)
+        }
+
+        fn align(&mut self) {
+            self.position = self.position.div_ceil(8) * 8;
+        }
+    }
+
+    // 符号長の並びから作ったハフマン符号（正準符号）
+    struct Huffman {
+        counts: Vec<usize>,  // 符号長ごとの符号の数
+        symbols: Vec<usize>, // 符号の順に並べた記号
+    }
+
+    impl Huffman {
+        fn new(lengths: &[usize]) -> Self {
+            let mut counts = vec![0; 16];
+            for length in lengths.iter() {
+                counts[*length] += 1;
+            }
+            counts[0] = 0;
+            let mut symbols = Vec::new();
+            for length in 1..16 {
+                for (symbol, x) in lengths.iter().enumerate() {
+                    if *x == length {
+                        symbols.push(symbol);
+                    }
+                }
+            }
+            Self { counts, symbols }
+        }
+
+        fn decode(&self, reader: &mut BitReader) -> usize {
+            let (mut code, mut first, mut index) = (0, 0, 0);
+            for length in 1..16 {
+                code |= reader.bit() as usize;
+                let count = self.counts[length];
+                if code < first + count {
+                    return self.symbols[index + code - first];
+                }
+                index += count;
+                first = (first + count) << 1;
+                code <<= 1;
+            }
+            panic!("Invalid Huffman code");
+        }
+    }
+
+    fn fixed() -> (Huffman, Huffman) {
+        let mut lengths = vec![8; 144];
+        lengths.extend(vec![9; 112]);
+        lengths.extend(vec![7; 24]);
+        lengths.extend(vec![8; 8]);
+        (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+    }
+
+    fn dynamic(reader: &mut BitReader) -> (Huffman, Huffman) {
+        const ORDER: [usize; 19] = [
+            16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+        ];
+        let literals = reader.bits(5) + 257;
+        let distances = reader.bits(5) + 1;
+        let code_lengths = reader.bits(4) + 4;
+        let mut lengths = [0; 19];
+        for k in ORDER.iter().take(code_lengths) {
+            lengths[*k] = reader.bits(3);
+        }
+        let code = Huffman::new(&lengths);
+        let mut lengths = Vec::new();
+        while lengths.len() < literals + distances {
+            match code.decode(reader) {
+                x @ 0..=15 => lengths.push(x),
+                16 => {
+                    let previous = *lengths.last().unwrap();
+                    let count = 3 + reader.bits(2);
+                    lengths.extend(vec![previous; count]);
+                }
+                17 => lengths.extend(vec![0; 3 + reader.bits(3)]),
+                _ => lengths.extend(vec![0; 11 + reader.bits(7)]),
+            }
+        }
+        (
+            Huffman::new(&lengths[..literals]),
+            Huffman::new(&lengths[literals..]),
+        )
+    }
+
+    // RFC 1951 に沿った検証用の展開
+    pub(crate) fn inflate_raw(src: &[u8]) -> Vec<u8> {
+        let mut reader = BitReader { src, position: 0 };
+        let mut dest: Vec<u8> = Vec::new();
+        loop {
+            let last = reader.bit() == 1;
+            match reader.bits(2) {
+                0 => {
+                    reader.align();
+                    let start = reader.position / 8;
+                    let length = u16::from_le_bytes([src[start], src[start + 1]]) as usize;
+                    dest.extend(&src[start + 4..start + 4 + length]);
+                    reader.position = (start + 4 + length) * 8;
+                }
+                block_type @ 1..=2 => {
+                    let (literal, distance) = if block_type == 1 {
+                        fixed()
+                    } else {
+                        dynamic(&mut reader)
+                    };
+                    loop {
+                        let symbol = literal.decode(&mut reader);
+                        if symbol < 256 {
+                            dest.push(symbol as u8);
+                            continue;
+                        }
+                        if symbol == 256 {
+                            break;
+                        }
+                        let k = symbol - 257;
+                        let length = LENGTH_BASES[k] + reader.bits(LENGTH_EXTRA_BITS[k]);
+                        let k = distance.decode(&mut reader);
+                        let back = DISTANCE_BASES[k] + reader.bits(DISTANCE_EXTRA_BITS[k]);
+                        assert!(back <= dest.len() && back <= WINDOW_SIZE);
+                        for _ in 0..length {
+                            dest.push(dest[dest.len() - back]);
+                        }
+                    }
+                }
+                _ => panic!("Invalid block type"),
+            }
+            if last {
+                return dest;
+            }
+        }
+    }
+
+    // zlib 形式の展開。ヘッダーと Adler-32 も確かめる。
+    pub(crate) fn inflate(src: &[u8]) -> Vec<u8> {
+        assert_eq!(src[0] & 0x0F, 8);
+        assert_eq!((src[0] as u16 * 256 + src[1] as u16) % 31, 0);
+        let dest = inflate_raw(&src[2..src.len() - 4]);
+        let n = src.len();
+        let checksum = u32::from_be_bytes([src[n - 4], src[n - 3], src[n - 2], src[n - 1]]);
+        assert_eq!(checksum, adler32(&dest));
+        dest
+    }
+
+    // 検証用の疑似乱数列
+    pub(crate) fn noise(length: usize, seed: u32) -> Vec<u8> {
+        let mut x = seed.wrapping_mul(2_654_435_761).wrapping_add(1);
+        (0..length)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                (x >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(update_crc32(crc32(b"12345"), b"6789"), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(&vec![0xFF; 100_000]), {
+            let (mut a, mut b) = (1u64, 0u64);
+            for _ in 0..100_000 {
+                a = (a + 0xFF) % 65521;
+                b = (b + a) % 65521;
+            }
+            ((b << 16) | a) as u32
+        });
+    }
+
+    // 検証用の展開を Python の zlib で圧縮したデータ（動的ハフマン符号と非圧縮ブロック）で確かめる。
+    #[test]
+    fn reference_streams() {
+        let expected: String = (0..60).map(|k| format!("{},", k * k % 97)).collect();
+        let dynamic = [
+            0x78, 0xDA, 0x1D, 0x8C, 0xC9, 0x0D, 0x04, 0x41, 0x08, 0xC4, 0x12, 0xF2, 0xA3, 0xB9,
+            0x21, 0xFF, 0xC4, 0xB6, 0x76, 0x24, 0x84, 0x38, 0xEC, 0x7A, 0x18, 0xC9, 0x61, 0x8D,
+            0x17, 0xD1, 0xE4, 0xD1, 0xC9, 0x1A, 0x81, 0x27, 0x39, 0x8C, 0xE3, 0x84, 0xD1, 0xCE,
+            0x89, 0x08, 0xE6, 0x61, 0x4E, 0x05, 0x27, 0x5A, 0xAE, 0x02, 0x34, 0x27, 0xF5, 0x58,
+            0xBA, 0xF0, 0x61, 0x97, 0x4A, 0xDC, 0x59, 0xA5, 0x29, 0xAA, 0x30, 0x63, 0x9B, 0x96,
+            0xB1, 0x84, 0x63, 0x22, 0xB9, 0x60, 0x8B, 0x39, 0x46, 0x3D, 0xBE, 0xFA, 0x56, 0x1D,
+            0xF5, 0xEA, 0x3F, 0x24, 0x54, 0x82, 0x34, 0xC9, 0x3F, 0x1C, 0x12, 0x21, 0x3E,
+        ];
+        assert_eq!(inflate(&dynamic), expected.as_bytes());
+        let stored = [
+            0x78, 0x01, 0x01, 0x06, 0x00, 0xF9, 0xFF, 0x73, 0x74, 0x6F, 0x72, 0x65, 0x64, 0x09,
+            0x3C, 0x02, 0x92,
+        ];
+        assert_eq!(inflate(&stored), b"stored");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut text = Vec::new();
+        for k in 0..5000 {
+            text.extend(format!("{} {}\n", k % 37, k * k % 101).bytes());
+        }
+        let cases: Vec<Vec<u8>> = vec![
+            Vec::new(),
+            vec![42],
+            b"abcabcabcabcabcabcabc".to_vec(),
+            vec![0; 100_000],                              // 最長一致 258 の繰り返し
+            noise(70_000, 1),                              // 一致のほとんどないデータ
+            text,                                          // ウィンドウ 32K を超える距離を含む
+            [noise(40_000, 2), noise(40_000, 2)].concat(), // 40000 離れた繰り返しは使えない
+        ];
+        for src in cases.iter() {
+            let compressed = zlib(src);
+            assert_eq!(&inflate(&compressed), src);
+        }
+        // 繰り返しが多ければ小さくなる。
+        assert!(zlib(&vec![0; 100_000]).len() < 1000);
+    }
+}
@@ -207,20 +207,28 @@ pub(crate) fn unpack_complex_packing_and_spatial_differencing(
     return g;
 }
 
-pub(crate) fn unpack_simple_packing(src: &Vec<usize>, r: f32, e: i32, d: i32) -> Vec<f32> {
-    let mut dest: Vec<f32> = vec![f32::NAN; src.len()];
-    for (i, value) in src.into_iter().enumerate() {
-        dest[i] = (r + (*value as f32) * 2.0f32.powi(e)) / 10.0f32.powi(d);
-    }
-    return dest;
+// 単純圧縮の値を物理量に戻す。ビットマップで欠けた値 (u16::MAX) は NaN とする。
+pub(crate) fn unpack_simple_packing(src: &[u16], r: f32, e: isize, d: isize) -> Vec<f32> {
+    let scale = 2.0f32.powi(e as i32);
+    let divisor = 10.0f32.powi(d as i32);
+    src.iter()
+        .map(|value| match *value {
+            u16::MAX => f32::NAN,
+            value => (r + value as f32 * scale) / divisor,
+        })
+        .collect()
 }
 
-pub(crate) fn unpack_level(src: &Vec<u8>, factor: isize, levels: &Vec<u16>) -> Vec<f32> {
-    let mut dest: Vec<f32> = vec![f32::NAN; src.len()];
-    for (i, value) in src.into_iter().enumerate() {
-        if 0 < *value {
-            dest[i] = (levels[*value as usize] as f32) / 10.0f32.powi(factor as i32);
-        }
-    }
-    return dest;
+// レベル値を代表値に戻す。代表値の並びはレベル 1 から始まる。
+// レベル 0（欠測）や範囲外のレベルは NaN とする。
+pub(crate) fn unpack_level(src: &[u8], factor: isize, levels: &[i16]) -> Vec<f32> {
+    let divisor = 10.0f32.powi(factor as i32);
+    src.iter()
+        .map(
+            |value| match levels.get((*value as usize).wrapping_sub(1)) {
+                Some(level) => *level as f32 / divisor,
+                None => f32::NAN,
+            },
+        )
+        .collect()
 }
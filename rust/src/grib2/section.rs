@@ -1,9 +1,19 @@
 //! GRIB2 Section declaration
 
+pub mod band_impl;
+pub mod constituent_impl;
+pub mod cross_section_impl;
+pub mod ensemble_impl;
+pub mod level_impl;
+pub mod probability_impl;
+pub mod radar_operation_impl;
+pub mod regrid_impl;
+pub mod sample_impl;
 pub mod section0_impl;
 pub mod section1_impl;
 pub mod section2_impl;
 pub mod section3_impl;
+pub mod section3_template;
 pub mod section4_impl;
 pub mod section4_template;
 pub mod section5_impl;
@@ -13,7 +23,13 @@ pub mod section7_impl;
 pub mod section7_template;
 pub mod section_impl;
 pub mod sectionset_impl;
+pub mod sounding_impl;
+pub mod statistics_impl;
+pub mod time_offset_impl;
+pub mod time_series_impl;
+pub mod window_impl;
 
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 
 // GRIB2 Section7 data type
@@ -46,6 +62,123 @@ pub struct Bounds {
     pub top: isize,
 }
 
+// 固定面（Code Table 4.5）
+#[derive(Serialize, Clone, Debug)]
+pub struct FixedSurface {
+    pub surface_type: usize,
+    pub name: &'static str,
+    pub unit: Option<&'static str>,
+    pub scale_factor: Option<isize>,
+    pub scaled_value: Option<isize>,
+    pub value: Option<f64>, // scaled_value / 10^scale_factor
+}
+
+// 鉛直レベル。単一の固定面か、2つの固定面で挟まれた層
+#[derive(Serialize, Clone, Debug)]
+pub enum Level {
+    Surface(FixedSurface),
+    Layer(FixedSurface, FixedSurface),
+}
+
+// 時間（Code Table 4.4 の単位付き）
+// 月以上の単位は日数が一定でないため月数で保持する。
+#[derive(Serialize, Copy, Clone, Debug)]
+pub enum TimeOffset {
+    Seconds(i64),
+    Months(i64),
+}
+
+// 統計処理の時間範囲（Section 4 の時間範囲ループの1要素）
+#[derive(Serialize, Clone, Debug)]
+pub struct TimeRange {
+    pub process: usize, // Code Table 4.10
+    pub process_name: &'static str,
+    pub increment_type: usize,         // Code Table 4.11
+    pub length: Option<TimeOffset>,    // 単位が予約または欠損値なら None
+    pub increment: Option<TimeOffset>, // 単位が予約または欠損値なら None
+}
+
+// 統計処理（テンプレート 4.8, 4.9, 4.11, 4.5000x など）
+#[derive(Serialize, Clone, Debug)]
+pub struct Statistics {
+    pub reference_time: DateTime<Utc>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub missing_count: usize,
+    pub time_ranges: Vec<TimeRange>,
+}
+
+// 確率予報（テンプレート 4.5, 4.9）
+#[derive(Serialize, Clone, Debug)]
+pub struct Probability {
+    pub forecast_probability_number: usize,
+    pub total_forecast_probabilities: usize,
+    pub probability_type: usize, // Code Table 4.9
+    pub probability_type_name: &'static str,
+    pub lower_limit: Option<f64>,
+    pub upper_limit: Option<f64>,
+}
+
+// パーセンタイル予報（テンプレート 4.6, 4.10）
+#[derive(Serialize, Clone, Debug)]
+pub struct Percentile {
+    pub value: usize, // 0-100
+}
+
+// アンサンブル予報のメンバー（テンプレート 4.1, 4.11）
+#[derive(Serialize, Clone, Debug)]
+pub struct Ensemble {
+    pub ensemble_type: usize, // Code Table 4.6
+    pub ensemble_type_name: &'static str,
+    pub perturbation_number: usize,
+    pub member_count: usize,
+}
+
+// アンサンブル予報の全メンバーから導出した予報（テンプレート 4.2, 4.12）
+#[derive(Serialize, Clone, Debug)]
+pub struct DerivedForecast {
+    pub derived_type: usize, // Code Table 4.7
+    pub derived_type_name: &'static str,
+    pub member_count: usize,
+}
+
+// 区間（Code Table 4.91 の種類付き）
+#[derive(Serialize, Clone, Debug)]
+pub struct Interval {
+    pub interval_type: usize, // Code Table 4.91
+    pub interval_type_name: &'static str,
+    pub first: Option<f64>,
+    pub second: Option<f64>,
+}
+
+// 大気化学成分またはエーロゾル（テンプレート 4.40, 4.44, 4.48）
+#[derive(Serialize, Clone, Debug)]
+pub struct Constituent {
+    pub constituent_type: usize, // Code Table 4.230, 4.233
+    pub name: &'static str,
+    pub size: Option<Interval>,       // 粒径 [m]
+    pub wavelength: Option<Interval>, // 波長 [m]
+}
+
+// 衛星の観測バンド（テンプレート 4.31, 4.32 のバンドループの1要素）
+#[derive(Serialize, Clone, Debug)]
+pub struct Band {
+    pub satellite_series: usize,
+    pub satellite_number: usize, // Common Code Table C-5
+    pub satellite_name: Option<&'static str>,
+    pub instrument_type: usize,
+    pub central_wave_number: Option<f64>, // [m-1]
+    pub channel: Option<usize>,           // 測器のバンド番号が分かれば Some
+}
+
+// レーダー等運用情報（気象庁ローカルテンプレート 4.5000x のレイアウトより後ろのオクテット）
+// ビットの意味は確認できていないため、立っているビットの位置だけを持つ。
+#[derive(Serialize, Clone, Debug)]
+pub struct RadarOperation {
+    pub octets: usize,    // オクテット数
+    pub bits: Vec<usize>, // 立っているビットの位置（先頭オクテットの最上位ビットを1とする）
+}
+
 #[derive(Copy, Clone)]
 pub(crate) struct Section0<'a> {
     buf: &'a [u8],
@@ -95,6 +228,61 @@ pub(crate) trait Section {
     fn section_number(&self) -> usize;
 }
 
+// 時系列の1時刻分の値
+#[derive(Serialize, Clone, Debug)]
+pub struct TimeSeriesPoint {
+    pub index: usize, // 値を取り出した SectionSet の位置
+    pub reference_time: DateTime<Utc>,
+    pub valid_time: DateTime<Utc>,
+    pub value: Option<f64>,
+}
+
+// 鉛直プロファイルの1気圧面分の値
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct SoundingLevel {
+    pub pressure: f64,                  // [hPa]
+    pub height: Option<f64>,            // ジオポテンシャル高度 [m]
+    pub temperature: Option<f64>,       // [°C]
+    pub dewpoint: Option<f64>,          // [°C]
+    pub relative_humidity: Option<f64>, // [%]
+    pub u: Option<f64>,                 // [m/s]
+    pub v: Option<f64>,                 // [m/s]
+    pub wind_direction: Option<f64>,    // 風向 [度]
+    pub wind_speed: Option<f64>,        // [m/s]
+}
+
+// 1地点の鉛直プロファイル。気圧の高い順に並ぶ。
+#[derive(Serialize, Clone, Debug)]
+pub struct Sounding {
+    pub reference_time: DateTime<Utc>,
+    pub valid_time: DateTime<Utc>,
+    pub longitude: f64,
+    pub latitude: f64,
+    pub levels: Vec<SoundingLevel>,
+}
+
+// 大円に沿った鉛直断面。行は気圧の低い順（上空から）、列は始点からの順に並ぶ。
+#[derive(Clone, Debug)]
+pub struct CrossSection {
+    pub width: usize,         // 経路上の点の数
+    pub height: usize,        // 等圧面の数
+    pub distances: Vec<f64>,  // 始点からの距離 [km]
+    pub longitudes: Vec<f64>, // 経路上の点の経度
+    pub latitudes: Vec<f64>,  // 経路上の点の緯度
+    pub pressures: Vec<f64>,  // [hPa]
+    pub values: Vec<f32>,     // 値がなければ NaN
+}
+
+// 格子から切り出す範囲。切り出した格子の列と行の位置、範囲、格子間隔を持つ。
+#[derive(Clone)]
+pub struct Window {
+    pub(crate) columns: Vec<usize>,
+    pub(crate) rows: Vec<usize>,
+    pub bounds: Bounds,
+    pub di: usize,
+    pub dj: usize,
+}
+
 #[derive(Copy, Clone)]
 pub struct SectionSet<'a> {
     pub(crate) section0: Option<Section0<'a>>,
@@ -117,6 +305,32 @@ pub struct SectionSetsIter<'a> {
     index: usize,
 }
 
+// 格子点値の補間方法
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Interpolation {
+    Nearest,
+    Bilinear,
+    Bicubic,
+}
+
+// 格子系の変換方法
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum RegridMethod {
+    Nearest,
+    Bilinear,
+    Conservative, // セルの重なりの面積で重み付けした平均
+}
+
+// 格子系の変換に使う重み。変換先の格子点ごとに、変換元の格子点の位置と重みを持つ。
+// 格子系と変換方法が同じなら、異なる要素や時刻の変換に使い回せる。
+#[derive(Clone, Debug)]
+pub struct RegridWeights {
+    pub(crate) ni: usize,
+    pub(crate) nj: usize,
+    pub(crate) source_count: usize,
+    pub(crate) weights: Vec<Vec<(usize, f64)>>,
+}
+
 #[derive(Serialize, Debug)]
 pub enum PackingType {
     Simple,
@@ -0,0 +1,89 @@
+//! GRIB2 satellite band implementation
+
+use std::fmt;
+
+use super::super::type_utils_impl::scaled_value_be;
+use super::super::type_utils_impl::u16_be;
+use super::Band;
+
+pub(crate) const BAND_LENGTH: usize = 11; // バンド1つあたりのオクテット数
+
+// 衛星の名称（Common Code Table C-5 のうちひまわり）
+fn satellite_name(satellite_number: usize) -> Option<&'static str> {
+    match satellite_number {
+        171 => Some("MTSAT-1R"),
+        172 => Some("MTSAT-2"),
+        173 => Some("Himawari-8"),
+        174 => Some("Himawari-9"),
+        _ => None,
+    }
+}
+
+// ひまわり8号・9号 AHI の各バンドの中心波長 [µm]
+const AHI_WAVELENGTHS: [f64; 16] = [
+    0.47, 0.51, 0.64, 0.86, 1.6, 2.3, 3.9, 6.2, 6.9, 7.3, 8.6, 9.6, 10.4, 11.2, 12.4, 13.3,
+];
+
+// 中心波長が最も近い AHI のバンド番号を返す。
+fn ahi_channel(wavelength: f64) -> Option<usize> {
+    AHI_WAVELENGTHS
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| (wavelength - *x).abs() / *x < 0.05)
+        .min_by(|(_, a), (_, b)| {
+            let a = (wavelength - *a).abs();
+            let b = (wavelength - *b).abs();
+            a.partial_cmp(&b).unwrap()
+        })
+        .map(|(i, _)| i + 1)
+}
+
+// 衛星シリーズから始まる11オクテットをバンドとして返す。
+//   0-1   衛星シリーズ
+//   2-3   衛星番号
+//   4-5   測器の種類
+//   6-10  中心波数の尺度因子と尺度付きの値 [m-1]
+pub(crate) fn band_be(src: &[u8]) -> Band {
+    assert!(src.len() == BAND_LENGTH);
+
+    let satellite_number = u16_be(&src[2..4]) as usize;
+    let central_wave_number = scaled_value_be(&src[6..11]).filter(|x| 0.0 < *x);
+
+    let satellite_name = satellite_name(satellite_number);
+    let channel = match satellite_number {
+        173 | 174 => central_wave_number.and_then(|x| ahi_channel(1e6 / x)),
+        _ => None,
+    };
+
+    Band {
+        satellite_series: u16_be(&src[0..2]) as usize,
+        satellite_number,
+        satellite_name,
+        instrument_type: u16_be(&src[4..6]) as usize,
+        central_wave_number,
+        channel,
+    }
+}
+
+impl Band {
+    // 中心波長 [µm]
+    pub fn wavelength(&self) -> Option<f64> {
+        Some(1e6 / self.central_wave_number?)
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.satellite_name {
+            Some(name) => write!(f, "{}", name)?,
+            None => write!(f, "Satellite {}", self.satellite_number)?,
+        }
+        if let Some(channel) = self.channel {
+            write!(f, " band {}", channel)?;
+        }
+        if let Some(wavelength) = self.wavelength() {
+            write!(f, " ({} µm)", (wavelength * 100.0).round() / 100.0)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+//! GRIB2 atmospheric chemical constituent and aerosol implementation
+
+use std::fmt;
+
+use super::Constituent;
+use super::Interval;
+
+const MISSING_INTERVAL_TYPE: usize = 255;
+
+// 大気化学成分の種類の名称（Code Table 4.230, 4.233）
+// 乾燥状態の粒子を表すエーロゾルは "dry" を省いて表す。
+pub(crate) fn constituent_type_name(constituent_type: usize) -> &'static str {
+    match constituent_type {
+        0 => "Ozone",
+        1 => "Water vapour",
+        2 => "Methane",
+        3 => "Carbon dioxide",
+        4 => "Carbon monoxide",
+        5 => "Nitrogen dioxide",
+        6 => "Nitrous oxide",
+        7 => "Formaldehyde",
+        8 => "Sulphur dioxide",
+        9 => "Ammonia",
+        10 => "Ammonium",
+        11 => "Nitrogen monoxide",
+        12 => "Atomic oxygen",
+        13 => "Nitrate radical",
+        14 => "Hydroperoxyl radical",
+        15 => "Dinitrogen pentoxide",
+        16 => "Nitrous acid",
+        17 => "Nitric acid",
+        18 => "Peroxynitric acid",
+        19 => "Hydrogen peroxide",
+        20 => "Molecular hydrogen",
+        21 => "Atomic nitrogen",
+        22 => "Sulphate",
+        23 => "Radon",
+        10000 => "Hydroxyl radical",
+        62000 => "Total aerosol",
+        62001 => "Dust",
+        62002 => "Water in ambient",
+        62003 => "Ammonium",
+        62004 => "Nitrate",
+        62005 => "Nitric acid trihydrate",
+        62006 => "Sulphate",
+        62007 => "Mercury",
+        62008 => "Sea salt",
+        62009 => "Black carbon",
+        62010 => "Particulate organic matter",
+        62020 => "Smoke - high absorption",
+        62021 => "Smoke - low absorption",
+        62022 => "Aerosol - high absorption",
+        62023 => "Aerosol - low absorption",
+        62025 => "Volcanic ash",
+        65535 => "Missing",
+        _ => "Unknown constituent",
+    }
+}
+
+// 区間の種類の名称（Code Table 4.91）
+pub(crate) fn interval_type_name(interval_type: usize) -> &'static str {
+    match interval_type {
+        0 => "Smaller than first limit",
+        1 => "Greater than second limit",
+        2 => "Between first and second limit, including first limit",
+        3 => "Greater than first limit",
+        4 => "Smaller than second limit",
+        5 => "Smaller or equal first limit",
+        6 => "Greater or equal second limit",
+        7 => "Between first and second limit, including both limits",
+        8 => "Greater or equal first limit",
+        9 => "Smaller or equal second limit",
+        10 => "Between first and second limit, including second limit",
+        11 => "Equal to first limit",
+        192..=254 => "Reserved for local use",
+        255 => "Missing",
+        _ => "Reserved",
+    }
+}
+
+// 区間を返す。種類が欠損値なら None を返す。
+pub(crate) fn interval(
+    interval_type: usize,
+    first: Option<f64>,
+    second: Option<f64>,
+) -> Option<Interval> {
+    if interval_type == MISSING_INTERVAL_TYPE {
+        return None;
+    }
+
+    Some(Interval {
+        interval_type,
+        interval_type_name: interval_type_name(interval_type),
+        first,
+        second,
+    })
+}
+
+// 値を単位に換算して表す。浮動小数点の誤差は丸める。
+fn format_value(value: Option<f64>, scale: f64) -> String {
+    match value {
+        Some(value) => format!("{}", (value * scale * 1e6).round() / 1e6),
+        None => String::from("?"),
+    }
+}
+
+impl Interval {
+    // 単位に換算して表す。 e.g. 0.03–0.55 µm
+    fn describe(&self, scale: f64, unit: &str) -> String {
+        let first = format_value(self.first, scale);
+        let second = format_value(self.second, scale);
+        match self.interval_type {
+            0 => format!("< {} {}", first, unit),
+            1 => format!("> {} {}", second, unit),
+            3 => format!("> {} {}", first, unit),
+            4 => format!("< {} {}", second, unit),
+            5 => format!("≤ {} {}", first, unit),
+            6 => format!("≥ {} {}", second, unit),
+            8 => format!("≥ {} {}", first, unit),
+            9 => format!("≤ {} {}", second, unit),
+            11 => format!("{} {}", first, unit),
+            _ => format!("{}–{} {}", first, second, unit),
+        }
+    }
+}
+
+impl Constituent {
+    // パラメーター名に成分を加えて表す。 e.g. Mass density – dust, 0.03–0.55 µm
+    pub fn describe(&self, parameter_name: &str) -> String {
+        let constituent = self.to_string();
+        let mut chars = constituent.chars();
+        match chars.next() {
+            Some(c) => format!(
+                "{} – {}{}",
+                parameter_name,
+                c.to_lowercase(),
+                chars.as_str()
+            ),
+            None => String::from(parameter_name),
+        }
+    }
+}
+
+impl fmt::Display for Constituent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+
+        // 粒径は µm、波長は nm で表す。
+        if let Some(size) = &self.size {
+            write!(f, ", {}", size.describe(1e6, "µm"))?;
+        }
+        if let Some(wavelength) = &self.wavelength {
+            write!(f, ", {}", wavelength.describe(1e9, "nm"))?;
+        }
+        Ok(())
+    }
+}
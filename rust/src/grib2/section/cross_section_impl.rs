@@ -0,0 +1,232 @@
+//! GRIB2 vertical cross-section implementation
+
+use super::section3_template::section3_template_impl::normalize_longitude;
+use super::sounding_impl::isobaric_pressure;
+use super::time_series_impl::variable_key;
+use super::CrossSection;
+use super::Interpolation;
+use super::SectionSets;
+
+const EARTH_RADIUS: f64 = 6371.0; // [km]
+
+// 経度と緯度 [度] を単位ベクトルで返す。
+fn to_vector(lon: f64, lat: f64) -> [f64; 3] {
+    let (lon, lat) = (lon.to_radians(), lat.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+// 単位ベクトルを経度と緯度 [度] で返す。
+fn to_lonlat(v: [f64; 3]) -> (f64, f64) {
+    let lon = v[1].atan2(v[0]).to_degrees();
+    let lat = v[2].atan2(v[0].hypot(v[1])).to_degrees();
+    (normalize_longitude(lon), lat)
+}
+
+// 2点を結ぶ大円上に等間隔に並ぶ count 個の点と、始点からの距離 [km] を返す。
+fn great_circle(start: (f64, f64), end: (f64, f64), count: usize) -> Vec<(f64, f64, f64)> {
+    let a = to_vector(start.0, start.1);
+    let b = to_vector(end.0, end.1);
+    let dot = (a[0] * b[0] + a[1] * b[1] + a[2] * b[2]).clamp(-1.0, 1.0);
+    let angle = dot.acos();
+
+    (0..count)
+        .map(|k| {
+            let t = if count < 2 {
+                0.0
+            } else {
+                k as f64 / (count - 1) as f64
+            };
+            let (lon, lat) = if angle.sin().abs() < 1e-12 {
+                // 2点が一致するか対蹠点なら始点から動かない。
+                start
+            } else {
+                // 球面線形補間
+                let wa = ((1.0 - t) * angle).sin() / angle.sin();
+                let wb = (t * angle).sin() / angle.sin();
+                to_lonlat([
+                    wa * a[0] + wb * b[0],
+                    wa * a[1] + wb * b[1],
+                    wa * a[2] + wb * b[2],
+                ])
+            };
+            (lon, lat, t * angle * EARTH_RADIUS)
+        })
+        .collect()
+}
+
+impl<'a> SectionSets<'a> {
+    // index の SectionSet と同じ要素、同じ時刻の全等圧面について、
+    // 始点から終点への大円上の count 点の値を鉛直断面として返す。
+    pub(crate) fn cross_section(
+        &self,
+        index: usize,
+        start: (f64, f64),
+        end: (f64, f64),
+        count: usize,
+        method: Interpolation,
+    ) -> Option<CrossSection> {
+        let base = self.items.get(index)?;
+        let key = variable_key(base)?;
+        let reference_time = base.reference_datetime()?;
+        let valid_time = base.datetime()?;
+
+        let path = great_circle(start, end, count);
+        let points: Vec<(f64, f64)> = path.iter().map(|(lon, lat, _)| (*lon, *lat)).collect();
+
+        // 気圧ごとに最初に見つかった SectionSet を使う。
+        let mut rows: Vec<(f64, Vec<f32>)> = Vec::new();
+        for sectionset in self.iter() {
+            if sectionset.reference_datetime() != Some(reference_time)
+                || sectionset.datetime() != Some(valid_time)
+                || variable_key(&sectionset).as_ref() != Some(&key)
+            {
+                continue;
+            }
+            let pressure = match isobaric_pressure(&sectionset) {
+                Some(pressure) => pressure / 100.0,
+                None => continue,
+            };
+            if rows.iter().any(|(x, _)| *x == pressure) {
+                continue;
+            }
+
+            let values = sectionset
+                .sample_points(&points, method)
+                .into_iter()
+                .map(|x| x.map_or(f32::NAN, |x| x as f32))
+                .collect();
+            rows.push((pressure, values));
+        }
+
+        if rows.is_empty() {
+            return None;
+        }
+        rows.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        Some(CrossSection {
+            width: count,
+            height: rows.len(),
+            distances: path.iter().map(|(_, _, x)| *x).collect(),
+            longitudes: points.iter().map(|(lon, _)| *lon).collect(),
+            latitudes: points.iter().map(|(_, lat)| *lat).collect(),
+            pressures: rows.iter().map(|(x, _)| *x).collect(),
+            values: rows.into_iter().flat_map(|(_, x)| x).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use std::f64::consts::PI;
+
+    use super::super::super::utils_impl::parse;
+    use super::super::super::utils_impl::tests::*;
+    use super::*;
+
+    fn assert_near(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    // 2023-06-01 00UTC 初期値の予報。北緯36度から35度、東経139度から141度の 3 x 2 格子
+    fn field(parameter: (u8, u8), hours: u32, pressure: i32, offset: f32) -> Vec<u8> {
+        let values: Vec<f32> = (1..=6).map(|x| x as f32 + offset).collect();
+        message(
+            0,
+            &[
+                vec![identification(34, Utc.ymd(2023, 6, 1).and_hms(0, 0, 0))],
+                vec![latlon_grid(3, 2, 36.0, 139.0, 1.0, 1.0)],
+                vec![product(
+                    0,
+                    &horizontal(parameter, 1, hours, (100, 0, pressure)),
+                )],
+                simple_packing(&values),
+            ]
+            .concat(),
+        )
+    }
+
+    #[test]
+    fn great_circle_points() {
+        // 赤道上の4分の1周
+        let path = great_circle((0.0, 0.0), (90.0, 0.0), 3);
+        assert_eq!(path.len(), 3);
+        assert_near(path[1].0, 45.0);
+        assert_near(path[1].1, 0.0);
+        assert_near(path[2].0, 90.0);
+        assert_near(path[2].2, PI / 2.0 * EARTH_RADIUS);
+
+        // 日付変更線をまたぐ経路の経度は -180 度以上 180 度未満
+        let path = great_circle((170.0, 0.0), (-170.0, 0.0), 3);
+        assert_near(path[1].0, -180.0);
+        assert_near(path[2].0, -170.0);
+        assert_near(path[2].2, 20f64.to_radians() * EARTH_RADIUS);
+
+        // 子午線上の中点
+        let path = great_circle((140.0, 30.0), (140.0, 40.0), 3);
+        assert_near(path[1].0, 140.0);
+        assert_near(path[1].1, 35.0);
+
+        // 2点が一致すれば始点から動かない。1点なら始点だけ
+        let path = great_circle((140.0, 35.0), (140.0, 35.0), 3);
+        assert!(path.iter().all(|x| *x == (140.0, 35.0, 0.0)));
+        assert_eq!(great_circle((0.0, 0.0), (90.0, 0.0), 1), [(0.0, 0.0, 0.0)]);
+    }
+
+    #[test]
+    fn cross_section() {
+        let buf = [
+            field((0, 0), 6, 85000, 0.0),
+            field((0, 0), 6, 50000, 10.0),
+            // 同じ気圧の2つ目、別の要素、別の有効時刻は使わない。
+            field((0, 0), 6, 85000, 100.0),
+            field((2, 2), 6, 70000, 0.0),
+            field((0, 0), 12, 70000, 0.0),
+        ]
+        .concat();
+        let sectionsets = parse(&buf);
+
+        // 東経140度の子午線に沿って北緯36度から34度まで
+        let section = sectionsets
+            .cross_section(1, (140.0, 36.0), (140.0, 34.0), 5, Interpolation::Bilinear)
+            .unwrap();
+        assert_eq!(section.width, 5);
+        assert_eq!(section.height, 2);
+        assert_eq!(section.pressures, [500.0, 850.0]);
+        for (latitude, expected) in section
+            .latitudes
+            .iter()
+            .zip(&[36.0, 35.5, 35.0, 34.5, 34.0])
+        {
+            assert_near(*latitude, *expected);
+        }
+        assert!(section.longitudes.iter().all(|x| (x - 140.0).abs() < 1e-6));
+        assert_near(section.distances[4], 2f64.to_radians() * EARTH_RADIUS);
+
+        // 気圧の低い順に並ぶ。格子の端から1格子未満は内側の格子点の値、それより外は NaN
+        let expected = [
+            12.0,
+            13.5,
+            15.0,
+            15.0,
+            f32::NAN,
+            2.0,
+            3.5,
+            5.0,
+            5.0,
+            f32::NAN,
+        ];
+        assert_eq!(section.values.len(), expected.len());
+        for (value, expected) in section.values.iter().zip(&expected) {
+            if expected.is_nan() {
+                assert!(value.is_nan());
+            } else {
+                assert!((value - expected).abs() < 1e-3, "{} != {}", value, expected);
+            }
+        }
+
+        assert!(sectionsets
+            .cross_section(99, (140.0, 36.0), (140.0, 34.0), 5, Interpolation::Bilinear)
+            .is_none());
+    }
+}
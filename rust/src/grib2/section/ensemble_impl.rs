@@ -0,0 +1,102 @@
+//! GRIB2 ensemble implementation
+
+use std::fmt;
+
+use super::super::type_utils_impl::u8_be;
+use super::DerivedForecast;
+use super::Ensemble;
+
+// アンサンブル予報の種類の名称（Code Table 4.6）
+pub(crate) fn ensemble_type_name(ensemble_type: usize) -> &'static str {
+    match ensemble_type {
+        0 => "Unperturbed high-resolution control forecast",
+        1 => "Unperturbed low-resolution control forecast",
+        2 => "Negatively perturbed forecast",
+        3 => "Positively perturbed forecast",
+        4 => "Multi-model forecast",
+        192..=254 => "Reserved for local use",
+        255 => "Missing",
+        _ => "Reserved",
+    }
+}
+
+// 導出予報の種類の名称（Code Table 4.7）
+pub(crate) fn derived_type_name(derived_type: usize) -> &'static str {
+    match derived_type {
+        0 => "Unweighted mean of all members",
+        1 => "Weighted mean of all members",
+        2 => "Standard deviation with respect to cluster mean",
+        3 => "Standard deviation with respect to cluster mean, normalized",
+        4 => "Spread of all members",
+        5 => "Large anomaly index of all members",
+        6 => "Unweighted mean of the cluster members",
+        7 => "Interquartile range",
+        8 => "Minimum of all ensemble members",
+        9 => "Maximum of all ensemble members",
+        10 => "Variance of all ensemble members",
+        192..=254 => "Reserved for local use",
+        255 => "Missing",
+        _ => "Reserved",
+    }
+}
+
+// アンサンブル予報の種類から始まる3オクテットをメンバーとして返す。
+//   0  アンサンブル予報の種類
+//   1  摂動番号
+//   2  アンサンブル予報のメンバー数
+pub(crate) fn ensemble_be(src: &[u8]) -> Ensemble {
+    assert!(src.len() == 3);
+
+    let ensemble_type = u8_be(&src[0..1]) as usize;
+    Ensemble {
+        ensemble_type,
+        ensemble_type_name: ensemble_type_name(ensemble_type),
+        perturbation_number: u8_be(&src[1..2]) as usize,
+        member_count: u8_be(&src[2..3]) as usize,
+    }
+}
+
+// 導出予報の種類から始まる2オクテットを導出予報として返す。
+//   0  導出予報の種類
+//   1  アンサンブル予報のメンバー数
+pub(crate) fn derived_forecast_be(src: &[u8]) -> DerivedForecast {
+    assert!(src.len() == 2);
+
+    let derived_type = u8_be(&src[0..1]) as usize;
+    DerivedForecast {
+        derived_type,
+        derived_type_name: derived_type_name(derived_type),
+        member_count: u8_be(&src[1..2]) as usize,
+    }
+}
+
+impl Ensemble {
+    // 摂動を与えていないコントロールランなら true を返す。
+    pub fn is_control(&self) -> bool {
+        self.ensemble_type == 0 || self.ensemble_type == 1
+    }
+}
+
+impl fmt::Display for Ensemble {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_control() {
+            write!(f, "Control ({} members)", self.member_count)
+        } else {
+            write!(
+                f,
+                "Member {}/{} ({})",
+                self.perturbation_number, self.member_count, self.ensemble_type_name
+            )
+        }
+    }
+}
+
+impl fmt::Display for DerivedForecast {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} members)",
+            self.derived_type_name, self.member_count
+        )
+    }
+}
@@ -0,0 +1,290 @@
+//! GRIB2 fixed surface (Code Table 4.5) implementation
+
+use std::fmt;
+
+use super::super::type_utils_impl::i32_be;
+use super::super::type_utils_impl::i8_be;
+use super::super::type_utils_impl::scaled_value_be;
+use super::super::type_utils_impl::u32_be;
+use super::super::type_utils_impl::u8_be;
+use super::FixedSurface;
+use super::Level;
+
+const MISSING_SURFACE_TYPE: usize = 255;
+
+// 固定面の種類の名称と単位（Code Table 4.5）
+// 値を持たない固定面の単位は None、無次元の値を持つ固定面の単位は空文字列とする。
+fn surface_type_description(surface_type: usize) -> (&'static str, Option<&'static str>) {
+    match surface_type {
+        0 => ("Reserved", None),
+        1 => ("Ground or water surface", None),
+        2 => ("Cloud base level", None),
+        3 => ("Level of cloud tops", None),
+        4 => ("Level of 0°C isotherm", None),
+        5 => ("Level of adiabatic condensation lifted from the surface", None),
+        6 => ("Maximum wind level", None),
+        7 => ("Tropopause", None),
+        8 => ("Nominal top of the atmosphere", None),
+        9 => ("Sea bottom", None),
+        10 => ("Entire atmosphere", None),
+        11 => ("Cumulonimbus base (CB)", Some("m")),
+        12 => ("Cumulonimbus top (CT)", Some("m")),
+        13 => ("Lowest level where vertically integrated cloud cover exceeds the specified percentage", Some("%")),
+        14 => ("Level of free convection (LFC)", None),
+        15 => ("Convection condensation level (CCL)", None),
+        16 => ("Level of neutral buoyancy or equilibrium (LNB)", None),
+        17 => ("Departure level of the most unstable parcel of air (MUDL)", None),
+        18 => ("Departure level of a mixed layer parcel of air with specified layer depth", Some("Pa")),
+        19 => ("Reserved", None),
+        20 => ("Isothermal level", Some("K")),
+        21 => ("Lowest level where mass density exceeds the specified value", Some("kg m-3")),
+        22 => ("Highest level where mass density exceeds the specified value", Some("kg m-3")),
+        23 => ("Lowest level where air concentration exceeds the specified value", Some("Bq m-3")),
+        24 => ("Highest level where air concentration exceeds the specified value", Some("Bq m-3")),
+        25 => ("Highest level where radar reflectivity exceeds the specified value", Some("dBZ")),
+        26 => ("Convective cloud layer base", Some("m")),
+        27 => ("Convective cloud layer top", Some("m")),
+        28..=29 => ("Reserved", None),
+        30 => ("Specified radius from the centre of the Sun", Some("m")),
+        31 => ("Solar photosphere", None),
+        32 => ("Ionospheric D-region level", None),
+        33 => ("Ionospheric E-region level", None),
+        34 => ("Ionospheric F1-region level", None),
+        35 => ("Ionospheric F2-region level", None),
+        36..=99 => ("Reserved", None),
+        100 => ("Isobaric surface", Some("Pa")),
+        101 => ("Mean sea level", None),
+        102 => ("Specific altitude above mean sea level", Some("m")),
+        103 => ("Specified height level above ground", Some("m")),
+        104 => ("Sigma level", Some("")),
+        105 => ("Hybrid level", Some("")),
+        106 => ("Depth below land surface", Some("m")),
+        107 => ("Isentropic (theta) level", Some("K")),
+        108 => ("Level at specified pressure difference from ground to level", Some("Pa")),
+        109 => ("Potential vorticity surface", Some("K m2 kg-1 s-1")),
+        110 => ("Reserved", None),
+        111 => ("Eta level", Some("")),
+        112 => ("Reserved", None),
+        113 => ("Logarithmic hybrid level", Some("")),
+        114 => ("Snow level", Some("")),
+        115 => ("Sigma height level", Some("")),
+        116 => ("Reserved", None),
+        117 => ("Mixed layer depth", Some("m")),
+        118 => ("Hybrid height level", Some("")),
+        119 => ("Hybrid pressure level", Some("")),
+        120..=149 => ("Reserved", None),
+        150 => ("Generalized vertical height coordinate", Some("")),
+        151 => ("Soil level", Some("")),
+        152 => ("Sea-ice level", Some("")),
+        153..=159 => ("Reserved", None),
+        160 => ("Depth below sea level", Some("m")),
+        161 => ("Depth below water surface", Some("m")),
+        162 => ("Lake or river bottom", None),
+        163 => ("Bottom of sediment layer", None),
+        164 => ("Bottom of thermally active sediment layer", None),
+        165 => ("Bottom of sediment layer penetrated by thermal wave", None),
+        166 => ("Mixing layer", None),
+        167 => ("Bottom of root zone", None),
+        168 => ("Ocean model level", Some("")),
+        169 => ("Ocean level defined by water density (sigma-theta) difference from near-surface to level", Some("kg m-3")),
+        170 => ("Ocean level defined by water potential temperature difference from near-surface to level", Some("K")),
+        171 => ("Ocean level defined by vertical eddy diffusivity difference from near-surface to level", Some("m2 s-1")),
+        172 => ("Ocean level defined by water density (rho) difference from near-surface to level", Some("kg m-3")),
+        173 => ("Top of snow over sea ice on sea, lake or river", None),
+        174 => ("Top surface of ice on sea, lake or river", None),
+        175 => ("Top surface of ice, under snow cover, on sea, lake or river", None),
+        176 => ("Bottom surface (underside) ice on sea, lake or river", None),
+        177 => ("Deep soil (of indefinite depth)", None),
+        178 => ("Reserved", None),
+        179 => ("Top surface of glacier ice and inland ice", None),
+        180 => ("Deep inland or glacier ice (of indefinite depth)", None),
+        181 => ("Grid tile land fraction as a model surface", None),
+        182 => ("Grid tile water fraction as a model surface", None),
+        183 => ("Grid tile ice fraction on sea, lake or river as a model surface", None),
+        184 => ("Grid tile glacier ice and inland ice fraction as a model surface", None),
+        185 => ("Roughness height", None),
+        186..=191 => ("Reserved", None),
+        200 => ("タンクモデルの全タンク（土壌雨量指数）", None), // 気象庁ローカル
+        201 => ("タンクモデルのタンク番号", Some("")),        // 気象庁ローカル
+        192..=254 => ("Reserved for local use", None),
+        _ => ("Missing", None),
+    }
+}
+
+// 固定面の種類(1), 尺度因子(1), 尺度付きの値(4) の6オクテットから固定面を返す。
+// 種類が欠損値なら None を返す。
+pub(crate) fn fixed_surface_be(src: &[u8]) -> Option<FixedSurface> {
+    assert!(src.len() == 6);
+
+    let surface_type = u8_be(&src[0..1]) as usize;
+    if surface_type == MISSING_SURFACE_TYPE {
+        return None;
+    }
+
+    // 全ビットが立っていれば欠損値
+    let scale_factor = match u8_be(&src[1..2]) {
+        u8::MAX => None,
+        _ => Some(i8_be(&src[1..2]) as isize),
+    };
+    let scaled_value = match u32_be(&src[2..6]) {
+        u32::MAX => None,
+        _ => Some(i32_be(&src[2..6]) as isize),
+    };
+    let value = scaled_value_be(&src[1..6]);
+
+    let (name, unit) = surface_type_description(surface_type);
+    Some(FixedSurface {
+        surface_type,
+        name,
+        unit,
+        scale_factor,
+        scaled_value,
+        value,
+    })
+}
+
+// 第一固定面と第二固定面の12オクテットからレベルを返す。
+pub(crate) fn level_be(src: &[u8]) -> Option<Level> {
+    assert!(src.len() == 12);
+
+    let first = fixed_surface_be(&src[0..6])?;
+    match fixed_surface_be(&src[6..12]) {
+        Some(second) => Some(Level::Layer(first, second)),
+        None => Some(Level::Surface(first)),
+    }
+}
+
+impl FixedSurface {
+    // 表示用の値と単位。気圧は hPa で表す。
+    fn display_value(&self) -> Option<(f64, &'static str)> {
+        let value = self.value?;
+        match self.unit? {
+            "Pa" => Some((value / 100.0, "hPa")),
+            unit => Some((value, unit)),
+        }
+    }
+}
+
+impl Level {
+    pub fn first(&self) -> &FixedSurface {
+        match self {
+            Level::Surface(first) => first,
+            Level::Layer(first, _) => first,
+        }
+    }
+}
+
+impl fmt::Display for FixedSurface {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.display_value() {
+            Some((value, "")) => write!(f, "{} {}", self.name, value),
+            Some((value, unit)) => write!(f, "{} {} {}", self.name, value, unit),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Level::Surface(surface) => write!(f, "{}", surface),
+            Level::Layer(first, second) => {
+                // 同じ種類の固定面で挟まれた層は値の範囲で表す。
+                if first.surface_type == second.surface_type {
+                    if let (Some((v1, unit)), Some((v2, _))) =
+                        (first.display_value(), second.display_value())
+                    {
+                        return match unit {
+                            "" => write!(f, "{} {}–{}", first.name, v1, v2),
+                            _ => write!(f, "{} {}–{} {}", first.name, v1, v2, unit),
+                        };
+                    }
+                }
+                write!(f, "{} – {}", first, second)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::utils_impl::tests::{fixed_surface, MISSING_SURFACE};
+    use super::*;
+
+    fn parse(first: (u8, i8, i32), second: (u8, i8, i32)) -> Option<Level> {
+        level_be(&[fixed_surface(first), fixed_surface(second)].concat())
+    }
+
+    // 気圧は hPa、尺度因子を反映した値で表示する。
+    #[test]
+    fn surface() {
+        let level = parse((100, 0, 85000), MISSING_SURFACE).unwrap();
+        assert_eq!(level.to_string(), "Isobaric surface 850 hPa");
+        assert_eq!(level.first().value, Some(85000.0));
+
+        let level = parse((103, 1, 15), MISSING_SURFACE).unwrap();
+        assert_eq!(
+            level.to_string(),
+            "Specified height level above ground 1.5 m"
+        );
+
+        // 負の尺度因子
+        let level = parse((102, -3, 2), MISSING_SURFACE).unwrap();
+        assert_eq!(
+            level.to_string(),
+            "Specific altitude above mean sea level 2000 m"
+        );
+
+        // 無次元の値
+        let level = parse((104, 4, 9950), MISSING_SURFACE).unwrap();
+        assert_eq!(level.to_string(), "Sigma level 0.995");
+    }
+
+    // 値を持たない固定面と、値が欠損値の固定面は名称だけを表示する。
+    #[test]
+    fn surface_without_value() {
+        let level = parse((1, -1, -1), MISSING_SURFACE).unwrap();
+        assert_eq!(level.to_string(), "Ground or water surface");
+        assert_eq!(level.first().scale_factor, None);
+        assert_eq!(level.first().scaled_value, None);
+
+        let level = parse((100, -1, -1), MISSING_SURFACE).unwrap();
+        assert_eq!(level.to_string(), "Isobaric surface");
+        assert_eq!(level.first().value, None);
+
+        assert_eq!(
+            parse((201, 0, 2), MISSING_SURFACE).unwrap().to_string(),
+            "タンクモデルのタンク番号 2"
+        );
+        assert_eq!(
+            parse((250, 0, 0), MISSING_SURFACE).unwrap().first().name,
+            "Reserved for local use"
+        );
+    }
+
+    // 同じ種類の固定面で挟まれた層は値の範囲、異なる種類なら両方を表示する。
+    #[test]
+    fn layer() {
+        assert_eq!(
+            parse((100, 0, 100000), (100, 0, 50000))
+                .unwrap()
+                .to_string(),
+            "Isobaric surface 1000–500 hPa"
+        );
+        assert_eq!(
+            parse((106, 2, 10), (106, 1, 4)).unwrap().to_string(),
+            "Depth below land surface 0.1–0.4 m"
+        );
+        assert_eq!(
+            parse((1, -1, -1), (8, -1, -1)).unwrap().to_string(),
+            "Ground or water surface – Nominal top of the atmosphere"
+        );
+    }
+
+    // 第一固定面が欠損値ならレベルはない。
+    #[test]
+    fn missing() {
+        assert!(parse(MISSING_SURFACE, MISSING_SURFACE).is_none());
+        assert!(parse(MISSING_SURFACE, (100, 0, 50000)).is_none());
+    }
+}
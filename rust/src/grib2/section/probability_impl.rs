@@ -0,0 +1,99 @@
+//! GRIB2 probability and percentile implementation
+
+use std::fmt;
+
+use super::super::type_utils_impl::scaled_value_be;
+use super::super::type_utils_impl::u8_be;
+use super::Percentile;
+use super::Probability;
+
+// 確率の種類の名称（Code Table 4.9）
+pub(crate) fn probability_type_name(probability_type: usize) -> &'static str {
+    match probability_type {
+        0 => "Probability of event below lower limit",
+        1 => "Probability of event above upper limit",
+        2 => "Probability of event between upper and lower limits",
+        3 => "Probability of event above lower limit",
+        4 => "Probability of event below upper limit",
+        5 => "Probability of event equal to lower limit",
+        6 => "Probability of event in above normal category",
+        7 => "Probability of event in near normal category",
+        8 => "Probability of event in below normal category",
+        9 => "Probability based on counts of categorical boolean",
+        192..=254 => "Reserved for local use",
+        255 => "Missing",
+        _ => "Reserved",
+    }
+}
+
+// 予報確率の番号から始まる13オクテットを確率予報として返す。
+//   0     予報確率の番号
+//   1     予報確率の総数
+//   2     確率の種類
+//   3-7   下限値の尺度因子と尺度付きの値
+//   8-12  上限値の尺度因子と尺度付きの値
+pub(crate) fn probability_be(src: &[u8]) -> Probability {
+    assert!(src.len() == 13);
+
+    let probability_type = u8_be(&src[2..3]) as usize;
+    Probability {
+        forecast_probability_number: u8_be(&src[0..1]) as usize,
+        total_forecast_probabilities: u8_be(&src[1..2]) as usize,
+        probability_type,
+        probability_type_name: probability_type_name(probability_type),
+        lower_limit: scaled_value_be(&src[3..8]),
+        upper_limit: scaled_value_be(&src[8..13]),
+    }
+}
+
+pub(crate) fn percentile_be(src: &[u8]) -> Percentile {
+    assert!(src.len() == 1);
+    Percentile {
+        value: u8_be(src) as usize,
+    }
+}
+
+// 値を単位付きで表す。
+fn format_limit(value: Option<f64>, unit: &str) -> String {
+    match (value, unit) {
+        (Some(value), "") => format!("{}", value),
+        (Some(value), unit) => format!("{} {}", value, unit),
+        (None, _) => String::from("?"),
+    }
+}
+
+impl Probability {
+    // 事象を表す。 e.g. P(Total precipitation > 10 kg m-2)
+    pub fn describe(&self, subject: &str, unit: &str) -> String {
+        let lower = format_limit(self.lower_limit, unit);
+        let upper = format_limit(self.upper_limit, unit);
+        match self.probability_type {
+            0 => format!("P({} < {})", subject, lower),
+            1 => format!("P({} > {})", subject, upper),
+            2 => format!("P({} ≤ {} < {})", lower, subject, upper),
+            3 => format!("P({} > {})", subject, lower),
+            4 => format!("P({} < {})", subject, upper),
+            5 => format!("P({} = {})", subject, lower),
+            _ => format!("{} ({})", self.probability_type_name, subject),
+        }
+    }
+}
+
+impl fmt::Display for Probability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.describe("x", ""))
+    }
+}
+
+impl fmt::Display for Percentile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let suffix = match (self.value % 10, self.value % 100) {
+            (_, 11..=13) => "th",
+            (1, _) => "st",
+            (2, _) => "nd",
+            (3, _) => "rd",
+            _ => "th",
+        };
+        write!(f, "{}{} percentile", self.value, suffix)
+    }
+}
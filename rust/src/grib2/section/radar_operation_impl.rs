@@ -0,0 +1,52 @@
+//! GRIB2 JMA radar operation implementation
+
+use std::fmt;
+
+use super::RadarOperation;
+
+// 気象庁ローカルテンプレートのレイアウトより後ろのオクテットを運用情報として返す。
+// ビットとレーダーサイトや運用状況の対応は確認できていないため、立っているビットの位置だけを返す。
+// オクテットがなければ None を返す。
+pub(crate) fn radar_operation_be(src: &[u8]) -> Option<RadarOperation> {
+    if src.is_empty() {
+        return None;
+    }
+
+    // ビットの位置は先頭オクテットの最上位ビットを1として数える。
+    let bits = (0..src.len() * 8)
+        .filter(|i| src[i / 8] & (0x80 >> (i % 8)) != 0)
+        .map(|i| i + 1)
+        .collect();
+
+    Some(RadarOperation {
+        octets: src.len(),
+        bits,
+    })
+}
+
+// 立っているビットの位置を列挙する。 e.g. 3, 4, 17
+impl fmt::Display for RadarOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bits: Vec<String> = self.bits.iter().map(|x| x.to_string()).collect();
+        write!(f, "{}", bits.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits() {
+        let operation = radar_operation_be(&[0b0011_0000, 0, 0b1000_0001]).unwrap();
+        assert_eq!(operation.octets, 3);
+        assert_eq!(operation.bits, [3, 4, 17, 24]);
+        assert_eq!(operation.to_string(), "3, 4, 17, 24");
+
+        let operation = radar_operation_be(&[0; 16]).unwrap();
+        assert!(operation.bits.is_empty());
+        assert_eq!(operation.to_string(), "");
+
+        assert!(radar_operation_be(&[]).is_none());
+    }
+}
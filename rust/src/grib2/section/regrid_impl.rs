@@ -0,0 +1,379 @@
+//! GRIB2 regridding implementation
+
+use std::str::FromStr;
+
+use super::sample_impl::grid_index;
+use super::section3_template::section3_template_impl::normalize_longitude;
+use super::section3_template::TemplateNumber as Section3TemplateNumber;
+use super::RegridMethod;
+use super::RegridWeights;
+
+const BOUNDARY_POINTS: usize = 8; // 保存型で変換先のセルの一辺あたり変換元の格子に写す点の数
+
+impl FromStr for RegridMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(RegridMethod::Nearest),
+            "bilinear" => Ok(RegridMethod::Bilinear),
+            "conservative" => Ok(RegridMethod::Conservative),
+            _ => Err(format!("Unknown regrid method: {}", s)),
+        }
+    }
+}
+
+// 変換元の格子点の位置（小数）に最も近い格子点
+fn nearest(source: &Section3TemplateNumber, i: f64, j: f64) -> Vec<(usize, f64)> {
+    match grid_index(source, i.round() as isize, j.round() as isize) {
+        Some(k) => vec![(k, 1.0)],
+        None => Vec::new(),
+    }
+}
+
+// 変換元の格子点の位置（小数）を囲む4点と重み
+fn bilinear(source: &Section3TemplateNumber, i: f64, j: f64) -> Vec<(usize, f64)> {
+    let (i0, j0) = (i.floor(), j.floor());
+    let (fi, fj) = (i - i0, j - j0);
+    let (i0, j0) = (i0 as isize, j0 as isize);
+
+    [
+        (i0, j0, (1.0 - fi) * (1.0 - fj)),
+        (i0 + 1, j0, fi * (1.0 - fj)),
+        (i0, j0 + 1, (1.0 - fi) * fj),
+        (i0 + 1, j0 + 1, fi * fj),
+    ]
+    .iter()
+    .filter(|(_, _, w)| 0.0 < *w)
+    .filter_map(|(i, j, w)| Some((grid_index(source, *i, *j)?, *w)))
+    .collect()
+}
+
+// 経緯度平面上の多角形の符号付き面積 [度^2]。反時計回りなら正
+fn signed_area(polygon: &[(f64, f64)]) -> f64 {
+    let n = polygon.len();
+    (0..n)
+        .map(|k| {
+            let (a, b) = (polygon[k], polygon[(k + 1) % n]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+// 経緯度平面上の多角形の球面上の面積 [sr]
+// 辺は経緯度平面上の直線とし、面積 = ∬ cos(緯度) を周回積分 -∮ sin(緯度) d経度 で求める。
+fn spherical_area(polygon: &[(f64, f64)]) -> f64 {
+    let n = polygon.len();
+    let integral: f64 = (0..n)
+        .map(|k| {
+            let (lon0, lat0) = polygon[k];
+            let (lon1, lat1) = polygon[(k + 1) % n];
+            let (lat0, lat1) = (lat0.to_radians(), lat1.to_radians());
+            let dlon = (lon1 - lon0).to_radians();
+            let dlat = lat1 - lat0;
+            if dlat.abs() < 1e-12 {
+                dlon * ((lat0 + lat1) / 2.0).sin()
+            } else {
+                dlon * (lat0.cos() - lat1.cos()) / dlat
+            }
+        })
+        .sum();
+    integral.abs()
+}
+
+// 経緯度平面上の多角形 subject を凸多角形 window で切り取る（Sutherland–Hodgman）。
+fn clip(subject: &[(f64, f64)], window: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let orientation = signed_area(window).signum();
+    if orientation == 0.0 {
+        return Vec::new();
+    }
+    let mut output = subject.to_vec();
+    for k in 0..window.len() {
+        if output.is_empty() {
+            break;
+        }
+        let (a, b) = (window[k], window[(k + 1) % window.len()]);
+        // 辺 a → b の内側なら正
+        let side =
+            |p: (f64, f64)| orientation * ((b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0));
+        let input = std::mem::take(&mut output);
+        for (m, &p) in input.iter().enumerate() {
+            let q = input[(m + 1) % input.len()];
+            let (sp, sq) = (side(p), side(q));
+            if 0.0 <= sp {
+                output.push(p);
+            }
+            if (0.0 <= sp) != (0.0 <= sq) {
+                let t = sp / (sp - sq);
+                output.push((p.0 + (q.0 - p.0) * t, p.1 + (q.1 - p.1) * t));
+            }
+        }
+    }
+    output
+}
+
+// 変換先のセル (i, j) と重なる変換元のセルと重なりの面積 [sr]
+// セルは四隅を経緯度平面上の直線で結んだ四角形とし、変換先と変換元のセルの重なりを切り出して面積を求める。
+fn conservative(
+    source: &Section3TemplateNumber,
+    target: &Section3TemplateNumber,
+    i: usize,
+    j: usize,
+) -> Vec<(usize, f64)> {
+    let cell = target.cell_corners(i, j);
+    let (center, _) = target.ij_to_lonlat(i as f64, j as f64);
+
+    // 変換先のセルの周と中心を変換元の格子に写し、重なりうる変換元のセルの範囲を求める。
+    let mut points: Vec<(f64, f64)> = (0..cell.len())
+        .flat_map(|k| {
+            let (a, b) = (cell[k], cell[(k + 1) % cell.len()]);
+            (0..BOUNDARY_POINTS).map(move |m| {
+                let t = m as f64 / BOUNDARY_POINTS as f64;
+                (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+            })
+        })
+        .collect();
+    points.push(target.ij_to_lonlat(i as f64, j as f64));
+    let positions: Vec<(f64, f64)> = points
+        .iter()
+        .filter_map(|(lon, lat)| source.lonlat_to_ij(*lon, *lat))
+        .collect();
+    let (first, _) = match positions.first() {
+        Some(x) => *x,
+        None => return Vec::new(),
+    };
+    // 周期的なら i は最初の点から連続させる。
+    let period = source.ni() as f64;
+    let periodic = source.is_periodic();
+    let unwrap = |x: f64| {
+        if periodic {
+            first + (x - first + period / 2.0).rem_euclid(period) - period / 2.0
+        } else {
+            x
+        }
+    };
+    let (i0, i1, j0, j1) = positions.iter().fold(
+        (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+        |(i0, i1, j0, j1), (i, j)| {
+            let i = unwrap(*i);
+            (i0.min(i), i1.max(i), j0.min(*j), j1.max(*j))
+        },
+    );
+    let i0 = i0.round() as isize - 1;
+    let i1 = (i1.round() as isize + 1).min(i0 + source.ni() as isize - 1);
+    let (j0, j1) = (j0.round() as isize - 1, j1.round() as isize + 1);
+
+    let mut weights: Vec<(usize, f64)> = Vec::new();
+    for sj in j0..=j1 {
+        for si in i0..=i1 {
+            let k = match grid_index(source, si, sj) {
+                Some(k) => k,
+                None => continue,
+            };
+            let (ci, cj) = (k % source.ni(), k / source.ni());
+            // 変換元のセルの経度を変換先のセルの中心から連続させる。
+            let (source_center, _) = source.ij_to_lonlat(ci as f64, cj as f64);
+            let shift = center + normalize_longitude(source_center - center) - source_center;
+            let window: Vec<(f64, f64)> = source
+                .cell_corners(ci, cj)
+                .iter()
+                .map(|(lon, lat)| (lon + shift, *lat))
+                .collect();
+            let overlap = clip(&cell, &window);
+            if overlap.len() < 3 {
+                continue;
+            }
+            let area = spherical_area(&overlap);
+            if 0.0 < area {
+                weights.push((k, area));
+            }
+        }
+    }
+    weights
+}
+
+// 変換元の格子系から変換先の格子系への重みを返す。
+pub(crate) fn regrid_weights(
+    source: &Section3TemplateNumber,
+    target: &Section3TemplateNumber,
+    method: RegridMethod,
+) -> RegridWeights {
+    let (ni, nj) = (target.ni(), target.nj());
+    let mut weights = Vec::with_capacity(ni * nj);
+    for j in 0..nj {
+        for i in 0..ni {
+            let w = match method {
+                RegridMethod::Conservative => conservative(source, target, i, j),
+                _ => {
+                    let (lon, lat) = target.ij_to_lonlat(i as f64, j as f64);
+                    match source.lonlat_to_ij(lon, lat) {
+                        Some((si, sj)) if method == RegridMethod::Nearest => {
+                            nearest(source, si, sj)
+                        }
+                        Some((si, sj)) => bilinear(source, si, sj),
+                        None => Vec::new(),
+                    }
+                }
+            };
+            weights.push(w);
+        }
+    }
+
+    RegridWeights {
+        ni,
+        nj,
+        source_count: source.ni() * source.nj(),
+        weights,
+    }
+}
+
+impl RegridWeights {
+    // 変換元の値を変換先の格子系の値にする。
+    // 欠測（NaN）の格子点を除いて重みを正規化し、有効な格子点がなければ NaN とする。
+    pub(crate) fn apply(&self, values: &[f32]) -> Result<Vec<f32>, String> {
+        if values.len() != self.source_count {
+            return Err(format!(
+                "Invalid source value count: {} (expected {})",
+                values.len(),
+                self.source_count
+            ));
+        }
+
+        Ok(self
+            .weights
+            .iter()
+            .map(|weights| {
+                let mut sum = 0.0;
+                let mut weight_sum = 0.0;
+                for (k, w) in weights.iter() {
+                    let value = values[*k];
+                    if !value.is_nan() {
+                        sum += value as f64 * w;
+                        weight_sum += w;
+                    }
+                }
+                if weight_sum <= 0.0 {
+                    f32::NAN
+                } else {
+                    (sum / weight_sum) as f32
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::super::super::utils_impl::tests::latlon_grid;
+    use super::super::Section3;
+    use super::*;
+
+    fn weights(source: &[u8], target: &[u8], method: RegridMethod) -> RegridWeights {
+        let source = Section3::create(source).template().unwrap();
+        let target = Section3::create(target).template().unwrap();
+        regrid_weights(&source, &target, method)
+    }
+
+    // 北緯40度、東経130度から 1 度間隔で 5 x 4 の格子と、値 2i + 3j
+    fn linear() -> (Vec<u8>, Vec<f32>) {
+        let buf = latlon_grid(5, 4, 40.0, 130.0, 1.0, 1.0);
+        let values = (0..20)
+            .map(|n| (2 * (n % 5) + 3 * (n / 5)) as f32)
+            .collect();
+        (buf, values)
+    }
+
+    #[test]
+    fn nearest_weights() {
+        let (source, _) = linear();
+        let target = latlon_grid(3, 2, 39.6, 130.6, 2.0, 2.0);
+        let weights = weights(&source, &target, RegridMethod::Nearest);
+        assert_eq!((weights.ni, weights.nj, weights.source_count), (3, 2, 20));
+        assert_eq!(
+            weights.weights,
+            [
+                vec![(1, 1.0)],
+                vec![(3, 1.0)],
+                Vec::new(), // 変換元の格子の外
+                vec![(11, 1.0)],
+                vec![(13, 1.0)],
+                Vec::new(),
+            ]
+        );
+    }
+
+    // 双線形補間の重みの和は 1 で、1次式を再現する。格子の外は NaN
+    #[test]
+    fn bilinear_weights() {
+        let (source, values) = linear();
+        let target = latlon_grid(3, 2, 39.5, 130.5, 2.5, 1.0);
+        let weights = weights(&source, &target, RegridMethod::Bilinear);
+        // 格子点の列の上なら重みのない2点を除く。
+        for (w, count) in weights.weights.iter().zip(&[4, 2, 0]) {
+            assert_eq!(w.len(), *count);
+            if 0 < *count {
+                assert!((w.iter().map(|(_, w)| w).sum::<f64>() - 1.0).abs() < 1e-9);
+            }
+        }
+
+        let result = weights.apply(&values).unwrap();
+        let expected = [2.5, 7.5, f32::NAN, 5.5, 10.5, f32::NAN];
+        for (value, expected) in result.iter().zip(&expected) {
+            if expected.is_nan() {
+                assert!(value.is_nan());
+            } else {
+                assert!((value - expected).abs() < 1e-5, "{} != {}", value, expected);
+            }
+        }
+    }
+
+    // 保存型の重みの和は変換先のセルの面積に等しく、定数の場は変わらない。
+    #[test]
+    fn conservative_weights() {
+        let source = latlon_grid(6, 6, 40.0, 130.0, 1.0, 1.0);
+        let target = latlon_grid(2, 2, 39.5, 131.5, 2.0, 2.0);
+        let weights = weights(&source, &target, RegridMethod::Conservative);
+
+        let grid = Section3::create(&target).template().unwrap();
+        for (k, w) in weights.weights.iter().enumerate() {
+            let area = spherical_area(&grid.cell_corners(k % 2, k / 2));
+            let sum: f64 = w.iter().map(|(_, w)| w).sum();
+            assert!((sum - area).abs() < area * 1e-9, "{} != {}", sum, area);
+        }
+
+        let result = weights.apply(&[3.0; 36]).unwrap();
+        assert!(result.iter().all(|x| (x - 3.0).abs() < 1e-6));
+    }
+
+    // 欠測の格子点を除いて正規化し、値の数が合わなければエラー
+    #[test]
+    fn apply() {
+        let weights = RegridWeights {
+            ni: 2,
+            nj: 1,
+            source_count: 3,
+            weights: vec![vec![(0, 1.0), (1, 3.0)], vec![(2, 1.0)]],
+        };
+        assert_eq!(weights.apply(&[1.0, 5.0, 7.0]).unwrap(), [4.0, 7.0]);
+        let result = weights.apply(&[1.0, f32::NAN, f32::NAN]).unwrap();
+        assert_eq!(result[0], 1.0);
+        assert!(result[1].is_nan());
+        assert!(weights.apply(&[1.0, 5.0]).is_err());
+    }
+
+    #[test]
+    fn polygons() {
+        // 北半球の面積は 2π
+        let hemisphere = [(0.0, 0.0), (360.0, 0.0), (360.0, 90.0), (0.0, 90.0)];
+        assert!((spherical_area(&hemisphere) - 2.0 * PI).abs() < 1e-9);
+
+        // 重なる2つの正方形の切り取りは一辺 1 の正方形。向きによらない
+        let a = [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+        let b = [(1.0, 1.0), (1.0, 3.0), (3.0, 3.0), (3.0, 1.0)];
+        assert!((signed_area(&clip(&a, &b)).abs() - 1.0).abs() < 1e-9);
+        assert!(clip(&a, &[(5.0, 5.0), (6.0, 5.0), (6.0, 6.0)]).is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+//! GRIB2 grid point sampling implementation
+
+use std::str::FromStr;
+
+use super::section3_template::TemplateNumber as Section3TemplateNumber;
+use super::Interpolation;
+
+impl FromStr for Interpolation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(Interpolation::Nearest),
+            "bilinear" => Ok(Interpolation::Bilinear),
+            "bicubic" => Ok(Interpolation::Bicubic),
+            _ => Err(format!("Unknown interpolation method: {}", s)),
+        }
+    }
+}
+
+// 格子点 (i, j) の資料の並び順での位置を返す。周期的なら i を折り返す。格子の外なら None を返す。
+pub(crate) fn grid_index(geometry: &Section3TemplateNumber, i: isize, j: isize) -> Option<usize> {
+    let (ni, nj) = (geometry.ni() as isize, geometry.nj() as isize);
+    let i = if geometry.is_periodic() {
+        i.rem_euclid(ni)
+    } else {
+        i
+    };
+    if i < 0 || ni <= i || j < 0 || nj <= j {
+        return None;
+    }
+    Some((j * ni + i) as usize)
+}
+
+// 格子点 (i, j) の値を返す。格子の外や欠測なら None を返す。
+fn value_at(geometry: &Section3TemplateNumber, values: &[f32], i: isize, j: isize) -> Option<f64> {
+    let value = *values.get(grid_index(geometry, i, j)?)?;
+    if value.is_nan() {
+        None
+    } else {
+        Some(value as f64)
+    }
+}
+
+fn nearest(geometry: &Section3TemplateNumber, values: &[f32], i: f64, j: f64) -> Option<f64> {
+    value_at(geometry, values, i.round() as isize, j.round() as isize)
+}
+
+// 周囲4点の重み付き平均。欠測の点を除いて重みを正規化する。
+fn bilinear(geometry: &Section3TemplateNumber, values: &[f32], i: f64, j: f64) -> Option<f64> {
+    let (i0, j0) = (i.floor(), j.floor());
+    let (fi, fj) = (i - i0, j - j0);
+    let (i0, j0) = (i0 as isize, j0 as isize);
+
+    let neighbors = [
+        (i0, j0, (1.0 - fi) * (1.0 - fj)),
+        (i0 + 1, j0, fi * (1.0 - fj)),
+        (i0, j0 + 1, (1.0 - fi) * fj),
+        (i0 + 1, j0 + 1, fi * fj),
+    ];
+
+    let mut sum = 0.0;
+    let mut weight_sum = 0.0;
+    for (i, j, weight) in neighbors.iter() {
+        if *weight <= 0.0 {
+            continue;
+        }
+        if let Some(value) = value_at(geometry, values, *i, *j) {
+            sum += value * weight;
+            weight_sum += weight;
+        }
+    }
+
+    if weight_sum <= 0.0 {
+        None
+    } else {
+        Some(sum / weight_sum)
+    }
+}
+
+// Catmull-Rom スプラインの重み
+fn cubic_weights(t: f64) -> [f64; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        (-t3 + 2.0 * t2 - t) / 2.0,
+        (3.0 * t3 - 5.0 * t2 + 2.0) / 2.0,
+        (-3.0 * t3 + 4.0 * t2 + t) / 2.0,
+        (t3 - t2) / 2.0,
+    ]
+}
+
+// 周囲16点による3次補間。1点でも欠けていれば双線形補間にする。
+fn bicubic(geometry: &Section3TemplateNumber, values: &[f32], i: f64, j: f64) -> Option<f64> {
+    let (i0, j0) = (i.floor(), j.floor());
+    let wi = cubic_weights(i - i0);
+    let wj = cubic_weights(j - j0);
+    let (i0, j0) = (i0 as isize, j0 as isize);
+
+    let mut sum = 0.0;
+    for (dj, wj) in wj.iter().enumerate() {
+        for (di, wi) in wi.iter().enumerate() {
+            let value = value_at(geometry, values, i0 + di as isize - 1, j0 + dj as isize - 1);
+            match value {
+                Some(value) => sum += value * wi * wj,
+                None => return bilinear(geometry, values, i, j),
+            }
+        }
+    }
+    Some(sum)
+}
+
+// 経度と緯度の値を補間して返す。格子の外や欠測なら None を返す。
+pub(crate) fn sample(
+    geometry: &Section3TemplateNumber,
+    values: &[f32],
+    lon: f64,
+    lat: f64,
+    method: Interpolation,
+) -> Option<f64> {
+    let (i, j) = geometry.lonlat_to_ij(lon, lat)?;
+    match method {
+        Interpolation::Nearest => nearest(geometry, values, i, j),
+        Interpolation::Bilinear => bilinear(geometry, values, i, j),
+        Interpolation::Bicubic => bicubic(geometry, values, i, j),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::utils_impl::tests::latlon_grid;
+    use super::super::Section3;
+    use super::*;
+
+    // 北緯40度、東経130度から 1 度間隔で 5 x 4 の格子。値は 2i + 3j
+    fn linear() -> (Vec<u8>, Vec<f32>) {
+        let buf = latlon_grid(5, 4, 40.0, 130.0, 1.0, 1.0);
+        let values = (0..20)
+            .map(|n| (2 * (n % 5) + 3 * (n / 5)) as f32)
+            .collect();
+        (buf, values)
+    }
+
+    fn assert_near(a: Option<f64>, b: f64) {
+        assert!((a.unwrap() - b).abs() < 1e-9, "{:?} != {}", a, b);
+    }
+
+    #[test]
+    fn nearest() {
+        let (buf, values) = linear();
+        let geometry = Section3::create(&buf).template().unwrap();
+        let sample = |lon, lat| sample(&geometry, &values, lon, lat, Interpolation::Nearest);
+        assert_eq!(sample(131.4, 38.6), Some(2.0 + 3.0));
+        assert_eq!(sample(131.6, 38.4), Some(4.0 + 6.0));
+        assert_eq!(sample(120.0, 38.0), None);
+    }
+
+    // 双線形補間は1次式を再現し、欠測の点を除いて重みを正規化する。
+    #[test]
+    fn bilinear() {
+        let (buf, mut values) = linear();
+        let geometry = Section3::create(&buf).template().unwrap();
+        let sample =
+            |values: &[f32], lon, lat| sample(&geometry, values, lon, lat, Interpolation::Bilinear);
+        assert_near(sample(&values, 131.25, 38.5), 2.5 + 4.5);
+        assert_near(sample(&values, 134.0, 37.0), 8.0 + 9.0);
+
+        // (1, 1) が欠測なら残りの3点で平均する。
+        values[6] = f32::NAN;
+        assert_near(sample(&values, 131.5, 38.5), (7.0 + 8.0 + 10.0) / 3.0);
+        // 欠測の格子点そのものは値がない。
+        assert_eq!(sample(&values, 131.0, 39.0), None);
+    }
+
+    // 3次補間は1次式を再現し、周囲16点が揃わなければ双線形補間にする。
+    #[test]
+    fn bicubic() {
+        let (buf, values) = linear();
+        let geometry = Section3::create(&buf).template().unwrap();
+        let sample = |values: &[f32], lon, lat, method| sample(&geometry, values, lon, lat, method);
+        assert_near(
+            sample(&values, 131.3, 38.8, Interpolation::Bicubic),
+            2.6 + 3.6,
+        );
+
+        // 1点だけ大きな値を持つ格子では、3次補間は周囲の点の外まで影響する。
+        let mut peak = vec![0.0; 20];
+        peak[8] = 1.0; // (3, 1)
+        let bicubic = sample(&peak, 131.5, 38.5, Interpolation::Bicubic).unwrap();
+        let bilinear = sample(&peak, 131.5, 38.5, Interpolation::Bilinear).unwrap();
+        assert_near(Some(bicubic), -0.0625 * 0.5625);
+        assert_eq!(bilinear, 0.0);
+
+        // 格子の端では双線形補間と同じ
+        assert_eq!(
+            sample(&values, 130.5, 39.5, Interpolation::Bicubic),
+            sample(&values, 130.5, 39.5, Interpolation::Bilinear)
+        );
+    }
+
+    // 経度を一周する格子は最後の列と最初の列の間も補間する。
+    #[test]
+    fn periodic() {
+        let buf = latlon_grid(4, 2, 10.0, 0.0, 90.0, 10.0);
+        let geometry = Section3::create(&buf).template().unwrap();
+        let values = [0.0, 1.0, 2.0, 3.0, 0.0, 1.0, 2.0, 3.0];
+        let bilinear = sample(&geometry, &values, -45.0, 5.0, Interpolation::Bilinear);
+        assert_near(bilinear, 1.5);
+        let nearest = sample(&geometry, &values, -30.0, 10.0, Interpolation::Nearest);
+        assert_eq!(nearest, Some(0.0));
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("bicubic".parse(), Ok(Interpolation::Bicubic));
+        assert!("cubic".parse::<Interpolation>().is_err());
+    }
+}
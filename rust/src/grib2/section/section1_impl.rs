@@ -4,6 +4,7 @@ use chrono::{DateTime, Utc};
 use std::fmt;
 
 use super::super::type_utils_impl::datetime_be;
+use super::super::type_utils_impl::u16_be;
 use super::super::type_utils_impl::u32_be;
 use super::super::type_utils_impl::u8_be;
 use super::Section;
@@ -14,6 +15,11 @@ impl<'a> Section1<'a> {
         Self { buf: buf }
     }
 
+    // Identification of originating/generating centre (see Common Code Table C-11)
+    pub(crate) fn centre(&self) -> usize {
+        u16_be(&self.buf[5..7]) as usize
+    }
+
     // Reference time of data
     pub(crate) fn reference_time(&self) -> DateTime<Utc> {
         datetime_be(&self.buf[12..19])
@@ -6,6 +6,11 @@ use super::super::type_utils_impl::i32_be;
 use super::super::type_utils_impl::u16_be;
 use super::super::type_utils_impl::u32_be;
 use super::super::type_utils_impl::u8_be;
+use super::section3_template::Template0;
+use super::section3_template::Template10;
+use super::section3_template::Template20;
+use super::section3_template::Template30;
+use super::section3_template::TemplateNumber;
 use super::Section;
 use super::Section3;
 
@@ -15,10 +20,30 @@ impl<'a> Section3<'a> {
     }
 
     // Grid definition template number (= N) (see Code table 3.1)
-    fn template_number(&self) -> usize {
+    pub(crate) fn template_number(&self) -> usize {
         u16_be(&self.buf[12..14]) as usize
     }
 
+    // return template
+    // j 方向に連続して並ぶ格子（0x20）と、隣り合う行の走査方向が逆の格子（0x10）は扱わない。
+    pub(crate) fn template(&self) -> Option<TemplateNumber<'a>> {
+        if self.scanning_mode()? & 0x30 != 0 {
+            return None;
+        }
+        match self.template_number() {
+            0 => Some(TemplateNumber::T0(Template0 { buf: self.buf })),
+            10 => Some(TemplateNumber::T10(Template10 { buf: self.buf })),
+            20 => Some(TemplateNumber::T20(Template20 { buf: self.buf })),
+            30 => Some(TemplateNumber::T30(Template30 { buf: self.buf })),
+            _ => None,
+        }
+    }
+
+    // 節の長さと節番号を除いた格子系定義。同じ格子系かどうかの比較に使う。
+    pub(crate) fn grid_definition(&self) -> &'a [u8] {
+        &self.buf[5..]
+    }
+
     // Number of data points
     pub(crate) fn point_count(&self) -> usize {
         u32_be(&self.buf[6..10]) as usize
@@ -63,6 +88,18 @@ impl<'a> Section3<'a> {
     pub(crate) fn dj(&self) -> usize {
         u32_be(&self.buf[67..71]) as usize
     }
+
+    // Scanning mode (flags – see Flag Table 3.4)
+    // 位置はテンプレートごとに異なる。対応していないテンプレートなら None を返す。
+    pub(crate) fn scanning_mode(&self) -> Option<u8> {
+        let offset = match self.template_number() {
+            0 => 71,
+            10 => 59,
+            20 | 30 => 64,
+            _ => return None,
+        };
+        Some(u8_be(self.buf.get(offset..offset + 1)?))
+    }
 }
 
 impl<'a> Section for Section3<'a> {
@@ -137,3 +174,47 @@ impl fmt::Debug for Section3<'_> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::utils_impl::tests::latlon_grid;
+    use super::*;
+
+    // 走査モードを書き換えた格子系定義
+    fn grid(template: u16, length: usize, offset: usize, scanning_mode: u8) -> Vec<u8> {
+        let mut buf = vec![0; length];
+        buf[12..14].copy_from_slice(&template.to_be_bytes());
+        buf[offset] = scanning_mode;
+        buf
+    }
+
+    #[test]
+    fn scanning_mode() {
+        let mut buf = latlon_grid(3, 2, 36.0, 139.0, 1.0, 1.0);
+        assert_eq!(Section3::create(&buf).scanning_mode(), Some(0x00));
+        assert!(Section3::create(&buf).template().is_some());
+        buf[71] = 0x40;
+        assert!(Section3::create(&buf).template().is_some());
+
+        // 0x10 と 0x20 の格子は扱わない。
+        for flag in [0x10, 0x20] {
+            buf[71] = flag;
+            assert!(Section3::create(&buf).template().is_none());
+        }
+
+        for (template, length, offset) in [(10, 72, 59), (20, 65, 64), (30, 81, 64)] {
+            let buf = grid(template, length, offset, 0x40);
+            assert_eq!(Section3::create(&buf).scanning_mode(), Some(0x40));
+            assert!(Section3::create(&buf).template().is_some());
+            for flag in [0x50, 0x60] {
+                let buf = grid(template, length, offset, flag);
+                assert!(Section3::create(&buf).template().is_none());
+            }
+        }
+
+        // 対応していないテンプレート
+        let buf = grid(40, 72, 71, 0x00);
+        assert_eq!(Section3::create(&buf).scanning_mode(), None);
+        assert!(Section3::create(&buf).template().is_none());
+    }
+}
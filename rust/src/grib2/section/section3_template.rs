@@ -0,0 +1,91 @@
+//! GRIB2 Section3 template declaration
+
+pub(crate) mod section3_template_impl;
+
+#[derive(Copy, Clone)]
+pub(crate) enum TemplateNumber<'a> {
+    T0(Template0<'a>),   // 3.0
+    T10(Template10<'a>), // 3.10
+    T20(Template20<'a>), // 3.20
+    T30(Template30<'a>), // 3.30
+}
+
+// 格子点の位置と経緯度の対応
+// 格子点の位置 (i, j) は資料の並び順で、値 = j * Ni + i の要素にあたる。
+// 経度は -180 以上 180 未満に正規化して返す。
+pub(crate) trait GridGeometry {
+    // i 方向と j 方向の格子点数
+    fn ni(&self) -> usize;
+    fn nj(&self) -> usize;
+
+    // i 方向が経度を一周していれば true を返す。
+    fn is_periodic(&self) -> bool;
+
+    // 格子点の位置（小数可）から経度と緯度を返す。
+    fn ij_to_lonlat(&self, i: f64, j: f64) -> (f64, f64);
+
+    // 経度と緯度から格子点の位置（小数）を返す。格子の外なら None を返す。
+    fn lonlat_to_ij(&self, lon: f64, lat: f64) -> Option<(f64, f64)>;
+}
+
+// 格子の座標参照系。角度は度、長さは m で表す。地球は半径 radius の球とする。
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Crs {
+    Geographic, // 経緯度
+    Mercator {
+        radius: f64,
+        lon0: f64, // 中心の経度
+        lad: f64,  // 標準緯線
+    },
+    PolarStereographic {
+        radius: f64,
+        lov: f64, // 中心の経度
+        lad: f64, // 縮尺が正しい緯度。南極中心なら負
+    },
+    LambertConformal {
+        radius: f64,
+        lov: f64,            // 中心の経度
+        latin1: f64,         // 標準緯線
+        latin2: f64,         // 標準緯線
+        false_northing: f64, // 原点を第1標準緯線とした北距の補正
+    },
+}
+
+// 格子点の位置 (i, j) から座標参照系の座標 (x, y) へのアフィン変換
+//   x = x1 + i * dx, y = y1 + j * dy
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct GridTransform {
+    pub(crate) crs: Crs,
+    pub(crate) x1: f64,
+    pub(crate) y1: f64,
+    pub(crate) dx: f64,
+    pub(crate) dy: f64,
+}
+
+// template 3.0
+// Latitude/longitude (or equidistant cylindrical, or Plate Carrée)
+#[derive(Copy, Clone)]
+pub(crate) struct Template0<'a> {
+    pub(crate) buf: &'a [u8],
+}
+
+// template 3.10
+// Mercator
+#[derive(Copy, Clone)]
+pub(crate) struct Template10<'a> {
+    pub(crate) buf: &'a [u8],
+}
+
+// template 3.20
+// Polar stereographic projection
+#[derive(Copy, Clone)]
+pub(crate) struct Template20<'a> {
+    pub(crate) buf: &'a [u8],
+}
+
+// template 3.30
+// Lambert conformal
+#[derive(Copy, Clone)]
+pub(crate) struct Template30<'a> {
+    pub(crate) buf: &'a [u8],
+}
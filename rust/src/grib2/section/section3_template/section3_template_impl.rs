@@ -0,0 +1,804 @@
+//! GRIB2 Section3 template implementation
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use super::super::super::type_utils_impl::i32_be;
+use super::super::super::type_utils_impl::i8_be;
+use super::super::super::type_utils_impl::u32_be;
+use super::super::super::type_utils_impl::u8_be;
+use super::Crs;
+use super::GridGeometry;
+use super::GridTransform;
+use super::Template0;
+use super::Template10;
+use super::Template20;
+use super::Template30;
+use super::TemplateNumber;
+
+const MICRO: f64 = 1e-6; // 経緯度の単位（10^-6 度）
+const MILLI: f64 = 1e-3; // 格子間隔の単位（10^-3 m）
+
+// 経度を -180 以上 180 未満に正規化する。
+pub(crate) fn normalize_longitude(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+// 角度 [rad] を -π 以上 π 未満に正規化する。
+fn normalize_radian(angle: f64) -> f64 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+// 地球の半径 [m]（Code Table 3.2）
+// 回転楕円体は平均半径 (2a + b) / 3 の球で近似する。
+fn earth_radius(buf: &[u8]) -> f64 {
+    let scaled = |factor: usize, value: usize| {
+        let factor = i8_be(&buf[factor..factor + 1]) as i32;
+        u32_be(&buf[value..value + 4]) as f64 / 10f64.powi(factor)
+    };
+    let spheroid = |a: f64, b: f64| (2.0 * a + b) / 3.0;
+
+    match u8_be(&buf[14..15]) {
+        0 => 6367470.0,
+        1 => scaled(15, 16),
+        2 => spheroid(6378160.0, 6356775.0),
+        3 => spheroid(scaled(20, 21), scaled(25, 26)) * 1000.0,
+        4 | 5 => spheroid(6378137.0, 6356752.314),
+        7 => spheroid(scaled(20, 21), scaled(25, 26)),
+        8 => 6371200.0,
+        _ => 6371229.0,
+    }
+}
+
+// 走査モード（Flag Table 3.4）から i, j 方向の符号を返す。
+//   bit 1  0: +i 方向（西から東）, 1: -i 方向
+//   bit 2  0: -j 方向（北から南）, 1: +j 方向
+fn scan_signs(scanning_mode: u8) -> (f64, f64) {
+    let si = if scanning_mode & 0x80 == 0 { 1.0 } else { -1.0 };
+    let sj = if scanning_mode & 0x40 == 0 { -1.0 } else { 1.0 };
+    (si, sj)
+}
+
+// 格子の範囲内か。周期的なら i は一周分の範囲に折り返す。
+fn within(i: f64, j: f64, ni: usize, nj: usize, period: Option<f64>) -> Option<(f64, f64)> {
+    let i = match period {
+        Some(period) => (i + 0.5).rem_euclid(period) - 0.5,
+        None => i,
+    };
+
+    let inside = |x: f64, n: usize| -0.5 <= x && x <= n as f64 - 0.5;
+    if inside(i, ni) && inside(j, nj) {
+        Some((i, j))
+    } else {
+        None
+    }
+}
+
+// 格子点数から格子間隔の数を返す。
+fn step_count(n: usize) -> f64 {
+    if n < 2 {
+        1.0
+    } else {
+        (n - 1) as f64
+    }
+}
+
+impl<'a> TemplateNumber<'a> {
+    fn geometry(&self) -> &dyn GridGeometry {
+        match self {
+            TemplateNumber::T0(t) => t,
+            TemplateNumber::T10(t) => t,
+            TemplateNumber::T20(t) => t,
+            TemplateNumber::T30(t) => t,
+        }
+    }
+
+    pub(crate) fn ni(&self) -> usize {
+        self.geometry().ni()
+    }
+
+    pub(crate) fn nj(&self) -> usize {
+        self.geometry().nj()
+    }
+
+    pub(crate) fn is_periodic(&self) -> bool {
+        self.geometry().is_periodic()
+    }
+
+    pub(crate) fn ij_to_lonlat(&self, i: f64, j: f64) -> (f64, f64) {
+        self.geometry().ij_to_lonlat(i, j)
+    }
+
+    pub(crate) fn lonlat_to_ij(&self, lon: f64, lat: f64) -> Option<(f64, f64)> {
+        self.geometry().lonlat_to_ij(lon, lat)
+    }
+
+    // 格子点の位置から座標参照系の座標へのアフィン変換
+    pub(crate) fn transform(&self) -> GridTransform {
+        match self {
+            TemplateNumber::T0(t) => t.transform(),
+            TemplateNumber::T10(t) => t.transform(),
+            TemplateNumber::T20(t) => t.transform(),
+            TemplateNumber::T30(t) => t.transform(),
+        }
+    }
+
+    // 全格子点の経度と緯度を資料の並び順で返す。
+    pub(crate) fn coordinates(&self) -> (Vec<f64>, Vec<f64>) {
+        let (ni, nj) = (self.ni(), self.nj());
+        let mut lons = Vec::with_capacity(ni * nj);
+        let mut lats = Vec::with_capacity(ni * nj);
+        for j in 0..nj {
+            for i in 0..ni {
+                let (lon, lat) = self.ij_to_lonlat(i as f64, j as f64);
+                lons.push(lon);
+                lats.push(lat);
+            }
+        }
+        (lons, lats)
+    }
+
+    // 格子点を中心とするセルの四隅の経度と緯度を返す。
+    // 日付変更線をまたぐセルが途切れないよう、隅の経度は中心の経度から連続させるため ±180 を超えることがある。
+    pub(crate) fn cell_corners(&self, i: usize, j: usize) -> [(f64, f64); 4] {
+        let (i, j) = (i as f64, j as f64);
+        let (center, _) = self.ij_to_lonlat(i, j);
+        let corner = |di: f64, dj: f64| {
+            let (lon, lat) = self.ij_to_lonlat(i + di, j + dj);
+            (
+                center + normalize_longitude(lon - center),
+                lat.clamp(-90.0, 90.0),
+            )
+        };
+
+        [
+            corner(-0.5, -0.5),
+            corner(0.5, -0.5),
+            corner(0.5, 0.5),
+            corner(-0.5, 0.5),
+        ]
+    }
+}
+
+// template 3.0
+// Latitude/longitude (or equidistant cylindrical, or Plate Carrée)
+impl<'a> Template0<'a> {
+    // 経緯度の単位 [度]。基本角が 0 または欠損値なら 10^-6 度
+    fn unit(&self) -> f64 {
+        let basic_angle = u32_be(&self.buf[38..42]);
+        let subdivisions = u32_be(&self.buf[42..46]);
+        match (basic_angle, subdivisions) {
+            (0, _) | (u32::MAX, _) | (_, 0) | (_, u32::MAX) => MICRO,
+            (basic_angle, subdivisions) => basic_angle as f64 / subdivisions as f64,
+        }
+    }
+
+    // Lo1, La1 – longitude and latitude of first grid point [度]
+    fn first_point(&self) -> (f64, f64) {
+        let unit = self.unit();
+        (
+            i32_be(&self.buf[50..54]) as f64 * unit,
+            i32_be(&self.buf[46..50]) as f64 * unit,
+        )
+    }
+
+    // 符号付きの i, j 方向の格子間隔 [度]
+    // 格子間隔が欠損値なら最初と最後の格子点から求める。
+    fn increments(&self) -> (f64, f64) {
+        let unit = self.unit();
+        let (si, sj) = scan_signs(u8_be(&self.buf[71..72]));
+        let (lo1, la1) = self.first_point();
+        let la2 = i32_be(&self.buf[55..59]) as f64 * unit;
+        let lo2 = i32_be(&self.buf[59..63]) as f64 * unit;
+
+        let di = match u32_be(&self.buf[63..67]) {
+            u32::MAX => ((lo2 - lo1) * si).rem_euclid(360.0) / step_count(self.ni()),
+            di => di as f64 * unit,
+        };
+        let dj = match u32_be(&self.buf[67..71]) {
+            u32::MAX => (la2 - la1).abs() / step_count(self.nj()),
+            dj => dj as f64 * unit,
+        };
+        (di * si, dj * sj)
+    }
+
+    fn transform(&self) -> GridTransform {
+        let (lo1, la1) = self.first_point();
+        let (dx, dy) = self.increments();
+        GridTransform {
+            crs: Crs::Geographic,
+            x1: lo1,
+            y1: la1,
+            dx,
+            dy,
+        }
+    }
+}
+
+impl<'a> GridGeometry for Template0<'a> {
+    // Ni – number of points along a parallel
+    fn ni(&self) -> usize {
+        u32_be(&self.buf[30..34]) as usize
+    }
+
+    // Nj – number of points along a meridian
+    fn nj(&self) -> usize {
+        u32_be(&self.buf[34..38]) as usize
+    }
+
+    fn is_periodic(&self) -> bool {
+        let (di, _) = self.increments();
+        (self.ni() as f64 * di.abs() - 360.0).abs() < di.abs() * 0.5
+    }
+
+    fn ij_to_lonlat(&self, i: f64, j: f64) -> (f64, f64) {
+        let (lo1, la1) = self.first_point();
+        let (di, dj) = self.increments();
+        (normalize_longitude(lo1 + i * di), la1 + j * dj)
+    }
+
+    fn lonlat_to_ij(&self, lon: f64, lat: f64) -> Option<(f64, f64)> {
+        let (lo1, la1) = self.first_point();
+        let (di, dj) = self.increments();
+
+        // 経度は最初の格子点から走査方向に測り、範囲外なら一周前として扱う。
+        let mut i = ((lon - lo1) * di.signum()).rem_euclid(360.0) / di.abs();
+        if self.ni() as f64 - 0.5 < i {
+            i -= 360.0 / di.abs();
+        }
+        let j = (lat - la1) / dj;
+
+        let period = if self.is_periodic() {
+            Some(self.ni() as f64)
+        } else {
+            None
+        };
+        within(i, j, self.ni(), self.nj(), period)
+    }
+}
+
+// 地図投影
+trait Projection {
+    // 経度と緯度 [rad] から投影面の座標 [m] を返す。
+    fn forward(&self, lon: f64, lat: f64) -> (f64, f64);
+
+    // 投影面の座標 [m] から経度と緯度 [rad] を返す。
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64);
+}
+
+// 投影面上で等間隔の格子
+struct ProjectedGrid<P: Projection> {
+    projection: P,
+    ni: usize,
+    nj: usize,
+    x1: f64, // 最初の格子点の座標 [m]
+    y1: f64,
+    dx: f64, // 符号付きの格子間隔 [m]
+    dy: f64,
+    period: Option<f64>, // i 方向に一周する格子点数
+}
+
+impl<P: Projection> ProjectedGrid<P> {
+    // 最初の格子点 (La1, Lo1) [10^-6 度] と格子間隔 [10^-3 m] と走査モードから格子を返す。
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        projection: P,
+        ni: usize,
+        nj: usize,
+        la1: isize,
+        lo1: isize,
+        dx: usize,
+        dy: usize,
+        scanning_mode: u8,
+    ) -> Self {
+        let (x1, y1) = projection.forward(
+            (lo1 as f64 * MICRO).to_radians(),
+            (la1 as f64 * MICRO).to_radians(),
+        );
+        let (si, sj) = scan_signs(scanning_mode);
+        Self {
+            projection,
+            ni,
+            nj,
+            x1,
+            y1,
+            dx: dx as f64 * MILLI * si,
+            dy: dy as f64 * MILLI * sj,
+            period: None,
+        }
+    }
+
+    fn transform(&self, crs: Crs) -> GridTransform {
+        GridTransform {
+            crs,
+            x1: self.x1,
+            y1: self.y1,
+            dx: self.dx,
+            dy: self.dy,
+        }
+    }
+
+    fn ij_to_lonlat(&self, i: f64, j: f64) -> (f64, f64) {
+        let (lon, lat) = self
+            .projection
+            .inverse(self.x1 + i * self.dx, self.y1 + j * self.dy);
+        (normalize_longitude(lon.to_degrees()), lat.to_degrees())
+    }
+
+    fn lonlat_to_ij(&self, lon: f64, lat: f64) -> Option<(f64, f64)> {
+        let (x, y) = self.projection.forward(lon.to_radians(), lat.to_radians());
+        let i = (x - self.x1) / self.dx;
+        let j = (y - self.y1) / self.dy;
+        if !i.is_finite() || !j.is_finite() {
+            return None;
+        }
+        within(i, j, self.ni, self.nj, self.period)
+    }
+}
+
+// 正角円筒図法（球）
+struct Mercator {
+    radius: f64, // 地球の半径 x cos(LaD) [m]
+    lon0: f64,   // 中心の経度 [rad]
+}
+
+impl Projection for Mercator {
+    fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        (
+            self.radius * normalize_radian(lon - self.lon0),
+            self.radius * (FRAC_PI_4 + lat / 2.0).tan().ln(),
+        )
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.lon0 + x / self.radius,
+            2.0 * (y / self.radius).exp().atan() - FRAC_PI_2,
+        )
+    }
+}
+
+// 平射図法（球）
+struct PolarStereographic {
+    radius: f64, // 地球の半径 x (1 + sin|LaD|) [m]
+    lov: f64,    // 格子の向きを決める経度 [rad]
+    south: bool, // 南極中心なら true
+}
+
+impl Projection for PolarStereographic {
+    fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let lambda = normalize_radian(lon - self.lov);
+        if self.south {
+            let rho = self.radius * (FRAC_PI_4 + lat / 2.0).tan();
+            (rho * lambda.sin(), rho * lambda.cos())
+        } else {
+            let rho = self.radius * (FRAC_PI_4 - lat / 2.0).tan();
+            (rho * lambda.sin(), -rho * lambda.cos())
+        }
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let rho = x.hypot(y);
+        if self.south {
+            (
+                self.lov + x.atan2(y),
+                2.0 * (rho / self.radius).atan() - FRAC_PI_2,
+            )
+        } else {
+            (
+                self.lov + x.atan2(-y),
+                FRAC_PI_2 - 2.0 * (rho / self.radius).atan(),
+            )
+        }
+    }
+}
+
+// 正角円錐図法（球、標準緯線2本）
+struct LambertConformal {
+    radius: f64, // 地球の半径 x F [m]
+    n: f64,      // 円錐定数
+    lov: f64,    // 格子の向きを決める経度 [rad]
+}
+
+impl LambertConformal {
+    fn new(radius: f64, latin1: f64, latin2: f64, lov: f64) -> Self {
+        let t = |lat: f64| (FRAC_PI_4 + lat / 2.0).tan();
+        let n = if (latin1 - latin2).abs() < 1e-10 {
+            latin1.sin()
+        } else {
+            (latin1.cos() / latin2.cos()).ln() / (t(latin2) / t(latin1)).ln()
+        };
+        let f = latin1.cos() * t(latin1).powf(n) / n;
+        Self {
+            radius: radius * f,
+            n,
+            lov,
+        }
+    }
+}
+
+impl Projection for LambertConformal {
+    fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let rho = self.radius / (FRAC_PI_4 + lat / 2.0).tan().powf(self.n);
+        let theta = self.n * normalize_radian(lon - self.lov);
+        (rho * theta.sin(), -rho * theta.cos())
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let sign = self.n.signum();
+        let rho = sign * x.hypot(y);
+        let theta = (sign * x).atan2(-sign * y);
+        (
+            self.lov + theta / self.n,
+            2.0 * (self.radius / rho).powf(1.0 / self.n).atan() - FRAC_PI_2,
+        )
+    }
+}
+
+// template 3.10
+// Mercator
+impl<'a> Template10<'a> {
+    fn grid(&self) -> ProjectedGrid<Mercator> {
+        let la1 = i32_be(&self.buf[38..42]) as isize;
+        let lo1 = i32_be(&self.buf[42..46]) as isize;
+        let lad = (i32_be(&self.buf[47..51]) as f64 * MICRO).to_radians();
+        let lo2 = i32_be(&self.buf[55..59]) as isize;
+        let scanning_mode = u8_be(&self.buf[59..60]);
+        let (si, _) = scan_signs(scanning_mode);
+
+        // 最初と最後の格子点の中間の経度を中心とする。
+        let span = ((lo2 - lo1) as f64 * MICRO * si).rem_euclid(360.0);
+        let lon0 = (lo1 as f64 * MICRO + span / 2.0 * si).to_radians();
+        let radius = earth_radius(self.buf) * lad.cos();
+
+        let mut grid = ProjectedGrid::new(
+            Mercator { radius, lon0 },
+            self.ni(),
+            self.nj(),
+            la1,
+            lo1,
+            u32_be(&self.buf[64..68]) as usize,
+            u32_be(&self.buf[68..72]) as usize,
+            scanning_mode,
+        );
+        if self.is_periodic() {
+            grid.period = Some(self.ni() as f64);
+        }
+        grid
+    }
+
+    fn transform(&self) -> GridTransform {
+        let grid = self.grid();
+        grid.transform(Crs::Mercator {
+            radius: earth_radius(self.buf),
+            lon0: normalize_longitude(grid.projection.lon0.to_degrees()),
+            lad: i32_be(&self.buf[47..51]) as f64 * MICRO,
+        })
+    }
+}
+
+impl<'a> GridGeometry for Template10<'a> {
+    // Ni – number of points along a parallel
+    fn ni(&self) -> usize {
+        u32_be(&self.buf[30..34]) as usize
+    }
+
+    // Nj – number of points along a meridian
+    fn nj(&self) -> usize {
+        u32_be(&self.buf[34..38]) as usize
+    }
+
+    fn is_periodic(&self) -> bool {
+        let lad = (i32_be(&self.buf[47..51]) as f64 * MICRO).to_radians();
+        let circumference = 2.0 * PI * earth_radius(self.buf) * lad.cos();
+        let di = u32_be(&self.buf[64..68]) as f64 * MILLI;
+        (self.ni() as f64 * di - circumference).abs() < di * 0.5
+    }
+
+    fn ij_to_lonlat(&self, i: f64, j: f64) -> (f64, f64) {
+        self.grid().ij_to_lonlat(i, j)
+    }
+
+    fn lonlat_to_ij(&self, lon: f64, lat: f64) -> Option<(f64, f64)> {
+        self.grid().lonlat_to_ij(lon, lat)
+    }
+}
+
+// template 3.20
+// Polar stereographic projection
+impl<'a> Template20<'a> {
+    fn grid(&self) -> ProjectedGrid<PolarStereographic> {
+        let lad = (i32_be(&self.buf[47..51]) as f64 * MICRO).to_radians();
+        let lov = (i32_be(&self.buf[51..55]) as f64 * MICRO).to_radians();
+        let south = u8_be(&self.buf[63..64]) & 0x80 != 0;
+
+        ProjectedGrid::new(
+            PolarStereographic {
+                radius: earth_radius(self.buf) * (1.0 + lad.sin().abs()),
+                lov,
+                south,
+            },
+            self.ni(),
+            self.nj(),
+            i32_be(&self.buf[38..42]) as isize,
+            i32_be(&self.buf[42..46]) as isize,
+            u32_be(&self.buf[55..59]) as usize,
+            u32_be(&self.buf[59..63]) as usize,
+            u8_be(&self.buf[64..65]),
+        )
+    }
+
+    fn transform(&self) -> GridTransform {
+        let grid = self.grid();
+        let lad = (i32_be(&self.buf[47..51]) as f64 * MICRO).abs();
+        grid.transform(Crs::PolarStereographic {
+            radius: earth_radius(self.buf),
+            lov: normalize_longitude(grid.projection.lov.to_degrees()),
+            lad: if grid.projection.south { -lad } else { lad },
+        })
+    }
+}
+
+impl<'a> GridGeometry for Template20<'a> {
+    // Nx – number of points along the x-axis
+    fn ni(&self) -> usize {
+        u32_be(&self.buf[30..34]) as usize
+    }
+
+    // Ny – number of points along the y-axis
+    fn nj(&self) -> usize {
+        u32_be(&self.buf[34..38]) as usize
+    }
+
+    fn is_periodic(&self) -> bool {
+        false
+    }
+
+    fn ij_to_lonlat(&self, i: f64, j: f64) -> (f64, f64) {
+        self.grid().ij_to_lonlat(i, j)
+    }
+
+    fn lonlat_to_ij(&self, lon: f64, lat: f64) -> Option<(f64, f64)> {
+        self.grid().lonlat_to_ij(lon, lat)
+    }
+}
+
+// template 3.30
+// Lambert conformal
+impl<'a> Template30<'a> {
+    fn grid(&self) -> ProjectedGrid<LambertConformal> {
+        let degrees = |src: &[u8]| (i32_be(src) as f64 * MICRO).to_radians();
+
+        ProjectedGrid::new(
+            LambertConformal::new(
+                earth_radius(self.buf),
+                degrees(&self.buf[65..69]),
+                degrees(&self.buf[69..73]),
+                degrees(&self.buf[51..55]),
+            ),
+            self.ni(),
+            self.nj(),
+            i32_be(&self.buf[38..42]) as isize,
+            i32_be(&self.buf[42..46]) as isize,
+            u32_be(&self.buf[55..59]) as usize,
+            u32_be(&self.buf[59..63]) as usize,
+            u8_be(&self.buf[64..65]),
+        )
+    }
+
+    fn transform(&self) -> GridTransform {
+        let grid = self.grid();
+        let latin1 = i32_be(&self.buf[65..69]) as f64 * MICRO;
+        let latin2 = i32_be(&self.buf[69..73]) as f64 * MICRO;
+
+        // 投影面の y は円錐の頂点を原点とするので、第1標準緯線を原点とする北距に直す。
+        let projection = &grid.projection;
+        let rho0 = projection.radius
+            / (FRAC_PI_4 + latin1.to_radians() / 2.0)
+                .tan()
+                .powf(projection.n);
+        grid.transform(Crs::LambertConformal {
+            radius: earth_radius(self.buf),
+            lov: normalize_longitude(projection.lov.to_degrees()),
+            latin1,
+            latin2,
+            false_northing: -rho0,
+        })
+    }
+}
+
+impl<'a> GridGeometry for Template30<'a> {
+    // Nx – number of points along the x-axis
+    fn ni(&self) -> usize {
+        u32_be(&self.buf[30..34]) as usize
+    }
+
+    // Ny – number of points along the y-axis
+    fn nj(&self) -> usize {
+        u32_be(&self.buf[34..38]) as usize
+    }
+
+    fn is_periodic(&self) -> bool {
+        false
+    }
+
+    fn ij_to_lonlat(&self, i: f64, j: f64) -> (f64, f64) {
+        self.grid().ij_to_lonlat(i, j)
+    }
+
+    fn lonlat_to_ij(&self, lon: f64, lat: f64) -> Option<(f64, f64)> {
+        self.grid().lonlat_to_ij(lon, lat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RADIUS: f64 = 6371229.0; // 形状 6 の地球の半径
+
+    fn put_u32(buf: &mut [u8], offset: usize, value: u32) {
+        buf[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    // 10^-6 度の符号付き整数（最上位ビットが符号）
+    fn put_i32(buf: &mut [u8], offset: usize, value: f64) {
+        let magnitude = (value.abs() / MICRO).round() as u32;
+        let sign = if value < 0.0 { 0x8000_0000 } else { 0 };
+        put_u32(buf, offset, sign | magnitude);
+    }
+
+    // 格子の範囲の共通部分。地球の形状 6、格子点数 ni x nj、最初の格子点
+    fn section(length: usize, ni: u32, nj: u32, la1: f64, lo1: f64) -> Vec<u8> {
+        let mut buf = vec![0; length];
+        buf[14] = 6;
+        put_u32(&mut buf, 30, ni);
+        put_u32(&mut buf, 34, nj);
+        put_i32(&mut buf, 38, la1);
+        put_i32(&mut buf, 42, lo1);
+        buf
+    }
+
+    // 球面上の2点間の距離 [m]
+    fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+        let (lon1, lat1) = (a.0.to_radians(), a.1.to_radians());
+        let (lon2, lat2) = (b.0.to_radians(), b.1.to_radians());
+        let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+        2.0 * RADIUS * h.sqrt().asin()
+    }
+
+    // 格子の内側の点が経緯度を経て同じ格子点の位置に戻ることを確かめる。
+    fn assert_round_trip(grid: &TemplateNumber) {
+        let (ni, nj) = (grid.ni() as f64, grid.nj() as f64);
+        for (fi, fj) in [(0.0, 0.0), (0.25, 0.75), (0.5, 0.5), (1.0, 1.0), (0.9, 0.1)] {
+            let (i, j) = ((ni - 1.0) * fi, (nj - 1.0) * fj);
+            let (lon, lat) = grid.ij_to_lonlat(i, j);
+            assert!((-180.0..180.0).contains(&lon), "{}", lon);
+            let (ri, rj) = grid.lonlat_to_ij(lon, lat).unwrap();
+            assert!(
+                (ri - i).abs() < 1e-6 && (rj - j).abs() < 1e-6,
+                "{} {} -> {} {}",
+                i,
+                j,
+                ri,
+                rj
+            );
+        }
+        // 格子の外
+        let (lon, lat) = grid.ij_to_lonlat(-3.0, -3.0);
+        assert!(grid.lonlat_to_ij(lon, lat).is_none());
+    }
+
+    fn assert_near(a: f64, b: f64, tolerance: f64) {
+        assert!((a - b).abs() < tolerance, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn latlon() {
+        // 南から北へ走査する全球 1 度格子
+        let mut buf = section(72, 360, 181, 0.0, 0.0);
+        put_u32(&mut buf, 42, u32::MAX);
+        put_i32(&mut buf, 46, -90.0);
+        put_i32(&mut buf, 50, 0.5);
+        put_i32(&mut buf, 55, 90.0);
+        put_i32(&mut buf, 59, 359.5);
+        put_u32(&mut buf, 63, 1_000_000);
+        put_u32(&mut buf, 67, 1_000_000);
+        buf[71] = 0x40;
+        let grid = TemplateNumber::T0(Template0 { buf: &buf });
+        assert!(grid.is_periodic());
+        assert_round_trip(&grid);
+        let (lon, lat) = grid.ij_to_lonlat(10.0, 100.0);
+        assert_near(lon, 10.5, 1e-9);
+        assert_near(lat, 10.0, 1e-9);
+        // 最後の列と最初の列の間も格子の中
+        let (i, _) = grid.lonlat_to_ij(0.0, 0.0).unwrap();
+        assert_near(i, -0.5, 1e-9);
+    }
+
+    #[test]
+    fn mercator() {
+        let mut buf = section(72, 200, 250, -10.0, 120.0);
+        put_i32(&mut buf, 47, 22.5); // LaD
+        put_i32(&mut buf, 55, 160.0); // Lo2
+        put_u32(&mut buf, 64, 20_000_000); // Di 20 km
+        put_u32(&mut buf, 68, 20_000_000);
+        buf[59] = 0x40;
+        let grid = TemplateNumber::T10(Template10 { buf: &buf });
+        assert_round_trip(&grid);
+        let (lon, lat) = grid.ij_to_lonlat(0.0, 0.0);
+        assert_near(lon, 120.0, 1e-6);
+        assert_near(lat, -10.0, 1e-6);
+        // 標準緯線上の格子間隔は Di
+        let (_, j) = grid.lonlat_to_ij(130.0, 22.5).unwrap();
+        let a = grid.ij_to_lonlat(50.0, j);
+        let b = grid.ij_to_lonlat(51.0, j);
+        assert_near(distance(a, b), 20_000.0, 1.0);
+    }
+
+    #[test]
+    fn polar_stereographic() {
+        // 北極中心、LoV 140 度、LaD 60 度
+        let mut buf = section(65, 300, 300, 10.0, 100.0);
+        put_i32(&mut buf, 47, 60.0);
+        put_i32(&mut buf, 51, 140.0);
+        put_u32(&mut buf, 55, 40_000_000); // Dx 40 km
+        put_u32(&mut buf, 59, 40_000_000);
+        buf[64] = 0x40;
+        let grid = TemplateNumber::T20(Template20 { buf: &buf });
+        assert_round_trip(&grid);
+        let (lon, lat) = grid.ij_to_lonlat(0.0, 0.0);
+        assert_near(lon, 100.0, 1e-6);
+        assert_near(lat, 10.0, 1e-6);
+        // LoV の経線は格子の j 軸に平行
+        let (i1, _) = grid.lonlat_to_ij(140.0, 50.0).unwrap();
+        let (i2, _) = grid.lonlat_to_ij(140.0, 70.0).unwrap();
+        assert_near(i1, i2, 1e-6);
+        // LaD 上の格子間隔は Dx
+        let (i, j) = grid.lonlat_to_ij(140.0, 60.0).unwrap();
+        let a = grid.ij_to_lonlat(i, j);
+        let b = grid.ij_to_lonlat(i + 1.0, j);
+        assert_near(distance(a, b), 40_000.0, 1.0);
+
+        // 南極中心
+        let mut buf = section(65, 100, 100, -40.0, 0.0);
+        put_i32(&mut buf, 47, -60.0);
+        put_i32(&mut buf, 51, 0.0);
+        put_u32(&mut buf, 55, 100_000_000);
+        put_u32(&mut buf, 59, 100_000_000);
+        buf[63] = 0x80;
+        buf[64] = 0x40;
+        let grid = TemplateNumber::T20(Template20 { buf: &buf });
+        assert_round_trip(&grid);
+    }
+
+    #[test]
+    fn lambert_conformal() {
+        // 標準緯線 30 度と 60 度、LoV 140 度
+        let mut buf = section(81, 300, 300, 15.0, 110.0);
+        put_i32(&mut buf, 47, 30.0);
+        put_i32(&mut buf, 51, 140.0);
+        put_u32(&mut buf, 55, 20_000_000); // Dx 20 km
+        put_u32(&mut buf, 59, 20_000_000);
+        buf[64] = 0x40;
+        put_i32(&mut buf, 65, 30.0);
+        put_i32(&mut buf, 69, 60.0);
+        let grid = TemplateNumber::T30(Template30 { buf: &buf });
+        assert_round_trip(&grid);
+        let (lon, lat) = grid.ij_to_lonlat(0.0, 0.0);
+        assert_near(lon, 110.0, 1e-6);
+        assert_near(lat, 15.0, 1e-6);
+        // 2本の標準緯線上の格子間隔は Dx
+        for latin in [30.0, 60.0] {
+            let (i, j) = grid.lonlat_to_ij(140.0, latin).unwrap();
+            let a = grid.ij_to_lonlat(i, j);
+            let b = grid.ij_to_lonlat(i + 1.0, j);
+            assert_near(distance(a, b), 20_000.0, 1.0);
+        }
+
+        // 標準緯線が1本（接円錐）
+        put_i32(&mut buf, 69, 30.0);
+        let grid = TemplateNumber::T30(Template30 { buf: &buf });
+        assert_round_trip(&grid);
+    }
+}
@@ -1,90 +1,76 @@
 //! GRIB2 Section4 template declaration
+//!
+//! プロダクト定義テンプレートはフィールドの並び（レイアウト）として宣言し、
+//! 汎用の読み取り処理で値を取り出す。テンプレートの追加はレイアウトの追加で行う。
 
 use chrono::{DateTime, Utc};
 
-pub(crate) mod section4_template_impl;
-
-pub(crate) enum TemplateNumber<'a> {
-    T0(Template0<'a>),         // 4.0
-    T1(Template1<'a>),         // 4.1
-    T8(Template8<'a>),         // 4.8
-    T9(Template9<'a>),         // 4.9
-    T11(Template11<'a>),       // 4.11
-    T50000(Template50000<'a>), // 4.50000
-    T50008(Template50008<'a>), // 4.50008
-    T50009(Template50009<'a>), // 4.50009
-    T50011(Template50011<'a>), // 4.50011
-    T50012(Template50012<'a>), // 4.50012
-                               // 4.50030
-}
-
-pub(crate) trait Template {
-    // パラメーターカテゴリー
-    fn parameter_category(&self) -> usize;
-
-    // パラメーター番号
-    fn parameter_number(&self) -> usize;
-
-    // データセットの時刻
-    fn datetime(&self, reference_time: DateTime<Utc>) -> DateTime<Utc>;
-}
-
-// template 4.0
-#[derive(Copy, Clone)]
-pub(crate) struct Template0<'a> {
-    pub(crate) buf: &'a [u8],
-}
-
-// template 4.1
-#[derive(Copy, Clone)]
-pub(crate) struct Template1<'a> {
-    pub(crate) buf: &'a [u8],
-}
+use super::FixedSurface;
+use super::TimeOffset;
 
-// template 4.8
-#[derive(Copy, Clone)]
-pub(crate) struct Template8<'a> {
-    pub(crate) buf: &'a [u8],
-}
+pub(crate) mod section4_template_impl;
+pub(crate) mod section4_template_layouts;
 
-// template 4.9
+// フィールドの型
 #[derive(Copy, Clone)]
-pub(crate) struct Template9<'a> {
-    pub(crate) buf: &'a [u8],
+pub(crate) enum FieldType {
+    Unsigned(usize), // 符号なし整数（オクテット数）
+    ScaledValue,     // 尺度因子(1) と尺度付きの値(4)
+    DateTime,        // 年(2) 月 日 時 分 秒
+    TimeOffset,      // 時間の単位(1) と値(4)
+    FixedSurface,    // 固定面の種類(1) と尺度因子(1) と尺度付きの値(4)
 }
 
-// template 4.11
-#[derive(Copy, Clone)]
-pub(crate) struct Template11<'a> {
-    pub(crate) buf: &'a [u8],
+// フィールド
+pub(crate) struct Field {
+    pub(crate) name: &'static str,
+    pub(crate) field_type: FieldType,
+    pub(crate) code_table: Option<&'static str>,
 }
 
-// template 4.50000
-#[derive(Copy, Clone)]
-pub(crate) struct Template50000<'a> {
-    pub(crate) buf: &'a [u8],
+// レイアウトの構成要素。先頭から順に並ぶ。
+pub(crate) enum Part {
+    // 固定長のフィールドの並び
+    Fields(&'static [Field]),
+
+    // 先行するフィールド count の値だけ繰り返すフィールドの並び
+    Repeat {
+        name: &'static str,
+        count: &'static str,
+        fields: &'static [Field],
+    },
 }
 
-// template 4.50008
-#[derive(Copy, Clone)]
-pub(crate) struct Template50008<'a> {
-    pub(crate) buf: &'a [u8],
+// テンプレートのレイアウト。オクテット10（パラメーターカテゴリー）から始まる。
+pub(crate) struct Layout {
+    pub(crate) number: usize,
+    pub(crate) parts: &'static [Part],
 }
 
-// template 4.50009
-#[derive(Copy, Clone)]
-pub(crate) struct Template50009<'a> {
-    pub(crate) buf: &'a [u8],
+// フィールドの値
+#[derive(Clone, Debug)]
+pub(crate) enum Value {
+    Integer(i64),
+    Float(f64),
+    DateTime(DateTime<Utc>),
+    TimeOffset(TimeOffset),
+    FixedSurface(FixedSurface),
+    Records(Vec<Vec<(&'static str, Value)>>),
+    Missing,
+    Invalid(String),
 }
 
-// template 4.50011
-#[derive(Copy, Clone)]
-pub(crate) struct Template50011<'a> {
-    pub(crate) buf: &'a [u8],
+// 値を読み取ったフィールド
+pub(crate) struct Entry {
+    pub(crate) field: Option<&'static Field>, // 繰り返しなら None
+    pub(crate) name: &'static str,
+    pub(crate) offset: usize,
+    pub(crate) value: Value,
 }
 
-// template 4.50012
+// プロダクト定義テンプレート
 #[derive(Copy, Clone)]
-pub(crate) struct Template50012<'a> {
+pub(crate) struct Template<'a> {
     pub(crate) buf: &'a [u8],
+    pub(crate) layout: &'static Layout,
 }
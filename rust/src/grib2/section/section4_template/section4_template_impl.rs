@@ -1,1708 +1,686 @@
 //! GRIB2 Section4 template implementation
 
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Utc};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
 use std::fmt;
 
 use super::super::super::type_utils_impl::datetime_be;
-use super::super::super::type_utils_impl::i32_be;
-use super::super::super::type_utils_impl::i8_be;
-use super::super::super::type_utils_impl::time_span_be;
+use super::super::super::type_utils_impl::scaled_value_be;
 use super::super::super::type_utils_impl::u16_be;
+use super::super::super::type_utils_impl::u32_be;
 use super::super::super::type_utils_impl::u8_be;
+use super::super::band_impl::band_be;
+use super::super::band_impl::BAND_LENGTH;
+use super::super::constituent_impl::constituent_type_name;
+use super::super::constituent_impl::interval;
+use super::super::constituent_impl::interval_type_name;
+use super::super::ensemble_impl::derived_forecast_be;
+use super::super::ensemble_impl::derived_type_name;
+use super::super::ensemble_impl::ensemble_be;
+use super::super::ensemble_impl::ensemble_type_name;
+use super::super::level_impl::fixed_surface_be;
+use super::super::level_impl::level_be;
+use super::super::probability_impl::percentile_be;
+use super::super::probability_impl::probability_be;
+use super::super::probability_impl::probability_type_name;
+use super::super::radar_operation_impl::radar_operation_be;
+use super::super::statistics_impl::process_name;
+use super::super::statistics_impl::statistics_be;
+use super::super::time_offset_impl::time_offset_be;
+use super::super::Band;
+use super::super::Constituent;
+use super::super::DerivedForecast;
+use super::super::Ensemble;
+use super::super::Interval;
+use super::super::Level;
+use super::super::Percentile;
+use super::super::Probability;
+use super::super::RadarOperation;
+use super::super::Statistics;
+use super::Entry;
+use super::Field;
+use super::FieldType;
+use super::Part;
 use super::Template;
-use super::Template0;
-use super::Template1;
-use super::Template11;
-use super::Template50000;
-use super::Template50008;
-use super::Template50009;
-use super::Template50011;
-use super::Template50012;
-use super::Template8;
-use super::Template9;
-use super::TemplateNumber;
-
-impl<'a> TemplateNumber<'a> {
-    pub(crate) fn parameter_category(&self) -> usize {
-        match self {
-            TemplateNumber::T0(t) => t.parameter_category(),
-            TemplateNumber::T1(t) => t.parameter_category(),
-            TemplateNumber::T8(t) => t.parameter_category(),
-            TemplateNumber::T9(t) => t.parameter_category(),
-            TemplateNumber::T11(t) => t.parameter_category(),
-            TemplateNumber::T50000(t) => t.parameter_category(),
-            TemplateNumber::T50008(t) => t.parameter_category(),
-            TemplateNumber::T50009(t) => t.parameter_category(),
-            TemplateNumber::T50011(t) => t.parameter_category(),
-            TemplateNumber::T50012(t) => t.parameter_category(),
-        }
-    }
-
-    pub(crate) fn parameter_number(&self) -> usize {
-        match self {
-            TemplateNumber::T0(t) => t.parameter_number(),
-            TemplateNumber::T1(t) => t.parameter_number(),
-            TemplateNumber::T8(t) => t.parameter_number(),
-            TemplateNumber::T9(t) => t.parameter_number(),
-            TemplateNumber::T11(t) => t.parameter_number(),
-            TemplateNumber::T50000(t) => t.parameter_number(),
-            TemplateNumber::T50008(t) => t.parameter_number(),
-            TemplateNumber::T50009(t) => t.parameter_number(),
-            TemplateNumber::T50011(t) => t.parameter_number(),
-            TemplateNumber::T50012(t) => t.parameter_number(),
-        }
-    }
-
-    pub(crate) fn datetime(&self, reference_time: DateTime<Utc>) -> DateTime<Utc> {
-        match self {
-            TemplateNumber::T0(t) => t.datetime(reference_time),
-            TemplateNumber::T1(t) => t.datetime(reference_time),
-            TemplateNumber::T8(t) => t.datetime(reference_time),
-            TemplateNumber::T9(t) => t.datetime(reference_time),
-            TemplateNumber::T11(t) => t.datetime(reference_time),
-            TemplateNumber::T50000(t) => t.datetime(reference_time),
-            TemplateNumber::T50008(t) => t.datetime(reference_time),
-            TemplateNumber::T50009(t) => t.datetime(reference_time),
-            TemplateNumber::T50011(t) => t.datetime(reference_time),
-            TemplateNumber::T50012(t) => t.datetime(reference_time),
-        }
-    }
-
-    pub(crate) fn first_plane_type(&self) -> Option<usize> {
-        match self {
-            TemplateNumber::T0(t) => Some(t.first_plane_type()),
-            TemplateNumber::T1(t) => Some(t.first_plane_type()),
-            TemplateNumber::T8(t) => Some(t.first_plane_type()),
-            TemplateNumber::T9(t) => Some(t.first_plane_type()),
-            TemplateNumber::T11(t) => Some(t.first_plane_type()),
-            TemplateNumber::T50000(t) => Some(t.first_plane_type()),
-            TemplateNumber::T50008(t) => Some(t.first_plane_type()),
-            TemplateNumber::T50009(t) => Some(t.first_plane_type()),
-            TemplateNumber::T50011(t) => Some(t.first_plane_type()),
-            TemplateNumber::T50012(t) => Some(t.first_plane_type()),
+use super::Value;
+
+const LAYOUT_OFFSET: usize = 9; // レイアウトはオクテット10から始まる
+
+impl FieldType {
+    // オクテット数
+    pub(crate) fn length(&self) -> usize {
+        match *self {
+            FieldType::Unsigned(octets) => octets,
+            FieldType::ScaledValue => 5,
+            FieldType::DateTime => 7,
+            FieldType::TimeOffset => 5,
+            FieldType::FixedSurface => 6,
         }
     }
 
-    pub(crate) fn first_plane_factor(&self) -> Option<isize> {
-        match self {
-            TemplateNumber::T0(t) => Some(t.first_plane_factor()),
-            TemplateNumber::T1(t) => Some(t.first_plane_factor()),
-            TemplateNumber::T8(t) => Some(t.first_plane_factor()),
-            TemplateNumber::T9(t) => Some(t.first_plane_factor()),
-            TemplateNumber::T11(t) => Some(t.first_plane_factor()),
-            TemplateNumber::T50000(t) => Some(t.first_plane_factor()),
-            TemplateNumber::T50008(t) => Some(t.first_plane_factor()),
-            TemplateNumber::T50009(t) => Some(t.first_plane_factor()),
-            TemplateNumber::T50011(t) => Some(t.first_plane_factor()),
-            TemplateNumber::T50012(t) => Some(t.first_plane_factor()),
-        }
-    }
-
-    pub(crate) fn first_plane_value(&self) -> Option<isize> {
-        match self {
-            TemplateNumber::T0(t) => Some(t.first_plane_value()),
-            TemplateNumber::T1(t) => Some(t.first_plane_value()),
-            TemplateNumber::T8(t) => Some(t.first_plane_value()),
-            TemplateNumber::T9(t) => Some(t.first_plane_value()),
-            TemplateNumber::T11(t) => Some(t.first_plane_value()),
-            TemplateNumber::T50000(t) => Some(t.first_plane_value()),
-            TemplateNumber::T50008(t) => Some(t.first_plane_value()),
-            TemplateNumber::T50009(t) => Some(t.first_plane_value()),
-            TemplateNumber::T50011(t) => Some(t.first_plane_value()),
-            TemplateNumber::T50012(t) => Some(t.first_plane_value()),
+    // バイト列を値として返す。
+    fn value(&self, src: &[u8]) -> Value {
+        match *self {
+            FieldType::Unsigned(1) => Value::Integer(u8_be(src) as i64),
+            FieldType::Unsigned(2) => Value::Integer(u16_be(src) as i64),
+            FieldType::Unsigned(4) => Value::Integer(u32_be(src) as i64),
+            FieldType::Unsigned(octets) => {
+                Value::Invalid(format!("Unsupported integer length: {}", octets))
+            }
+            FieldType::ScaledValue => match scaled_value_be(src) {
+                Some(value) => Value::Float(value),
+                None => Value::Missing,
+            },
+            // 全ビットが立っていれば欠損値
+            FieldType::DateTime | FieldType::TimeOffset if src.iter().all(|x| *x == u8::MAX) => {
+                Value::Missing
+            }
+            FieldType::DateTime => Value::DateTime(datetime_be(src)),
+            FieldType::TimeOffset => match time_offset_be(src) {
+                Ok(offset) => Value::TimeOffset(offset),
+                Err(msg) => Value::Invalid(msg),
+            },
+            FieldType::FixedSurface => match fixed_surface_be(src) {
+                Some(surface) => Value::FixedSurface(surface),
+                None => Value::Missing,
+            },
         }
     }
 }
 
-impl fmt::Display for TemplateNumber<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            TemplateNumber::T0(t) => write!(f, "{}", t),
-            TemplateNumber::T1(t) => write!(f, "{}", t),
-            TemplateNumber::T8(t) => write!(f, "{}", t),
-            TemplateNumber::T9(t) => write!(f, "{}", t),
-            TemplateNumber::T11(t) => write!(f, "{}", t),
-            TemplateNumber::T50000(t) => write!(f, "{}", t),
-            TemplateNumber::T50008(t) => write!(f, "{}", t),
-            TemplateNumber::T50009(t) => write!(f, "{}", t),
-            TemplateNumber::T50011(t) => write!(f, "{}", t),
-            TemplateNumber::T50012(t) => write!(f, "{}", t),
-        }
-    }
-}
-impl fmt::Debug for TemplateNumber<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            TemplateNumber::T0(t) => write!(f, "{:?}", t),
-            TemplateNumber::T1(t) => write!(f, "{:?}", t),
-            TemplateNumber::T8(t) => write!(f, "{:?}", t),
-            TemplateNumber::T9(t) => write!(f, "{:?}", t),
-            TemplateNumber::T11(t) => write!(f, "{:?}", t),
-            TemplateNumber::T50000(t) => write!(f, "{:?}", t),
-            TemplateNumber::T50008(t) => write!(f, "{:?}", t),
-            TemplateNumber::T50009(t) => write!(f, "{:?}", t),
-            TemplateNumber::T50011(t) => write!(f, "{:?}", t),
-            TemplateNumber::T50012(t) => write!(f, "{:?}", t),
+// コード表の値の名称
+fn code_name(code_table: &str, code: usize) -> Option<&'static str> {
+    match code_table {
+        "4.6" => Some(ensemble_type_name(code)),
+        "4.7" => Some(derived_type_name(code)),
+        "4.9" => Some(probability_type_name(code)),
+        "4.10" => Some(process_name(code)),
+        "4.91" => Some(interval_type_name(code)),
+        "4.230" | "4.233" => Some(constituent_type_name(code)),
+        _ => None,
+    }
+}
+
+// フィールドの並びを読み取り、次のオフセットを返す。バイト列が足りなければ None を返す。
+fn read_fields(
+    buf: &[u8],
+    offset: usize,
+    fields: &'static [Field],
+    entries: &mut Vec<Entry>,
+) -> Option<usize> {
+    let mut offset = offset;
+    for field in fields.iter() {
+        let length = field.field_type.length();
+        let src = buf.get(offset..offset + length)?;
+        entries.push(Entry {
+            field: Some(field),
+            name: field.name,
+            offset,
+            value: field.field_type.value(src),
+        });
+        offset += length;
+    }
+    Some(offset)
+}
+
+impl<'a> Template<'a> {
+    // Product Definition Template Number (see Code Table 4.0)
+    pub(crate) fn number(&self) -> usize {
+        self.layout.number
+    }
+
+    // レイアウトに従ってフィールドを先頭から順に読み取る。
+    pub(crate) fn entries(&self) -> Vec<Entry> {
+        self.read(None)
+    }
+
+    // 名前が target のフィールドだけを読み取り、見つかったところでやめる。
+    fn entry(&self, target: &str) -> Option<Entry> {
+        self.read(Some(target)).pop()
+    }
+
+    // レイアウトに従ってフィールドを先頭から順に読み取る。
+    // target を指定すれば、そのフィールドと繰り返しの数を除いて値を読まずにオフセットだけを進める。
+    fn read(&self, target: Option<&str>) -> Vec<Entry> {
+        let wanted = |name: &str| target.is_none() || target == Some(name);
+        let mut entries = Vec::new();
+        let mut positions: Vec<(&'static Field, usize)> = Vec::new(); // 通過したフィールドと位置
+        let mut offset = LAYOUT_OFFSET;
+        for part in self.layout.parts.iter() {
+            match part {
+                Part::Fields(fields) => {
+                    for field in fields.iter() {
+                        let length = field.field_type.length();
+                        let src = match self.buf.get(offset..offset + length) {
+                            Some(src) => src,
+                            None => return entries,
+                        };
+                        positions.push((field, offset));
+                        if wanted(field.name) {
+                            entries.push(Entry {
+                                field: Some(field),
+                                name: field.name,
+                                offset,
+                                value: field.field_type.value(src),
+                            });
+                            if target.is_some() {
+                                return entries;
+                            }
+                        }
+                        offset += length;
+                    }
+                }
+                Part::Repeat {
+                    name,
+                    count,
+                    fields,
+                } => {
+                    let count = positions
+                        .iter()
+                        .find(|(field, _)| field.name == *count)
+                        .and_then(|(field, offset)| {
+                            let length = field.field_type.length();
+                            field
+                                .field_type
+                                .value(&self.buf[*offset..*offset + length])
+                                .integer()
+                        })
+                        .unwrap_or(0);
+
+                    let start = offset;
+                    if !wanted(name) {
+                        // 読み取れる完全な繰り返しの分だけ進める。
+                        let length: usize = fields.iter().map(|x| x.field_type.length()).sum();
+                        if let Some(available) =
+                            self.buf.len().saturating_sub(offset).checked_div(length)
+                        {
+                            offset += count.min(available) * length;
+                        }
+                        continue;
+                    }
+
+                    let mut records = Vec::new();
+                    for _ in 0..count {
+                        let mut record = Vec::new();
+                        match read_fields(self.buf, offset, fields, &mut record) {
+                            Some(next) => offset = next,
+                            None => break,
+                        }
+                        records.push(record.into_iter().map(|x| (x.name, x.value)).collect());
+                    }
+
+                    entries.push(Entry {
+                        field: None,
+                        name,
+                        offset: start,
+                        value: Value::Records(records),
+                    });
+                    if target.is_some() {
+                        return entries;
+                    }
+                }
+            }
         }
-    }
-}
-
-// template 4.0
-impl<'a> Template0<'a> {
-    // 作画処理の種類
-    fn generating_type(&self) -> usize {
-        u8_be(&self.buf[11..12]) as usize
-    }
-
-    // Background generating process identifier
-    fn background_generating_identifier(&self) -> usize {
-        u8_be(&self.buf[12..13]) as usize
-    }
-
-    // Hours after reference time of data cut–off (see Note)
-    fn cut_off_hours(&self) -> usize {
-        u16_be(&self.buf[14..16]) as usize
-    }
-
-    // Minutes after reference time of data cut–off
-    fn cut_off_minutes(&self) -> usize {
-        u8_be(&self.buf[16..17]) as usize
-    }
-
-    // 予報時間
-    fn forecast_time_span(&self) -> usize {
-        time_span_be(&self.buf[17..22])
-    }
-
-    // 第一固定面の種類
-    fn first_plane_type(&self) -> usize {
-        u8_be(&self.buf[22..23]) as usize
+        entries
     }
 
-    // 第一固定面の尺度因子
-    fn first_plane_factor(&self) -> isize {
-        i8_be(&self.buf[23..24]) as isize
+    // フィールドの値
+    pub(crate) fn get(&self, name: &str) -> Option<Value> {
+        Some(self.entry(name)?.value)
     }
 
-    // 第一固定面の尺度付きの値
-    fn first_plane_value(&self) -> isize {
-        i32_be(&self.buf[24..28]) as isize
+    // 整数のフィールドの値
+    pub(crate) fn integer(&self, name: &str) -> Option<usize> {
+        self.get(name)?.integer()
     }
 
-    // 第二固定面の種類
-    fn second_plane_type(&self) -> usize {
-        u8_be(&self.buf[28..29]) as usize
+    // フィールドの先頭から始まるバイト列
+    fn bytes(&self, name: &str) -> Option<&'a [u8]> {
+        let offset = self.entry(name)?.offset;
+        self.buf.get(offset..)
     }
 
-    // 第二固定面の尺度因子
-    fn second_plane_factor(&self) -> isize {
-        i8_be(&self.buf[29..30]) as isize
+    // フィールドの先頭から length オクテットのバイト列
+    fn octets(&self, name: &str, length: usize) -> Option<&'a [u8]> {
+        self.bytes(name)?.get(0..length)
     }
 
-    // 第二固定面の尺度付きの値
-    fn second_plane_value(&self) -> isize {
-        i32_be(&self.buf[30..34]) as isize
-    }
-}
-
-impl<'a> Template for Template0<'a> {
     // パラメーターカテゴリー
-    fn parameter_category(&self) -> usize {
-        u8_be(&self.buf[9..10]) as usize
-    }
-    // パラメーター番号
-    fn parameter_number(&self) -> usize {
-        u8_be(&self.buf[10..11]) as usize
-    }
-
-    // データセットの時刻を返す。
-    fn datetime(&self, reference_time: DateTime<Utc>) -> DateTime<Utc> {
-        reference_time + Duration::seconds(self.forecast_time_span() as i64)
+    pub(crate) fn parameter_category(&self) -> Option<usize> {
+        self.integer("parameter_category")
     }
-}
 
-impl fmt::Display for Template0<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-parameter-category: {}\n\
-parameter-number: {}\n\
-generating type: {}\n\
-background generating identifier: {}\n\
-cut-off hours: {}\n\
-cut-off minutes: {}\n\
-forecast_time_span: {}\n\
-first-plane-type: {}\n\
-first-plane-factor: {}\n\
-first-plane-value: {}\n\
-second-plane-type: {}\n\
-second-plane-factor: {}\n\
-second-plane-value: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.generating_type(),
-            self.background_generating_identifier(),
-            self.cut_off_hours(),
-            self.cut_off_minutes(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-        )
-    }
-}
-impl fmt::Debug for Template0<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-\tparameter category: {}\n\
-\tparameter number: {}\n\
-\tforecast time span: {}\n\
-\tfirst plane type: {}\n\
-\tfirst plane factor: {}\n\
-\tfirst plane value: {}\n\
-\tsecond plane type: {}\n\
-\tsecond plane factor: {}\n\
-\tsecond plane value: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-        )
-    }
-}
-
-// template 4.1
-impl<'a> Template1<'a> {
-    // 作画処理の種類
-    fn generating_type(&self) -> usize {
-        u8_be(&self.buf[11..12]) as usize
-    }
-
-    // Background generating process identifier
-    fn background_generating_identifier(&self) -> usize {
-        u8_be(&self.buf[12..13]) as usize
-    }
-
-    // Hours after reference time of data cut–off (see Note)
-    fn cut_off_hours(&self) -> usize {
-        u16_be(&self.buf[14..16]) as usize
-    }
-
-    // Minutes after reference time of data cut–off
-    fn cut_off_minutes(&self) -> usize {
-        u8_be(&self.buf[16..17]) as usize
-    }
-
-    // 予報時間
-    pub(crate) fn forecast_time_span(&self) -> usize {
-        time_span_be(&self.buf[17..22])
-    }
-
-    // 第一固定面の種類
-    fn first_plane_type(&self) -> usize {
-        u8_be(&self.buf[22..23]) as usize
-    }
-
-    // 第一固定面の尺度因子
-    fn first_plane_factor(&self) -> isize {
-        i8_be(&self.buf[23..24]) as isize
-    }
-
-    // 第一固定面の尺度付きの値
-    fn first_plane_value(&self) -> isize {
-        i32_be(&self.buf[24..28]) as isize
-    }
-
-    // 第二固定面の種類
-    fn second_plane_type(&self) -> usize {
-        u8_be(&self.buf[28..29]) as usize
-    }
-
-    // 第二固定面の尺度因子
-    fn second_plane_factor(&self) -> isize {
-        i8_be(&self.buf[29..30]) as isize
-    }
-
-    // 第二固定面の尺度付きの値
-    fn second_plane_value(&self) -> isize {
-        i32_be(&self.buf[30..34]) as isize
-    }
-}
-
-impl<'a> Template for Template1<'a> {
-    // パラメーターカテゴリー
-    fn parameter_category(&self) -> usize {
-        u8_be(&self.buf[9..10]) as usize
-    }
     // パラメーター番号
-    fn parameter_number(&self) -> usize {
-        u8_be(&self.buf[10..11]) as usize
-    }
-
-    // データセットの時刻を返す。
-    fn datetime(&self, reference_time: DateTime<Utc>) -> DateTime<Utc> {
-        reference_time + Duration::seconds(self.forecast_time_span() as i64)
-    }
-}
-
-impl fmt::Display for Template1<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-parameter-category: {}\n\
-parameter-number: {}\n\
-generating type: {}\n\
-background generating identifier: {}\n\
-cut-off hours: {}\n\
-cut-off minutes: {}\n\
-forecast_time_span: {}\n\
-first-plane-type: {}\n\
-first-plane-factor: {}\n\
-first-plane-value: {}\n\
-second-plane-type: {}\n\
-second-plane-factor: {}\n\
-second-plane-value: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.generating_type(),
-            self.background_generating_identifier(),
-            self.cut_off_hours(),
-            self.cut_off_minutes(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-        )
-    }
-}
-impl fmt::Debug for Template1<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-\tparameter category: {}\n\
-\tparameter number: {}\n\
-\tforecast time span: {}\n\
-\tfirst plane type: {}\n\
-\tfirst plane factor: {}\n\
-\tfirst plane value: {}\n\
-\tsecond plane type: {}\n\
-\tsecond plane factor: {}\n\
-\tsecond plane value: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-        )
-    }
-}
-
-// template 4.8
-impl<'a> Template8<'a> {
-    // 作画処理の種類
-    fn generating_type(&self) -> usize {
-        u8_be(&self.buf[11..12]) as usize
-    }
-
-    // Background generating process identifier
-    fn background_generating_identifier(&self) -> usize {
-        u8_be(&self.buf[12..13]) as usize
-    }
-
-    // Hours after reference time of data cut–off (see Note)
-    fn cut_off_hours(&self) -> usize {
-        u16_be(&self.buf[14..16]) as usize
-    }
-
-    // Minutes after reference time of data cut–off
-    fn cut_off_minutes(&self) -> usize {
-        u8_be(&self.buf[16..17]) as usize
+    pub(crate) fn parameter_number(&self) -> Option<usize> {
+        self.integer("parameter_number")
     }
 
-    // 予報時間
-    pub(crate) fn forecast_time_span(&self) -> usize {
-        time_span_be(&self.buf[17..22])
+    // 第一固定面と第二固定面
+    pub(crate) fn level(&self) -> Option<Level> {
+        level_be(self.octets("first_fixed_surface", 12)?)
     }
 
-    // 第一固定面の種類
-    fn first_plane_type(&self) -> usize {
-        u8_be(&self.buf[22..23]) as usize
-    }
-
-    // 第一固定面の尺度因子
-    fn first_plane_factor(&self) -> isize {
-        i8_be(&self.buf[23..24]) as isize
-    }
-
-    // 第一固定面の尺度付きの値
-    fn first_plane_value(&self) -> isize {
-        i32_be(&self.buf[24..28]) as isize
-    }
-
-    // 第二固定面の種類
-    fn second_plane_type(&self) -> usize {
-        u8_be(&self.buf[28..29]) as usize
-    }
-
-    // 第二固定面の尺度因子
-    fn second_plane_factor(&self) -> isize {
-        i8_be(&self.buf[29..30]) as isize
-    }
-
-    // 第二固定面の尺度付きの値
-    fn second_plane_value(&self) -> isize {
-        i32_be(&self.buf[30..34]) as isize
-    }
-
-    // 全時間間隔の終了時刻
-    fn end_time(&self) -> DateTime<Utc> {
-        datetime_be(&self.buf[34..41])
-    }
-
-    // 統計処理の種類
-    fn statistics_type(&self) -> usize {
-        u8_be(&self.buf[46..47]) as usize
-    }
-
-    // 統計処理した期間の長さ
-    fn statistics_time_span(&self) -> usize {
-        time_span_be(&self.buf[48..53])
-    }
-}
-
-impl<'a> Template for Template8<'a> {
-    // パラメーターカテゴリー
-    fn parameter_category(&self) -> usize {
-        u8_be(&self.buf[9..10]) as usize
-    }
-    // パラメーター番号
-    fn parameter_number(&self) -> usize {
-        u8_be(&self.buf[10..11]) as usize
-    }
-
-    // データセットの時刻を返す。
-    fn datetime(&self, _reference_time: DateTime<Utc>) -> DateTime<Utc> {
-        self.end_time()
-    }
-}
+    // 統計処理。統計処理を持たないテンプレートなら None を返す。
+    pub(crate) fn statistics(
+        &self,
+        reference_time: DateTime<Utc>,
+    ) -> Option<Result<Statistics, String>> {
+        let src = self.bytes("end_time")?;
+        if src.len() < 12 {
+            return None;
+        }
 
-impl fmt::Display for Template8<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-parameter-category: {}\n\
-parameter-number: {}\n\
-generating type: {}\n\
-background generating identifier: {}\n\
-cut-off hours: {}\n\
-cut-off minutes: {}\n\
-forecast_time_span: {}\n\
-first-plane-type: {}\n\
-first-plane-factor: {}\n\
-first-plane-value: {}\n\
-second-plane-type: {}\n\
-second-plane-factor: {}\n\
-second-plane-value: {}\n\
-end time: {}\n\
-statistics-type: {}\n\
-statistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.generating_type(),
-            self.background_generating_identifier(),
-            self.cut_off_hours(),
-            self.cut_off_minutes(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
+        Some(
+            self.forecast_time_after(reference_time)
+                .and_then(|start_time| statistics_be(src, reference_time, start_time)),
         )
     }
-}
-impl fmt::Debug for Template8<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-\tparameter category: {}\n\
-\tparameter number: {}\n\
-\tforecast time span: {}\n\
-\tfirst plane type: {}\n\
-\tfirst plane factor: {}\n\
-\tfirst plane value: {}\n\
-\tsecond plane type: {}\n\
-\tsecond plane factor: {}\n\
-\tsecond plane value: {}\n\
-\tend time: {}\n\
-\tstatistics type: {}\n\
-\tstatistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
-    }
-}
-
-// template 4.9
-impl<'a> Template9<'a> {
-    // 作画処理の種類
-    fn generating_type(&self) -> usize {
-        u8_be(&self.buf[11..12]) as usize
-    }
-
-    // Background generating process identifier
-    fn background_generating_identifier(&self) -> usize {
-        u8_be(&self.buf[12..13]) as usize
-    }
-
-    // Hours after reference time of data cut–off (see Note)
-    fn cut_off_hours(&self) -> usize {
-        u16_be(&self.buf[14..16]) as usize
-    }
-
-    // Minutes after reference time of data cut–off
-    fn cut_off_minutes(&self) -> usize {
-        u8_be(&self.buf[16..17]) as usize
-    }
-
-    // 予報時間
-    pub(crate) fn forecast_time_span(&self) -> usize {
-        time_span_be(&self.buf[17..22])
-    }
-
-    // 第一固定面の種類
-    fn first_plane_type(&self) -> usize {
-        u8_be(&self.buf[22..23]) as usize
-    }
-
-    // 第一固定面の尺度因子
-    fn first_plane_factor(&self) -> isize {
-        i8_be(&self.buf[23..24]) as isize
-    }
-
-    // 第一固定面の尺度付きの値
-    fn first_plane_value(&self) -> isize {
-        i32_be(&self.buf[24..28]) as isize
-    }
-
-    // 第二固定面の種類
-    fn second_plane_type(&self) -> usize {
-        u8_be(&self.buf[28..29]) as usize
-    }
 
-    // 第二固定面の尺度因子
-    fn second_plane_factor(&self) -> isize {
-        i8_be(&self.buf[29..30]) as isize
+    // 確率予報
+    pub(crate) fn probability(&self) -> Option<Probability> {
+        Some(probability_be(
+            self.octets("forecast_probability_number", 13)?,
+        ))
     }
 
-    // 第二固定面の尺度付きの値
-    fn second_plane_value(&self) -> isize {
-        i32_be(&self.buf[30..34]) as isize
+    // パーセンタイル値
+    pub(crate) fn percentile(&self) -> Option<Percentile> {
+        Some(percentile_be(self.octets("percentile", 1)?))
     }
 
-    // 全時間間隔の終了時刻
-    fn end_time(&self) -> DateTime<Utc> {
-        datetime_be(&self.buf[47..54])
+    // アンサンブル予報のメンバー
+    pub(crate) fn ensemble(&self) -> Option<Ensemble> {
+        Some(ensemble_be(self.octets("ensemble_type", 3)?))
     }
 
-    // 統計処理の種類
-    fn statistics_type(&self) -> usize {
-        u8_be(&self.buf[59..60]) as usize
+    // 導出予報
+    pub(crate) fn derived_forecast(&self) -> Option<DerivedForecast> {
+        Some(derived_forecast_be(
+            self.octets("derived_forecast_type", 2)?,
+        ))
     }
 
-    // 統計処理した期間の長さ
-    fn statistics_time_span(&self) -> usize {
-        time_span_be(&self.buf[61..66])
-    }
-}
-
-impl<'a> Template for Template9<'a> {
-    // パラメーターカテゴリー
-    fn parameter_category(&self) -> usize {
-        u8_be(&self.buf[9..10]) as usize
-    }
-    // パラメーター番号
-    fn parameter_number(&self) -> usize {
-        u8_be(&self.buf[10..11]) as usize
-    }
-
-    // データセットの時刻を返す。
-    fn datetime(&self, _reference_time: DateTime<Utc>) -> DateTime<Utc> {
-        self.end_time()
-    }
-}
-
-impl fmt::Display for Template9<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-parameter-category: {}\n\
-parameter-number: {}\n\
-generating type: {}\n\
-background generating identifier: {}\n\
-cut-off hours: {}\n\
-cut-off minutes: {}\n\
-forecast_time_span: {}\n\
-first-plane-type: {}\n\
-first-plane-factor: {}\n\
-first-plane-value: {}\n\
-second-plane-type: {}\n\
-second-plane-factor: {}\n\
-second-plane-value: {}\n\
-end time: {}\n\
-statistics-type: {}\n\
-statistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.generating_type(),
-            self.background_generating_identifier(),
-            self.cut_off_hours(),
-            self.cut_off_minutes(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
+    // 衛星の観測バンド
+    pub(crate) fn bands(&self) -> Option<Vec<Band>> {
+        let count = self.integer("band_count")?;
+        let src = self.bytes("bands")?;
+        Some(
+            src.chunks_exact(BAND_LENGTH)
+                .take(count)
+                .map(band_be)
+                .collect(),
         )
     }
-}
-impl fmt::Debug for Template9<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-\tparameter category: {}\n\
-\tparameter number: {}\n\
-\tforecast time span: {}\n\
-\tfirst plane type: {}\n\
-\tfirst plane factor: {}\n\
-\tfirst plane value: {}\n\
-\tsecond plane type: {}\n\
-\tsecond plane factor: {}\n\
-\tsecond plane value: {}\n\
-\tend time: {}\n\
-\tstatistics type: {}\n\
-\tstatistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
-    }
-}
-
-// template 4.11
-impl<'a> Template11<'a> {
-    // 作画処理の種類
-    fn generating_type(&self) -> usize {
-        u8_be(&self.buf[11..12]) as usize
-    }
-
-    // Background generating process identifier
-    fn background_generating_identifier(&self) -> usize {
-        u8_be(&self.buf[12..13]) as usize
-    }
-
-    // Hours after reference time of data cut–off (see Note)
-    fn cut_off_hours(&self) -> usize {
-        u16_be(&self.buf[14..16]) as usize
-    }
-
-    // Minutes after reference time of data cut–off
-    fn cut_off_minutes(&self) -> usize {
-        u8_be(&self.buf[16..17]) as usize
-    }
 
-    // 予報時間
-    pub(crate) fn forecast_time_span(&self) -> usize {
-        time_span_be(&self.buf[17..22])
+    // レーダー等運用情報（気象庁ローカル）。レイアウトの後ろのオクテットから読み取る。
+    pub(crate) fn radar_operation(&self) -> Option<RadarOperation> {
+        radar_operation_be(self.local_octets()?)
     }
 
-    // 第一固定面の種類
-    fn first_plane_type(&self) -> usize {
-        u8_be(&self.buf[22..23]) as usize
-    }
-
-    // 第一固定面の尺度因子
-    fn first_plane_factor(&self) -> isize {
-        i8_be(&self.buf[23..24]) as isize
-    }
-
-    // 第一固定面の尺度付きの値
-    fn first_plane_value(&self) -> isize {
-        i32_be(&self.buf[24..28]) as isize
-    }
-
-    // 第二固定面の種類
-    fn second_plane_type(&self) -> usize {
-        u8_be(&self.buf[28..29]) as usize
-    }
-
-    // 第二固定面の尺度因子
-    fn second_plane_factor(&self) -> isize {
-        i8_be(&self.buf[29..30]) as isize
-    }
-
-    // 第二固定面の尺度付きの値
-    fn second_plane_value(&self) -> isize {
-        i32_be(&self.buf[30..34]) as isize
-    }
-
-    // 全時間間隔の終了時刻
-    fn end_time(&self) -> DateTime<Utc> {
-        datetime_be(&self.buf[37..44])
-    }
-
-    // 統計処理の種類
-    fn statistics_type(&self) -> usize {
-        u8_be(&self.buf[49..50]) as usize
-    }
-
-    // 統計処理した期間の長さ
-    fn statistics_time_span(&self) -> usize {
-        time_span_be(&self.buf[51..56])
-    }
-}
-
-impl<'a> Template for Template11<'a> {
-    // パラメーターカテゴリー
-    fn parameter_category(&self) -> usize {
-        u8_be(&self.buf[9..10]) as usize
-    }
-    // パラメーター番号
-    fn parameter_number(&self) -> usize {
-        u8_be(&self.buf[10..11]) as usize
-    }
-
-    // データセットの時刻を返す。
-    fn datetime(&self, _reference_time: DateTime<Utc>) -> DateTime<Utc> {
-        self.end_time()
-    }
-}
-
-impl fmt::Display for Template11<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-parameter-category: {}\n\
-parameter-number: {}\n\
-generating type: {}\n\
-background generating identifier: {}\n\
-cut-off hours: {}\n\
-cut-off minutes: {}\n\
-forecast_time_span: {}\n\
-first-plane-type: {}\n\
-first-plane-factor: {}\n\
-first-plane-value: {}\n\
-second-plane-type: {}\n\
-second-plane-factor: {}\n\
-second-plane-value: {}\n\
-end time: {}\n\
-statistics-type: {}\n\
-statistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.generating_type(),
-            self.background_generating_identifier(),
-            self.cut_off_hours(),
-            self.cut_off_minutes(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
-    }
-}
-impl fmt::Debug for Template11<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-\tparameter category: {}\n\
-\tparameter number: {}\n\
-\tforecast time span: {}\n\
-\tfirst plane type: {}\n\
-\tfirst plane factor: {}\n\
-\tfirst plane value: {}\n\
-\tsecond plane type: {}\n\
-\tsecond plane factor: {}\n\
-\tsecond plane value: {}\n\
-\tend time: {}\n\
-\tstatistics type: {}\n\
-\tstatistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
-    }
-}
-
-// template 4.50000
-impl<'a> Template50000<'a> {
-    // 作画処理の種類
-    fn generating_type(&self) -> usize {
-        u8_be(&self.buf[11..12]) as usize
-    }
-
-    // Background generating process identifier
-    fn background_generating_identifier(&self) -> usize {
-        u8_be(&self.buf[12..13]) as usize
-    }
-
-    // Hours after reference time of data cut–off (see Note)
-    fn cut_off_hours(&self) -> usize {
-        u16_be(&self.buf[14..16]) as usize
-    }
-
-    // Minutes after reference time of data cut–off
-    fn cut_off_minutes(&self) -> usize {
-        u8_be(&self.buf[16..17]) as usize
-    }
-
-    // 予報時間
-    fn forecast_time_span(&self) -> usize {
-        time_span_be(&self.buf[17..22])
-    }
-
-    // 第一固定面の種類
-    fn first_plane_type(&self) -> usize {
-        u8_be(&self.buf[22..23]) as usize
-    }
-
-    // 第一固定面の尺度因子
-    fn first_plane_factor(&self) -> isize {
-        i8_be(&self.buf[23..24]) as isize
-    }
-
-    // 第一固定面の尺度付きの値
-    fn first_plane_value(&self) -> isize {
-        i32_be(&self.buf[24..28]) as isize
-    }
-
-    // 第二固定面の種類
-    fn second_plane_type(&self) -> usize {
-        u8_be(&self.buf[28..29]) as usize
+    // レイアウトの終わりのオフセット。バイト列が足りなければ None を返す。
+    fn end(&self) -> Option<usize> {
+        let mut offset = LAYOUT_OFFSET;
+        for part in self.layout.parts.iter() {
+            let (count, fields) = match part {
+                Part::Fields(fields) => (1, fields),
+                Part::Repeat { count, fields, .. } => (self.integer(count)?, fields),
+            };
+            let length: usize = fields.iter().map(|x| x.field_type.length()).sum();
+            offset += count * length;
+        }
+        match offset <= self.buf.len() {
+            true => Some(offset),
+            false => None,
+        }
     }
 
-    // 第二固定面の尺度因子
-    fn second_plane_factor(&self) -> isize {
-        i8_be(&self.buf[29..30]) as isize
+    // レイアウトより後ろのオクテット。レイアウトで定義していないローカルの情報が入る。
+    pub(crate) fn local_octets(&self) -> Option<&'a [u8]> {
+        self.buf.get(self.end()?..)
     }
 
-    // 第二固定面の尺度付きの値
-    fn second_plane_value(&self) -> isize {
-        i32_be(&self.buf[30..34]) as isize
-    }
-}
+    // 大気化学成分またはエーロゾルの種類と粒径・波長の区間
+    pub(crate) fn constituent(&self) -> Option<Constituent> {
+        let constituent_type = self
+            .integer("constituent_type")
+            .or_else(|| self.integer("aerosol_type"))?;
 
-impl<'a> Template for Template50000<'a> {
-    // パラメーターカテゴリー
-    fn parameter_category(&self) -> usize {
-        u8_be(&self.buf[9..10]) as usize
+        Some(Constituent {
+            constituent_type,
+            name: constituent_type_name(constituent_type),
+            size: self.interval("size_interval_type", "first_size", "second_size"),
+            wavelength: self.interval(
+                "wavelength_interval_type",
+                "first_wavelength",
+                "second_wavelength",
+            ),
+        })
     }
-    // パラメーター番号
-    fn parameter_number(&self) -> usize {
-        u8_be(&self.buf[10..11]) as usize
-    }
-
-    // データセットの時刻を返す。
-    fn datetime(&self, reference_time: DateTime<Utc>) -> DateTime<Utc> {
-        reference_time + Duration::seconds(self.forecast_time_span() as i64)
-    }
-}
 
-impl fmt::Display for Template50000<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-parameter-category: {}\n\
-parameter-number: {}\n\
-generating type: {}\n\
-background generating identifier: {}\n\
-cut-off hours: {}\n\
-cut-off minutes: {}\n\
-forecast time span: {}\n\
-first-plane-type: {}\n\
-first-plane-factor: {}\n\
-first-plane-value: {}\n\
-second-plane-type: {}\n\
-second-plane-factor: {}\n\
-second-plane-value: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.generating_type(),
-            self.background_generating_identifier(),
-            self.cut_off_hours(),
-            self.cut_off_minutes(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
+    // 区間の種類と第一・第二の限界値のフィールドから区間を返す。
+    fn interval(&self, interval_type: &str, first: &str, second: &str) -> Option<Interval> {
+        interval(
+            self.integer(interval_type)?,
+            self.get(first)?.float(),
+            self.get(second)?.float(),
         )
     }
-}
 
-impl fmt::Debug for Template50000<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-\tparameter category: {}\n\
-\tparameter number: {}\n\
-\tforecast time span: {}\n\
-\tfirst plane type: {}\n\
-\tfirst plane factor: {}\n\
-\tfirst plane value: {}\n\
-\tsecond plane type: {}\n\
-\tsecond plane factor: {}\n\
-\tsecond plane value: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-        )
-    }
-}
-
-// template 4.50008
-impl<'a> Template50008<'a> {
-    // 作画処理の種類
-    fn generating_type(&self) -> usize {
-        u8_be(&self.buf[11..12]) as usize
-    }
-
-    // Background generating process identifier
-    fn background_generating_identifier(&self) -> usize {
-        u8_be(&self.buf[12..13]) as usize
-    }
-
-    // Hours after reference time of data cut–off (see Note)
-    fn cut_off_hours(&self) -> usize {
-        u16_be(&self.buf[14..16]) as usize
-    }
-
-    // Minutes after reference time of data cut–off
-    fn cut_off_minutes(&self) -> usize {
-        u8_be(&self.buf[16..17]) as usize
-    }
-
-    // 予報時間
-    fn forecast_time_span(&self) -> usize {
-        time_span_be(&self.buf[17..22])
-    }
-
-    // 第一固定面の種類
-    fn first_plane_type(&self) -> usize {
-        u8_be(&self.buf[22..23]) as usize
-    }
-
-    // 第一固定面の尺度因子
-    fn first_plane_factor(&self) -> isize {
-        i8_be(&self.buf[23..24]) as isize
-    }
-
-    // 第一固定面の尺度付きの値
-    fn first_plane_value(&self) -> isize {
-        i32_be(&self.buf[24..28]) as isize
-    }
-
-    // 第二固定面の種類
-    fn second_plane_type(&self) -> usize {
-        u8_be(&self.buf[28..29]) as usize
-    }
-
-    // 第二固定面の尺度因子
-    fn second_plane_factor(&self) -> isize {
-        i8_be(&self.buf[29..30]) as isize
-    }
-
-    // 第二固定面の尺度付きの値
-    fn second_plane_value(&self) -> isize {
-        i32_be(&self.buf[30..34]) as isize
-    }
-
-    // 全時間間隔の終了時刻
-    fn end_time(&self) -> DateTime<Utc> {
-        datetime_be(&self.buf[34..41])
-    }
-
-    // 統計処理の種類
-    fn statistics_type(&self) -> usize {
-        u8_be(&self.buf[46..47]) as usize
+    // 参照時刻に予報時間を加えた時刻。予報時間を持たないテンプレートなら参照時刻を返す。
+    fn forecast_time_after(&self, reference_time: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        match self.get("forecast_time") {
+            Some(Value::TimeOffset(offset)) => offset.after(reference_time),
+            Some(Value::Invalid(msg)) => Err(msg),
+            _ => Ok(reference_time),
+        }
     }
 
-    // 統計処理した期間の長さ
-    fn statistics_time_span(&self) -> usize {
-        time_span_be(&self.buf[48..53])
+    // データセットの時刻。統計処理していれば全時間間隔の終了時刻を返す。
+    pub(crate) fn datetime(&self, reference_time: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        match self.statistics(reference_time) {
+            Some(statistics) => Ok(statistics?.end_time),
+            None => self.forecast_time_after(reference_time),
+        }
     }
 }
 
-impl<'a> Template for Template50008<'a> {
-    // パラメーターカテゴリー
-    fn parameter_category(&self) -> usize {
-        u8_be(&self.buf[9..10]) as usize
-    }
-    // パラメーター番号
-    fn parameter_number(&self) -> usize {
-        u8_be(&self.buf[10..11]) as usize
+impl Value {
+    pub(crate) fn integer(&self) -> Option<usize> {
+        match *self {
+            Value::Integer(value) if 0 <= value => Some(value as usize),
+            _ => None,
+        }
     }
 
-    // データセットの時刻を返す。
-    fn datetime(&self, _reference_time: DateTime<Utc>) -> DateTime<Utc> {
-        self.end_time()
+    pub(crate) fn float(&self) -> Option<f64> {
+        match *self {
+            Value::Float(value) => Some(value),
+            _ => None,
+        }
     }
 }
 
-impl fmt::Display for Template50008<'_> {
+impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-parameter-category: {}\n\
-parameter-number: {}\n\
-generating type: {}\n\
-background generating identifier: {}\n\
-cut-off hours: {}\n\
-cut-off minutes: {}\n\
-forecast time span: {}\n\
-first-plane-type: {}\n\
-first-plane-factor: {}\n\
-first-plane-value: {}\n\
-second-plane-type: {}\n\
-second-plane-factor: {}\n\
-second-plane-value: {}\n\
-end time: {}\n\
-statistics-type: {}\n\
-statistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.generating_type(),
-            self.background_generating_identifier(),
-            self.cut_off_hours(),
-            self.cut_off_minutes(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
+        match self {
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::DateTime(value) => write!(f, "{}", value),
+            Value::TimeOffset(value) => write!(f, "{}", value),
+            Value::FixedSurface(value) => write!(f, "{}", value),
+            Value::Records(records) => {
+                let records: Vec<String> = records
+                    .iter()
+                    .map(|record| {
+                        let fields: Vec<String> = record
+                            .iter()
+                            .map(|(name, value)| format!("{}: {}", name, value))
+                            .collect();
+                        format!("{{{}}}", fields.join(", "))
+                    })
+                    .collect();
+                write!(f, "[{}]", records.join(", "))
+            }
+            Value::Missing => write!(f, "missing"),
+            Value::Invalid(msg) => write!(f, "{}", msg),
+        }
     }
 }
 
-impl fmt::Debug for Template50008<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-\tparameter category: {}\n\
-\tparameter number: {}\n\
-\tforecast time span: {}\n\
-\tfirst plane type: {}\n\
-\tfirst plane factor: {}\n\
-\tfirst plane value: {}\n\
-\tsecond plane type: {}\n\
-\tsecond plane factor: {}\n\
-\tsecond plane value: {}\n\
-\tend time: {}\n\
-\tstatistics type: {}\n\
-\tstatistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Integer(value) => serializer.serialize_i64(*value),
+            Value::Float(value) => serializer.serialize_f64(*value),
+            Value::DateTime(value) => value.serialize(serializer),
+            Value::TimeOffset(value) => value.serialize(serializer),
+            Value::FixedSurface(value) => value.serialize(serializer),
+            Value::Records(records) => {
+                let mut seq = serializer.serialize_seq(Some(records.len()))?;
+                for record in records.iter() {
+                    seq.serialize_element(&Record(record))?;
+                }
+                seq.end()
+            }
+            Value::Missing => serializer.serialize_none(),
+            Value::Invalid(msg) => serializer.serialize_str(msg),
+        }
     }
 }
 
-// template 4.50009
-impl<'a> Template50009<'a> {
-    // 作画処理の種類
-    fn generating_type(&self) -> usize {
-        u8_be(&self.buf[11..12]) as usize
-    }
-
-    // Background generating process identifier
-    fn background_generating_identifier(&self) -> usize {
-        u8_be(&self.buf[12..13]) as usize
-    }
-
-    // Hours after reference time of data cut–off (see Note)
-    fn cut_off_hours(&self) -> usize {
-        u16_be(&self.buf[14..16]) as usize
-    }
-
-    // Minutes after reference time of data cut–off
-    fn cut_off_minutes(&self) -> usize {
-        u8_be(&self.buf[16..17]) as usize
-    }
-
-    // 予報時間
-    fn forecast_time_span(&self) -> usize {
-        time_span_be(&self.buf[17..22])
-    }
-
-    // 第一固定面の種類
-    fn first_plane_type(&self) -> usize {
-        u8_be(&self.buf[22..23]) as usize
-    }
-
-    // 第一固定面の尺度因子
-    fn first_plane_factor(&self) -> isize {
-        i8_be(&self.buf[23..24]) as isize
-    }
-
-    // 第一固定面の尺度付きの値
-    fn first_plane_value(&self) -> isize {
-        i32_be(&self.buf[24..28]) as isize
-    }
-
-    // 第二固定面の種類
-    fn second_plane_type(&self) -> usize {
-        u8_be(&self.buf[28..29]) as usize
-    }
-
-    // 第二固定面の尺度因子
-    fn second_plane_factor(&self) -> isize {
-        i8_be(&self.buf[29..30]) as isize
-    }
+// 繰り返しの1要素をマップとして直列化する。
+struct Record<'a>(&'a [(&'static str, Value)]);
 
-    // 第二固定面の尺度付きの値
-    fn second_plane_value(&self) -> isize {
-        i32_be(&self.buf[30..34]) as isize
-    }
-
-    // 全時間間隔の終了時刻
-    fn end_time(&self) -> DateTime<Utc> {
-        datetime_be(&self.buf[34..41])
-    }
-
-    // 統計処理の種類
-    fn statistics_type(&self) -> usize {
-        u8_be(&self.buf[46..47]) as usize
-    }
-
-    // 統計処理した期間の長さ
-    fn statistics_time_span(&self) -> usize {
-        time_span_be(&self.buf[48..53])
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in self.0.iter() {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
     }
 }
 
-impl<'a> Template for Template50009<'a> {
-    // パラメーターカテゴリー
-    fn parameter_category(&self) -> usize {
-        u8_be(&self.buf[9..10]) as usize
-    }
-    // パラメーター番号
-    fn parameter_number(&self) -> usize {
-        u8_be(&self.buf[10..11]) as usize
-    }
-
-    // データセットの時刻を返す。
-    fn datetime(&self, _reference_time: DateTime<Utc>) -> DateTime<Utc> {
-        self.end_time()
+impl Serialize for Template<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries = self.entries();
+        let mut map = serializer.serialize_map(Some(entries.len() + 1))?;
+        map.serialize_entry("template", &format!("4.{}", self.number()))?;
+        for entry in entries.iter() {
+            map.serialize_entry(entry.name, &entry.value)?;
+        }
+        map.end()
     }
 }
 
-impl fmt::Display for Template50009<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-parameter-category: {}\n\
-parameter-number: {}\n\
-generating type: {}\n\
-background generating identifier: {}\n\
-cut-off hours: {}\n\
-cut-off minutes: {}\n\
-forecast time span: {}\n\
-first-plane-type: {}\n\
-first-plane-factor: {}\n\
-first-plane-value: {}\n\
-second-plane-type: {}\n\
-second-plane-factor: {}\n\
-second-plane-value: {}\n\
-end time: {}\n\
-statistics-type: {}\n\
-statistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.generating_type(),
-            self.background_generating_identifier(),
-            self.cut_off_hours(),
-            self.cut_off_minutes(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
+impl Entry {
+    // コード表の値なら名称を付けて表す。
+    pub(crate) fn value_string(&self) -> String {
+        let code_table = self.field.and_then(|x| x.code_table);
+        match (code_table, self.value.integer()) {
+            (Some(code_table), Some(code)) => match code_name(code_table, code) {
+                Some(name) => format!("{} ({})", code, name),
+                None => format!("{}", code),
+            },
+            _ => format!("{}", self.value),
+        }
     }
 }
 
-impl fmt::Debug for Template50009<'_> {
+impl fmt::Display for Template<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-\tparameter category: {}\n\
-\tparameter number: {}\n\
-\tforecast time span: {}\n\
-\tfirst plane type: {}\n\
-\tfirst plane factor: {}\n\
-\tfirst plane value: {}\n\
-\tsecond plane type: {}\n\
-\tsecond plane factor: {}\n\
-\tsecond plane value: {}\n\
-\tend time: {}\n\
-\tstatistics type: {}\n\
-\tstatistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
-    }
-}
-
-// template 4.50011
-impl<'a> Template50011<'a> {
-    // 作画処理の種類
-    fn generating_type(&self) -> usize {
-        u8_be(&self.buf[11..12]) as usize
-    }
-
-    // Background generating process identifier
-    fn background_generating_identifier(&self) -> usize {
-        u8_be(&self.buf[12..13]) as usize
-    }
-
-    // Hours after reference time of data cut–off (see Note)
-    fn cut_off_hours(&self) -> usize {
-        u16_be(&self.buf[14..16]) as usize
-    }
-
-    // Minutes after reference time of data cut–off
-    fn cut_off_minutes(&self) -> usize {
-        u8_be(&self.buf[16..17]) as usize
-    }
-
-    // 予報時間
-    pub(crate) fn forecast_time_span(&self) -> usize {
-        time_span_be(&self.buf[17..22])
-    }
-
-    // 第一固定面の種類
-    fn first_plane_type(&self) -> usize {
-        u8_be(&self.buf[22..23]) as usize
-    }
-
-    // 第一固定面の尺度因子
-    fn first_plane_factor(&self) -> isize {
-        i8_be(&self.buf[23..24]) as isize
-    }
-
-    // 第一固定面の尺度付きの値
-    fn first_plane_value(&self) -> isize {
-        i32_be(&self.buf[24..28]) as isize
-    }
-
-    // 第二固定面の種類
-    fn second_plane_type(&self) -> usize {
-        u8_be(&self.buf[28..29]) as usize
-    }
-
-    // 第二固定面の尺度因子
-    fn second_plane_factor(&self) -> isize {
-        i8_be(&self.buf[29..30]) as isize
-    }
-
-    // 第二固定面の尺度付きの値
-    fn second_plane_value(&self) -> isize {
-        i32_be(&self.buf[30..34]) as isize
-    }
-
-    // 全時間間隔の終了時刻
-    fn end_time(&self) -> DateTime<Utc> {
-        datetime_be(&self.buf[34..41])
-    }
-
-    // 統計処理の種類
-    fn statistics_type(&self) -> usize {
-        u8_be(&self.buf[46..47]) as usize
-    }
-
-    // 統計処理した期間の長さ
-    fn statistics_time_span(&self) -> usize {
-        time_span_be(&self.buf[48..53])
-    }
-}
-
-impl<'a> Template for Template50011<'a> {
-    // パラメーターカテゴリー
-    fn parameter_category(&self) -> usize {
-        u8_be(&self.buf[9..10]) as usize
-    }
-    // パラメーター番号
-    fn parameter_number(&self) -> usize {
-        u8_be(&self.buf[10..11]) as usize
-    }
-    // データセットの時刻を返す。
-    fn datetime(&self, _reference_time: DateTime<Utc>) -> DateTime<Utc> {
-        self.end_time()
+        for entry in self.entries().iter() {
+            writeln!(
+                f,
+                "{}: {}",
+                entry.name.replace('_', "-"),
+                entry.value_string()
+            )?;
+        }
+        Ok(())
     }
 }
 
-impl fmt::Display for Template50011<'_> {
+impl fmt::Debug for Template<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-parameter-category: {}\n\
-parameter-number: {}\n\
-generating type: {}\n\
-background generating identifier: {}\n\
-cut-off hours: {}\n\
-cut-off minutes: {}\n\
-forecast time span: {}\n\
-first-plane-type: {}\n\
-first-plane-factor: {}\n\
-first-plane-value: {}\n\
-second-plane-type: {}\n\
-second-plane-factor: {}\n\
-second-plane-value: {}\n\
-end time: {}\n\
-statistics-type: {}\n\
-statistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.generating_type(),
-            self.background_generating_identifier(),
-            self.cut_off_hours(),
-            self.cut_off_minutes(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
-    }
-}
-impl fmt::Debug for Template50011<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-\tparameter category: {}\n\
-\tparameter number: {}\n\
-\tforecast time span: {}\n\
-\tfirst plane type: {}\n\
-\tfirst plane factor: {}\n\
-\tfirst plane value: {}\n\
-\tsecond plane type: {}\n\
-\tsecond plane factor: {}\n\
-\tsecond plane value: {}\n\
-\tend time: {}\n\
-\tstatistics type: {}\n\
-\tstatistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
+        for entry in self.entries().iter() {
+            writeln!(
+                f,
+                "\t{}: {}",
+                entry.name.replace('_', " "),
+                entry.value_string()
+            )?;
+        }
+        Ok(())
     }
 }
 
-// template 4.50012
-impl<'a> Template50012<'a> {
-    // 作画処理の種類
-    fn generating_type(&self) -> usize {
-        u8_be(&self.buf[11..12]) as usize
-    }
-
-    // Background generating process identifier
-    fn background_generating_identifier(&self) -> usize {
-        u8_be(&self.buf[12..13]) as usize
-    }
-
-    // Hours after reference time of data cut–off (see Note)
-    fn cut_off_hours(&self) -> usize {
-        u16_be(&self.buf[14..16]) as usize
-    }
-
-    // Minutes after reference time of data cut–off
-    fn cut_off_minutes(&self) -> usize {
-        u8_be(&self.buf[16..17]) as usize
-    }
-
-    // 予報時間
-    pub(crate) fn forecast_time_span(&self) -> usize {
-        time_span_be(&self.buf[17..22])
-    }
-
-    // 第一固定面の種類
-    fn first_plane_type(&self) -> usize {
-        u8_be(&self.buf[22..23]) as usize
-    }
-
-    // 第一固定面の尺度因子
-    fn first_plane_factor(&self) -> isize {
-        i8_be(&self.buf[23..24]) as isize
-    }
-
-    // 第一固定面の尺度付きの値
-    fn first_plane_value(&self) -> isize {
-        i32_be(&self.buf[24..28]) as isize
-    }
-
-    // 第二固定面の種類
-    fn second_plane_type(&self) -> usize {
-        u8_be(&self.buf[28..29]) as usize
-    }
-
-    // 第二固定面の尺度因子
-    fn second_plane_factor(&self) -> isize {
-        i8_be(&self.buf[29..30]) as isize
-    }
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
 
-    // 第二固定面の尺度付きの値
-    fn second_plane_value(&self) -> isize {
-        i32_be(&self.buf[30..34]) as isize
-    }
-
-    // 全時間間隔の終了時刻
-    fn end_time(&self) -> DateTime<Utc> {
-        datetime_be(&self.buf[34..41])
-    }
+    use super::super::super::super::utils_impl::tests::*;
+    use super::super::super::TimeOffset;
+    use super::super::section4_template_layouts::layout;
+    use super::*;
 
-    // 統計処理の種類
-    fn statistics_type(&self) -> usize {
-        u8_be(&self.buf[46..47]) as usize
-    }
-
-    // 統計処理した期間の長さ
-    fn statistics_time_span(&self) -> usize {
-        time_span_be(&self.buf[48..53])
+    fn parse(number: usize, buf: &[u8]) -> Template {
+        Template {
+            buf,
+            layout: layout(number).unwrap(),
+        }
     }
-}
 
-impl<'a> Template for Template50012<'a> {
-    // パラメーターカテゴリー
-    fn parameter_category(&self) -> usize {
-        u8_be(&self.buf[9..10]) as usize
-    }
-    // パラメーター番号
-    fn parameter_number(&self) -> usize {
-        u8_be(&self.buf[10..11]) as usize
-    }
-    // データセットの時刻を返す。
-    fn datetime(&self, _reference_time: DateTime<Utc>) -> DateTime<Utc> {
-        self.end_time()
-    }
-}
+    // 時間範囲を n 個持つテンプレート 4.8 のオクテット10以降
+    fn statistical(ranges: &[(u8, u32)]) -> Vec<u8> {
+        let end_time = Utc.ymd(2023, 6, 1).and_hms(6, 0, 0);
+        let mut body = horizontal((1, 8), 1, 0, (1, 0, 0));
+        body.extend(statistics(end_time, ranges[0].0, ranges[0].1));
+        body[25 + 7] = ranges.len() as u8; // 時間範囲の数
+        for (process, hours) in ranges[1..].iter() {
+            body.extend_from_slice(&[*process, 2, 1]);
+            body.extend_from_slice(&hours.to_be_bytes());
+            body.extend_from_slice(&[1, 0, 0, 0, 0]);
+        }
+        body
+    }
+
+    // フィールドはオクテット10から順に並び、型に従って値を読み取る。
+    #[test]
+    fn fields() {
+        let buf = product(0, &horizontal((0, 0), 31, 6, (100, 0, 85000)));
+        let template = parse(0, &buf);
+        let entries = template.entries();
+        let names: Vec<&str> = entries.iter().map(|x| x.name).collect();
+        assert_eq!(
+            names,
+            [
+                "parameter_category",
+                "parameter_number",
+                "generating_process_type",
+                "background_generating_process",
+                "generating_process_identifier",
+                "cut_off_hours",
+                "cut_off_minutes",
+                "forecast_time",
+                "first_fixed_surface",
+                "second_fixed_surface",
+            ]
+        );
+        let offsets: Vec<usize> = entries.iter().map(|x| x.offset).collect();
+        assert_eq!(offsets, [9, 10, 11, 12, 13, 14, 16, 17, 22, 28]);
+
+        assert_eq!(template.integer("generating_process_identifier"), Some(31));
+        assert!(matches!(
+            template.get("forecast_time"),
+            Some(Value::TimeOffset(TimeOffset::Seconds(21600)))
+        ));
+        assert_eq!(
+            template.get("first_fixed_surface").unwrap().to_string(),
+            "Isobaric surface 850 hPa"
+        );
+        assert!(matches!(
+            template.get("second_fixed_surface"),
+            Some(Value::Missing)
+        ));
+        assert!(template.get("ensemble_type").is_none());
+    }
+
+    // 予約された単位の時間は Invalid、全ビットが立った時間は Missing とする。
+    #[test]
+    fn invalid_and_missing() {
+        let mut body = horizontal((0, 0), 31, 6, (100, 0, 85000));
+        body[8] = 200;
+        let buf = product(0, &body);
+        assert!(matches!(
+            parse(0, &buf).get("forecast_time"),
+            Some(Value::Invalid(_))
+        ));
+
+        body[8..13].copy_from_slice(&[u8::MAX; 5]);
+        let buf = product(0, &body);
+        assert!(matches!(
+            parse(0, &buf).get("forecast_time"),
+            Some(Value::Missing)
+        ));
+    }
+
+    // 繰り返しの数は先行するフィールドの値に従う。
+    #[test]
+    fn repeat() {
+        let buf = product(8, &statistical(&[(1, 6), (1, 1)]));
+        let template = parse(8, &buf);
+        let records = match template.get("time_ranges") {
+            Some(Value::Records(records)) => records,
+            _ => panic!("No time ranges"),
+        };
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1][0].0, "statistical_process");
+        assert_eq!(records[1][2].1.to_string(), "1 h");
+        assert_eq!(
+            template.entries().last().unwrap().value.to_string(),
+            "[{statistical_process: 1, increment_type: 2, length: 6 h, increment: 0 h}, \
+             {statistical_process: 1, increment_type: 2, length: 1 h, increment: 0 h}]"
+        );
+    }
+
+    // バイト列が足りなければ、読み取れたフィールドと完全な繰り返しだけを返す。
+    #[test]
+    fn truncated() {
+        let body = statistical(&[(1, 6), (1, 1)]);
+        let buf = product(8, &body);
+        let template = parse(8, &buf[..buf.len() - 1]);
+        match template.get("time_ranges") {
+            Some(Value::Records(records)) => assert_eq!(records.len(), 1),
+            _ => panic!("No time ranges"),
+        }
 
-impl fmt::Display for Template50012<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-parameter-category: {}\n\
-parameter-number: {}\n\
-generating type: {}\n\
-background generating identifier: {}\n\
-cut-off hours: {}\n\
-cut-off minutes: {}\n\
-forecast time span: {}\n\
-first-plane-type: {}\n\
-first-plane-factor: {}\n\
-first-plane-value: {}\n\
-second-plane-type: {}\n\
-second-plane-factor: {}\n\
-second-plane-value: {}\n\
-end time: {}\n\
-statistics-type: {}\n\
-statistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.generating_type(),
-            self.background_generating_identifier(),
-            self.cut_off_hours(),
-            self.cut_off_minutes(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
-    }
-}
-impl fmt::Debug for Template50012<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\
-\tparameter category: {}\n\
-\tparameter number: {}\n\
-\tforecast time span: {}\n\
-\tfirst plane type: {}\n\
-\tfirst plane factor: {}\n\
-\tfirst plane value: {}\n\
-\tsecond plane type: {}\n\
-\tsecond plane factor: {}\n\
-\tsecond plane value: {}\n\
-\tend time: {}\n\
-\tstatistics type: {}\n\
-\tstatistics time span: {}\n\
-",
-            self.parameter_category(),
-            self.parameter_number(),
-            self.forecast_time_span(),
-            self.first_plane_type(),
-            self.first_plane_factor(),
-            self.first_plane_value(),
-            self.second_plane_type(),
-            self.second_plane_factor(),
-            self.second_plane_value(),
-            self.end_time(),
-            self.statistics_type(),
-            self.statistics_time_span(),
-        )
+        let buf = product(0, &body[..18]);
+        let template = parse(0, &buf);
+        assert_eq!(template.entries().len(), 8);
+        assert!(template.get("first_fixed_surface").is_none());
+    }
+
+    // コード表の値は名称を付け、直列化ではテンプレート番号を加える。
+    #[test]
+    fn entries() {
+        let mut body = horizontal((0, 0), 31, 6, (100, 0, 85000));
+        body.extend_from_slice(&[3, 5, 51]);
+        let buf = product(1, &body);
+        let template = parse(1, &buf);
+        let entry = template.entry("ensemble_type").unwrap();
+        assert_eq!(entry.value_string(), "3 (Positively perturbed forecast)");
+        assert_eq!(template.entry("member_count").unwrap().value_string(), "51");
+
+        let value = serde_json::to_value(template).unwrap();
+        assert_eq!(value["template"], "4.1");
+        assert_eq!(value["perturbation_number"], 5);
+        assert_eq!(value["second_fixed_surface"], serde_json::Value::Null);
+        assert!(template
+            .to_string()
+            .contains("ensemble-type: 3 (Positively perturbed forecast)\n"));
+    }
+
+    // レイアウトより後ろのオクテットは、繰り返しの後から始まる。
+    #[test]
+    fn local_octets() {
+        let mut body = statistical(&[(1, 3), (1, 1)]);
+        body.extend_from_slice(&[0xAA, 0x55]);
+        let buf = product(50008, &body);
+        let template = parse(50008, &buf);
+        assert_eq!(template.local_octets(), Some(&[0xAA, 0x55][..]));
+        assert_eq!(
+            template.radar_operation().unwrap().bits,
+            [1, 3, 5, 7, 10, 12, 14, 16]
+        );
+
+        let buf = product(8, &statistical(&[(1, 3)]));
+        assert_eq!(parse(8, &buf).local_octets(), Some(&[][..]));
+        assert!(parse(8, &buf).radar_operation().is_none());
+
+        // 繰り返しが足りなければ None
+        let buf = product(8, &statistical(&[(1, 3), (1, 1)]));
+        assert!(parse(8, &buf[..buf.len() - 1]).local_octets().is_none());
     }
 }
@@ -0,0 +1,333 @@
+//! GRIB2 Section4 template layouts
+
+use super::Field;
+use super::FieldType;
+use super::Layout;
+use super::Part;
+
+const fn field(name: &'static str, field_type: FieldType) -> Field {
+    Field {
+        name,
+        field_type,
+        code_table: None,
+    }
+}
+
+const fn code(name: &'static str, octets: usize, code_table: &'static str) -> Field {
+    Field {
+        name,
+        field_type: FieldType::Unsigned(octets),
+        code_table: Some(code_table),
+    }
+}
+
+// octets 10-11
+const PARAMETER: &[Field] = &[
+    code("parameter_category", 1, "4.1"),
+    code("parameter_number", 1, "4.2"),
+];
+
+// octets 12-34 (4.0)
+// Analysis or forecast at a horizontal level or in a horizontal layer at a point in time
+const HORIZONTAL: &[Field] = &[
+    code("generating_process_type", 1, "4.3"),
+    field("background_generating_process", FieldType::Unsigned(1)),
+    field("generating_process_identifier", FieldType::Unsigned(1)),
+    field("cut_off_hours", FieldType::Unsigned(2)),
+    field("cut_off_minutes", FieldType::Unsigned(1)),
+    field("forecast_time", FieldType::TimeOffset),
+    field("first_fixed_surface", FieldType::FixedSurface),
+    field("second_fixed_surface", FieldType::FixedSurface),
+];
+
+// octets 12-13 (4.40)
+const CONSTITUENT: &[Field] = &[code("constituent_type", 2, "4.230")];
+
+// octets 12-24 (4.44)
+const AEROSOL: &[Field] = &[
+    code("aerosol_type", 2, "4.233"),
+    code("size_interval_type", 1, "4.91"),
+    field("first_size", FieldType::ScaledValue),
+    field("second_size", FieldType::ScaledValue),
+];
+
+// octets 25-35 (4.48)
+const AEROSOL_WAVELENGTH: &[Field] = &[
+    code("wavelength_interval_type", 1, "4.91"),
+    field("first_wavelength", FieldType::ScaledValue),
+    field("second_wavelength", FieldType::ScaledValue),
+];
+
+// octets 12-14 (4.31)
+const SATELLITE_OBSERVATION: &[Field] = &[
+    code("generating_process_type", 1, "4.3"),
+    field(
+        "observation_generating_process_identifier",
+        FieldType::Unsigned(1),
+    ),
+    field("band_count", FieldType::Unsigned(1)),
+];
+
+// octets 12-23 (4.32)
+const SATELLITE_SIMULATION: &[Field] = &[
+    code("generating_process_type", 1, "4.3"),
+    field("background_generating_process", FieldType::Unsigned(1)),
+    field("generating_process_identifier", FieldType::Unsigned(1)),
+    field("cut_off_hours", FieldType::Unsigned(2)),
+    field("cut_off_minutes", FieldType::Unsigned(1)),
+    field("forecast_time", FieldType::TimeOffset),
+    field("band_count", FieldType::Unsigned(1)),
+];
+
+// バンド 11オクテット x NB
+const BAND: &[Field] = &[
+    field("satellite_series", FieldType::Unsigned(2)),
+    field("satellite_number", FieldType::Unsigned(2)),
+    field("instrument_type", FieldType::Unsigned(2)),
+    field("central_wave_number", FieldType::ScaledValue),
+];
+
+const BANDS: Part = Part::Repeat {
+    name: "bands",
+    count: "band_count",
+    fields: BAND,
+};
+
+// octets 35-37
+const ENSEMBLE: &[Field] = &[
+    code("ensemble_type", 1, "4.6"),
+    field("perturbation_number", FieldType::Unsigned(1)),
+    field("member_count", FieldType::Unsigned(1)),
+];
+
+// octets 35-36
+const DERIVED_FORECAST: &[Field] = &[
+    code("derived_forecast_type", 1, "4.7"),
+    field("member_count", FieldType::Unsigned(1)),
+];
+
+// octets 35-47
+const PROBABILITY: &[Field] = &[
+    field("forecast_probability_number", FieldType::Unsigned(1)),
+    field("total_forecast_probabilities", FieldType::Unsigned(1)),
+    code("probability_type", 1, "4.9"),
+    field("lower_limit", FieldType::ScaledValue),
+    field("upper_limit", FieldType::ScaledValue),
+];
+
+// octet 35
+const PERCENTILE: &[Field] = &[field("percentile", FieldType::Unsigned(1))];
+
+// octets 35-37
+const SPATIAL_PROCESSING: &[Field] = &[
+    code("statistical_process", 1, "4.10"),
+    code("spatial_processing_type", 1, "4.15"),
+    field("data_point_count", FieldType::Unsigned(1)),
+];
+
+// 全時間間隔の終了時刻から時間範囲の数まで
+const STATISTICS: &[Field] = &[
+    field("end_time", FieldType::DateTime),
+    field("time_range_count", FieldType::Unsigned(1)),
+    field("missing_count", FieldType::Unsigned(4)),
+];
+
+// 時間範囲 12オクテット x n
+const TIME_RANGE: &[Field] = &[
+    code("statistical_process", 1, "4.10"),
+    code("increment_type", 1, "4.11"),
+    field("length", FieldType::TimeOffset),
+    field("increment", FieldType::TimeOffset),
+];
+
+const TIME_RANGES: Part = Part::Repeat {
+    name: "time_ranges",
+    count: "time_range_count",
+    fields: TIME_RANGE,
+};
+
+static LAYOUTS: &[Layout] = &[
+    Layout {
+        number: 0,
+        parts: &[Part::Fields(PARAMETER), Part::Fields(HORIZONTAL)],
+    },
+    Layout {
+        number: 1,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(ENSEMBLE),
+        ],
+    },
+    Layout {
+        number: 2,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(DERIVED_FORECAST),
+        ],
+    },
+    Layout {
+        number: 5,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(PROBABILITY),
+        ],
+    },
+    Layout {
+        number: 6,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(PERCENTILE),
+        ],
+    },
+    Layout {
+        number: 8,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(STATISTICS),
+            TIME_RANGES,
+        ],
+    },
+    Layout {
+        number: 9,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(PROBABILITY),
+            Part::Fields(STATISTICS),
+            TIME_RANGES,
+        ],
+    },
+    Layout {
+        number: 10,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(PERCENTILE),
+            Part::Fields(STATISTICS),
+            TIME_RANGES,
+        ],
+    },
+    Layout {
+        number: 11,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(ENSEMBLE),
+            Part::Fields(STATISTICS),
+            TIME_RANGES,
+        ],
+    },
+    Layout {
+        number: 12,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(DERIVED_FORECAST),
+            Part::Fields(STATISTICS),
+            TIME_RANGES,
+        ],
+    },
+    Layout {
+        number: 15,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(SPATIAL_PROCESSING),
+        ],
+    },
+    Layout {
+        number: 31,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(SATELLITE_OBSERVATION),
+            BANDS,
+        ],
+    },
+    Layout {
+        number: 32,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(SATELLITE_SIMULATION),
+            BANDS,
+        ],
+    },
+    Layout {
+        number: 40,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(CONSTITUENT),
+            Part::Fields(HORIZONTAL),
+        ],
+    },
+    Layout {
+        number: 44,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(AEROSOL),
+            Part::Fields(HORIZONTAL),
+        ],
+    },
+    Layout {
+        number: 48,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(AEROSOL),
+            Part::Fields(AEROSOL_WAVELENGTH),
+            Part::Fields(HORIZONTAL),
+        ],
+    },
+    // 気象庁ローカル
+    Layout {
+        number: 50000,
+        parts: &[Part::Fields(PARAMETER), Part::Fields(HORIZONTAL)],
+    },
+    Layout {
+        number: 50008,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(STATISTICS),
+            TIME_RANGES,
+        ],
+    },
+    Layout {
+        number: 50009,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(STATISTICS),
+            TIME_RANGES,
+        ],
+    },
+    Layout {
+        number: 50011,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(STATISTICS),
+            TIME_RANGES,
+        ],
+    },
+    Layout {
+        number: 50012,
+        parts: &[
+            Part::Fields(PARAMETER),
+            Part::Fields(HORIZONTAL),
+            Part::Fields(STATISTICS),
+            TIME_RANGES,
+        ],
+    },
+    // 気象庁ローカルテンプレートのレイアウトより後ろのオクテット（レーダー等運用情報など）と、
+    // 4.50030 や降水ナウキャスト・雷ナウキャスト・竜巻発生確度ナウキャストのテンプレートは
+    // 配信資料に関する技術情報でオクテットの並びを確認できていないため定義していない。
+];
+
+// テンプレート番号のレイアウトを返す。
+pub(crate) fn layout(number: usize) -> Option<&'static Layout> {
+    LAYOUTS.iter().find(|x| x.number == number)
+}
@@ -6,15 +6,32 @@ use std::fmt;
 use super::super::bit_map_utils_impl::apply_bit_map;
 use super::super::decode_utils_impl::unpack;
 use super::super::decode_utils_impl::unpack_complex_packing_and_spatial_differencing;
+use super::super::decode_utils_impl::unpack_level;
 use super::super::decode_utils_impl::unpack_run_length_packing;
+use super::super::decode_utils_impl::unpack_simple_packing;
+use super::regrid_impl::regrid_weights;
+use super::sample_impl::sample;
+use super::section3_template::TemplateNumber as Section3TemplateNumber;
 use super::section5_template::TemplateNumber as Section5TemplateNumber;
+use super::Band;
 use super::Bounds;
+use super::Constituent;
+use super::DerivedForecast;
+use super::Ensemble;
+use super::Interpolation;
+use super::Level;
 use super::PackingType;
+use super::Percentile;
+use super::Probability;
+use super::RadarOperation;
 use super::RawRunLengthPackingImage;
 use super::RawSimplePackingImage;
+use super::RegridMethod;
+use super::RegridWeights;
 use super::SectionSet;
 use super::SectionSets;
 use super::SectionSetsIter;
+use super::Statistics;
 
 impl<'a> SectionSet<'a> {
     // Discipline – GRIB Master table number
@@ -35,6 +52,11 @@ impl<'a> SectionSet<'a> {
         Some(self.section3?.point_count())
     }
 
+    // 作成中枢
+    pub(crate) fn centre(&self) -> Option<usize> {
+        Some(self.section1?.centre())
+    }
+
     // データセットの参照時刻を返す。
     pub fn reference_datetime(&self) -> Option<DateTime<Utc>> {
         Some(self.section1?.reference_time())
@@ -43,32 +65,136 @@ impl<'a> SectionSet<'a> {
     // データセットの時刻を返す。
     pub fn datetime(&self) -> Option<DateTime<Utc>> {
         let reference_time = self.section1?.reference_time();
-        Some(self.section4?.template()?.datetime(reference_time))
+        self.section4?.template()?.datetime(reference_time).ok()
+    }
+
+    // 作成処理の識別子。作成中枢が定める。
+    pub(crate) fn generating_process_identifier(&self) -> Option<usize> {
+        self.section4?
+            .template()?
+            .integer("generating_process_identifier")
     }
 
     // パラメーターカテゴリー
     pub fn parameter_category(&self) -> Option<usize> {
-        Some(self.section4?.template()?.parameter_category())
+        self.section4?.template()?.parameter_category()
     }
 
     // パラメーター番号
     pub fn parameter_number(&self) -> Option<usize> {
-        Some(self.section4?.template()?.parameter_number())
+        self.section4?.template()?.parameter_number()
     }
 
-    // パラメーター番号
+    // 第一固定面の種類
     pub fn first_plane_type(&self) -> Option<usize> {
-        self.section4?.template()?.first_plane_type()
+        Some(self.level()?.first().surface_type)
     }
 
-    // パラメーター番号
+    // 第一固定面の尺度因子
     pub fn first_plane_factor(&self) -> Option<isize> {
-        self.section4?.template()?.first_plane_factor()
+        self.level()?.first().scale_factor
     }
 
-    // パラメーター番号
+    // 第一固定面の尺度付きの値
     pub fn first_plane_value(&self) -> Option<isize> {
-        self.section4?.template()?.first_plane_value()
+        self.level()?.first().scaled_value
+    }
+
+    // 第一固定面と第二固定面からなるレベル
+    pub fn level(&self) -> Option<Level> {
+        self.section4?.template()?.level()
+    }
+
+    // 統計処理
+    pub fn statistics(&self) -> Option<Statistics> {
+        let reference_time = self.section1?.reference_time();
+        self.section4?.template()?.statistics(reference_time)?.ok()
+    }
+
+    // 確率予報
+    pub fn probability(&self) -> Option<Probability> {
+        self.section4?.template()?.probability()
+    }
+
+    // パーセンタイル予報
+    pub fn percentile(&self) -> Option<Percentile> {
+        self.section4?.template()?.percentile()
+    }
+
+    // アンサンブル予報のメンバー
+    pub fn ensemble(&self) -> Option<Ensemble> {
+        self.section4?.template()?.ensemble()
+    }
+
+    // アンサンブル予報の全メンバーから導出した予報
+    pub fn derived_forecast(&self) -> Option<DerivedForecast> {
+        self.section4?.template()?.derived_forecast()
+    }
+
+    // レーダー等運用情報（気象庁ローカル）
+    pub fn radar_operation(&self) -> Option<RadarOperation> {
+        self.section4?.template()?.radar_operation()
+    }
+
+    // 衛星の観測バンド
+    pub fn bands(&self) -> Option<Vec<Band>> {
+        self.section4?.template()?.bands()
+    }
+
+    // 大気化学成分またはエーロゾル
+    pub fn constituent(&self) -> Option<Constituent> {
+        self.section4?.template()?.constituent()
+    }
+
+    // プロダクト定義テンプレートの全フィールド
+    pub fn product_definition(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self.section4?.template()?).ok()
+    }
+
+    // プロダクト定義テンプレートの全フィールドの名前と値（コード表の値は名称付き）
+    pub(crate) fn product_definition_entries(&self) -> Vec<(&'static str, String)> {
+        match self.section4.as_ref().and_then(|x| x.template()) {
+            Some(template) => template
+                .entries()
+                .iter()
+                .map(|x| (x.name, x.value_string()))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // 格子系定義テンプレート番号と i 方向、j 方向の格子点数
+    pub(crate) fn grid_shape(&self) -> Option<(usize, usize, usize)> {
+        let section3 = self.section3?;
+        Some((section3.template_number(), section3.ni(), section3.nj()))
+    }
+
+    // 格子点の位置と経緯度の対応
+    pub(crate) fn geometry(&self) -> Option<Section3TemplateNumber<'a>> {
+        self.section3?.template()
+    }
+
+    // 節の長さと節番号を除いた格子系定義
+    pub(crate) fn grid_definition(&self) -> Option<&'a [u8]> {
+        Some(self.section3?.grid_definition())
+    }
+
+    // この格子系から target の格子系へ変換する重みを返す。
+    pub(crate) fn regrid_weights(
+        &self,
+        target: &SectionSet,
+        method: RegridMethod,
+    ) -> Option<RegridWeights> {
+        Some(regrid_weights(
+            &self.geometry()?,
+            &target.geometry()?,
+            method,
+        ))
+    }
+
+    // 重みを使って格子系を変換した値を返す。
+    pub(crate) fn regrid(&self, weights: &RegridWeights) -> Result<Vec<f32>, String> {
+        weights.apply(&self.values()?)
     }
 
     pub fn bounds(&self) -> Option<Bounds> {
@@ -90,6 +216,59 @@ impl<'a> SectionSet<'a> {
         Some(self.section3?.dj())
     }
 
+    // 全格子点の物理量を資料の並び順で返す。欠測やビットマップで欠けた格子点は NaN とする。
+    pub fn values(&self) -> Result<Vec<f32>, String> {
+        match self.packing_type() {
+            Some(PackingType::Simple) => {
+                let image = self.unpack_simple()?;
+                Ok(unpack_simple_packing(
+                    &image.pixels,
+                    image.r,
+                    image.e,
+                    image.d,
+                ))
+            }
+            Some(PackingType::ComplexPackingAndSpatialDifferencing) => {
+                let image = self.unpack_complex_packing_and_spatial_differencing()?;
+                Ok(unpack_simple_packing(
+                    &image.pixels,
+                    image.r,
+                    image.e,
+                    image.d,
+                ))
+            }
+            Some(PackingType::RunLength) => {
+                let image = self.unpack_run_length()?;
+                Ok(unpack_level(&image.pixels, image.factor, &image.levels))
+            }
+            None => Err(String::from("Unsupported packing type")),
+        }
+    }
+
+    // 経度と緯度の値を補間して返す。格子の外や欠測なら None を返す。
+    pub fn sample(&self, lon: f64, lat: f64, method: Interpolation) -> Option<f64> {
+        let geometry = self.geometry()?;
+        let values = self.values().ok()?;
+        sample(&geometry, &values, lon, lat, method)
+    }
+
+    // 複数地点の値をまとめて補間して返す。
+    pub fn sample_points(&self, points: &[(f64, f64)], method: Interpolation) -> Vec<Option<f64>> {
+        let geometry = match self.geometry() {
+            Some(geometry) => geometry,
+            None => return vec![None; points.len()],
+        };
+        let values = match self.values() {
+            Ok(values) => values,
+            Err(_) => return vec![None; points.len()],
+        };
+
+        points
+            .iter()
+            .map(|(lon, lat)| sample(&geometry, &values, *lon, *lat, method))
+            .collect()
+    }
+
     pub fn unpack_simple(&self) -> Result<RawSimplePackingImage, String> {
         let sec3 = self.section3.ok_or("Invalid section 3");
         let width = sec3?.ni();
@@ -288,8 +467,9 @@ impl<'a> SectionSets<'a> {
         self.items.extend(sectionsets.items);
     }
 
-    pub fn get(&self, index: usize) -> SectionSet<'a> {
-        return self.items[index];
+    // index の SectionSet。範囲外なら None を返す。
+    pub fn try_get(&self, index: usize) -> Option<SectionSet<'a>> {
+        self.items.get(index).copied()
     }
 
     pub fn len(&self) -> usize {
@@ -335,3 +515,162 @@ impl<'a> Iterator for SectionSetsIter<'a> {
 //         write!(f, "bit_map: ")
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::super::super::utils_impl::parse;
+    use super::super::super::utils_impl::tests::*;
+
+    // ランレングス圧縮（テンプレート 5.200）の値は、レベル 1 の代表値から順に対応する。
+    #[test]
+    fn run_length_levels() {
+        let mut section5 = 6u32.to_be_bytes().to_vec();
+        section5.extend_from_slice(&200u16.to_be_bytes());
+        section5.push(8); // ビット数
+        section5.extend_from_slice(&3u16.to_be_bytes()); // MV
+        section5.extend_from_slice(&3u16.to_be_bytes()); // MVL
+        section5.push(1); // 代表値の尺度因子
+        for level in [0i16, 10, 25] {
+            section5.extend_from_slice(&level.to_be_bytes());
+        }
+        // レベル 1 が3つ続き (1, 6)、レベル 2, 0, 3 が1つずつ
+        let buf = message(
+            0,
+            &[
+                identification(34, Utc.ymd(2023, 6, 1).and_hms(0, 0, 0)),
+                latlon_grid(3, 2, 36.0, 139.0, 1.0, 1.0),
+                product(0, &horizontal((1, 8), 0, 0, (1, 0, 0))),
+                section(5, &section5),
+                section(6, &[255]),
+                section(7, &[1, 6, 2, 0, 3]),
+            ],
+        );
+
+        let values = parse(&buf).try_get(0).unwrap().values().unwrap();
+        assert_eq!(values[..4], [0.0, 0.0, 0.0, 1.0]);
+        assert!(values[4].is_nan());
+        assert_eq!(values[5], 2.5);
+    }
+
+    // 2023-06-01 00UTC 初期値の 24 時間予報の地上気温を、4.0 のオクテット34までとして作る。
+    fn field(template: u16, extension: &[u8]) -> Vec<u8> {
+        let mut body = horizontal((0, 0), 1, 24, (103, 0, 2));
+        body.extend_from_slice(extension);
+        message(
+            0,
+            &[
+                vec![identification(34, Utc.ymd(2023, 6, 1).and_hms(0, 0, 0))],
+                vec![latlon_grid(2, 1, 36.0, 139.0, 1.0, 1.0)],
+                vec![product(template, &body)],
+                simple_packing(&[1.0, 2.0]),
+            ]
+            .concat(),
+        )
+    }
+
+    // 確率予報（テンプレート 4.5）
+    #[test]
+    fn probability() {
+        let mut extension = vec![1, 3, 1];
+        extension.extend([signed(-1, 1), signed(-1, 4)].concat()); // 下限値は欠損値
+        extension.extend([signed(1, 1), signed(100, 4)].concat());
+        let buf = field(5, &extension);
+        let sectionset = parse(&buf).try_get(0).unwrap();
+
+        let probability = sectionset.probability().unwrap();
+        assert_eq!(probability.forecast_probability_number, 1);
+        assert_eq!(probability.total_forecast_probabilities, 3);
+        assert_eq!(probability.lower_limit, None);
+        assert_eq!(probability.upper_limit, Some(10.0));
+        assert_eq!(probability.describe("T", "K"), "P(T > 10 K)");
+        assert!(sectionset.percentile().is_none());
+        assert!(sectionset.statistics().is_none());
+        assert_eq!(
+            sectionset.datetime(),
+            Some(Utc.ymd(2023, 6, 2).and_hms(0, 0, 0))
+        );
+    }
+
+    // パーセンタイル予報（テンプレート 4.6）と統計処理したパーセンタイル予報（テンプレート 4.10）
+    #[test]
+    fn percentile() {
+        let buf = field(6, &[90]);
+        let sectionset = parse(&buf).try_get(0).unwrap();
+        assert_eq!(sectionset.percentile().unwrap().value, 90);
+        assert_eq!(
+            sectionset.percentile().unwrap().to_string(),
+            "90th percentile"
+        );
+        assert!(sectionset.probability().is_none());
+
+        let end_time = Utc.ymd(2023, 6, 2).and_hms(6, 0, 0);
+        let buf = field(10, &[vec![50], statistics(end_time, 2, 6)].concat());
+        let sectionset = parse(&buf).try_get(0).unwrap();
+        assert_eq!(sectionset.percentile().unwrap().value, 50);
+        assert_eq!(
+            sectionset.statistics().unwrap().to_string(),
+            "Maximum over 6 h"
+        );
+        assert_eq!(sectionset.datetime(), Some(end_time));
+    }
+
+    // アンサンブル予報のメンバー（テンプレート 4.1, 4.11）
+    #[test]
+    fn ensemble() {
+        let buf = field(1, &[3, 5, 51]);
+        let sectionset = parse(&buf).try_get(0).unwrap();
+        let ensemble = sectionset.ensemble().unwrap();
+        assert_eq!(ensemble.perturbation_number, 5);
+        assert_eq!(ensemble.member_count, 51);
+        assert!(!ensemble.is_control());
+        assert_eq!(
+            ensemble.to_string(),
+            "Member 5/51 (Positively perturbed forecast)"
+        );
+        assert!(sectionset.derived_forecast().is_none());
+
+        let end_time = Utc.ymd(2023, 6, 2).and_hms(3, 0, 0);
+        let buf = field(11, &[vec![0, 0, 51], statistics(end_time, 1, 3)].concat());
+        let sectionset = parse(&buf).try_get(0).unwrap();
+        assert!(sectionset.ensemble().unwrap().is_control());
+        assert_eq!(
+            sectionset.ensemble().unwrap().to_string(),
+            "Control (51 members)"
+        );
+        assert_eq!(
+            sectionset.statistics().unwrap().to_string(),
+            "Accumulation 24–27 h"
+        );
+    }
+
+    // アンサンブル予報の全メンバーから導出した予報（テンプレート 4.2, 4.12）
+    #[test]
+    fn derived_forecast() {
+        let buf = field(2, &[4, 51]);
+        let sectionset = parse(&buf).try_get(0).unwrap();
+        let derived_forecast = sectionset.derived_forecast().unwrap();
+        assert_eq!(derived_forecast.derived_type, 4);
+        assert_eq!(
+            derived_forecast.to_string(),
+            "Spread of all members (51 members)"
+        );
+        assert!(sectionset.ensemble().is_none());
+
+        let end_time = Utc.ymd(2023, 6, 2).and_hms(12, 0, 0);
+        let buf = field(12, &[vec![0, 27], statistics(end_time, 0, 12)].concat());
+        let sectionset = parse(&buf).try_get(0).unwrap();
+        let derived_forecast = sectionset.derived_forecast().unwrap();
+        assert_eq!(
+            derived_forecast.derived_type_name,
+            "Unweighted mean of all members"
+        );
+        assert_eq!(derived_forecast.member_count, 27);
+        assert_eq!(
+            sectionset.statistics().unwrap().to_string(),
+            "Average over 12 h"
+        );
+        assert_eq!(sectionset.datetime(), Some(end_time));
+    }
+}
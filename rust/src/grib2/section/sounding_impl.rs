@@ -0,0 +1,299 @@
+//! GRIB2 vertical profile (sounding) implementation
+
+use super::Interpolation;
+use super::Level;
+use super::SectionSet;
+use super::SectionSets;
+use super::Sounding;
+use super::SoundingLevel;
+
+const ISOBARIC_SURFACE: usize = 100;
+
+const ZERO_CELSIUS: f64 = 273.15; // [K]
+
+// Magnus の式の係数（水面上）
+const MAGNUS_A: f64 = 6.112; // [hPa]
+const MAGNUS_B: f64 = 17.62;
+const MAGNUS_C: f64 = 243.12; // [°C]
+
+// プロファイルに使う要素（discipline 0 のパラメーターカテゴリー, パラメーター番号）
+#[derive(Copy, Clone)]
+enum Element {
+    Temperature,        // 0.0 [K]
+    Dewpoint,           // 0.6 [K]
+    SpecificHumidity,   // 1.0 [kg kg-1]
+    RelativeHumidity,   // 1.1 [%]
+    U,                  // 2.2 [m s-1]
+    V,                  // 2.3 [m s-1]
+    GeopotentialHeight, // 3.5 [gpm]
+}
+
+fn element(sectionset: &SectionSet) -> Option<Element> {
+    if sectionset.discipline()? != 0 {
+        return None;
+    }
+    match (
+        sectionset.parameter_category()?,
+        sectionset.parameter_number()?,
+    ) {
+        (0, 0) => Some(Element::Temperature),
+        (0, 6) => Some(Element::Dewpoint),
+        (1, 0) => Some(Element::SpecificHumidity),
+        (1, 1) => Some(Element::RelativeHumidity),
+        (2, 2) => Some(Element::U),
+        (2, 3) => Some(Element::V),
+        (3, 5) => Some(Element::GeopotentialHeight),
+        _ => None,
+    }
+}
+
+// 統計処理や確率などの派生した値なら true を返す。
+fn is_derived(sectionset: &SectionSet) -> bool {
+    sectionset.statistics().is_some()
+        || sectionset.probability().is_some()
+        || sectionset.percentile().is_some()
+        || sectionset.derived_forecast().is_some()
+}
+
+// 等圧面の気圧 [Pa]。層や等圧面以外なら None を返す。
+pub(crate) fn isobaric_pressure(sectionset: &SectionSet) -> Option<f64> {
+    match sectionset.level()? {
+        Level::Surface(first) if first.surface_type == ISOBARIC_SURFACE => first.value,
+        _ => None,
+    }
+}
+
+// 気圧の等しいレベルを返す。なければ加える。
+fn level_at(levels: &mut Vec<SoundingLevel>, pressure: f64) -> &mut SoundingLevel {
+    match levels.iter().position(|x| x.pressure == pressure) {
+        Some(i) => &mut levels[i],
+        None => {
+            levels.push(SoundingLevel {
+                pressure,
+                ..Default::default()
+            });
+            levels.last_mut().unwrap()
+        }
+    }
+}
+
+// 水蒸気圧 [hPa] から露点温度 [°C] を返す。
+fn dewpoint_from_vapor_pressure(e: f64) -> Option<f64> {
+    if e <= 0.0 {
+        return None;
+    }
+    let gamma = (e / MAGNUS_A).ln();
+    Some(MAGNUS_C * gamma / (MAGNUS_B - gamma))
+}
+
+// 飽和水蒸気圧 [hPa]
+fn saturation_vapor_pressure(t: f64) -> f64 {
+    MAGNUS_A * (MAGNUS_B * t / (MAGNUS_C + t)).exp()
+}
+
+impl SoundingLevel {
+    // 与えられた要素から露点温度、相対湿度、風向風速を補う。
+    fn complete(&mut self) {
+        if let (None, Some(t), Some(rh)) = (self.dewpoint, self.temperature, self.relative_humidity)
+        {
+            self.dewpoint = dewpoint_from_vapor_pressure(saturation_vapor_pressure(t) * rh / 100.0);
+        }
+        if let (Some(t), Some(td)) = (self.temperature, self.dewpoint) {
+            if self.relative_humidity.is_none() {
+                self.relative_humidity =
+                    Some(100.0 * saturation_vapor_pressure(td) / saturation_vapor_pressure(t));
+            }
+        }
+        if let (Some(u), Some(v)) = (self.u, self.v) {
+            self.wind_speed = Some(u.hypot(v));
+            // 風が吹いてくる方向を北から時計回りに測る。
+            self.wind_direction = Some((270.0 - v.atan2(u).to_degrees()).rem_euclid(360.0));
+        }
+    }
+
+    fn set(&mut self, element: Element, value: f64) {
+        match element {
+            Element::Temperature => self.temperature = Some(value - ZERO_CELSIUS),
+            Element::Dewpoint => self.dewpoint = Some(value - ZERO_CELSIUS),
+            Element::SpecificHumidity => {
+                // 比湿と気圧から水蒸気圧を求める。
+                if self.dewpoint.is_none() {
+                    let e = value * self.pressure / (0.622 + 0.378 * value);
+                    self.dewpoint = dewpoint_from_vapor_pressure(e);
+                }
+            }
+            Element::RelativeHumidity => self.relative_humidity = Some(value),
+            Element::U => self.u = Some(value),
+            Element::V => self.v = Some(value),
+            Element::GeopotentialHeight => self.height = Some(value),
+        }
+    }
+}
+
+impl<'a> SectionSets<'a> {
+    // index の SectionSet と同じ参照時刻、有効時刻、アンサンブルメンバーの等圧面の要素を集めて、
+    // 経度と緯度の鉛直プロファイルを返す。
+    pub(crate) fn sounding(
+        &self,
+        index: usize,
+        lon: f64,
+        lat: f64,
+        method: Interpolation,
+    ) -> Option<Sounding> {
+        let base = self.items.get(index)?;
+        let reference_time = base.reference_datetime()?;
+        let valid_time = base.datetime()?;
+        let member = base.ensemble().map(|x| x.perturbation_number);
+
+        let mut levels: Vec<SoundingLevel> = Vec::new();
+        let mut humidities: Vec<(f64, Element, f64)> = Vec::new();
+        for sectionset in self.iter() {
+            if sectionset.reference_datetime() != Some(reference_time)
+                || sectionset.datetime() != Some(valid_time)
+                || sectionset.ensemble().map(|x| x.perturbation_number) != member
+                || is_derived(&sectionset)
+            {
+                continue;
+            }
+            let (element, pressure) = match (element(&sectionset), isobaric_pressure(&sectionset)) {
+                (Some(element), Some(pressure)) => (element, pressure / 100.0),
+                _ => continue,
+            };
+            let value = match sectionset.sample(lon, lat, method) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            // 比湿は露点温度や相対湿度がなければ使うので後回しにする。
+            if let Element::SpecificHumidity = element {
+                humidities.push((pressure, element, value));
+                continue;
+            }
+
+            level_at(&mut levels, pressure).set(element, value);
+        }
+
+        for (pressure, element, value) in humidities {
+            let level = level_at(&mut levels, pressure);
+            if level.relative_humidity.is_none() {
+                level.set(element, value);
+            }
+        }
+
+        if levels.is_empty() {
+            return None;
+        }
+        for level in levels.iter_mut() {
+            level.complete();
+        }
+        levels.sort_by(|a, b| b.pressure.partial_cmp(&a.pressure).unwrap());
+
+        Some(Sounding {
+            reference_time,
+            valid_time,
+            longitude: lon,
+            latitude: lat,
+            levels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::super::super::utils_impl::parse;
+    use super::super::super::utils_impl::tests::*;
+    use super::*;
+
+    // 2023-06-01 00UTC 初期値の予報。全格子点の値が value の 2 x 2 格子
+    fn field(parameter: (u8, u8), hours: u32, surface: (u8, i8, i32), value: f32) -> Vec<u8> {
+        message(
+            0,
+            &[
+                vec![identification(34, Utc.ymd(2023, 6, 1).and_hms(0, 0, 0))],
+                vec![latlon_grid(2, 2, 36.0, 139.0, 1.0, 1.0)],
+                vec![product(0, &horizontal(parameter, 1, hours, surface))],
+                simple_packing(&[value; 4]),
+            ]
+            .concat(),
+        )
+    }
+
+    fn assert_near(a: Option<f64>, b: f64) {
+        assert!((a.unwrap() - b).abs() < 1e-3, "{:?} != {}", a, b);
+    }
+
+    #[test]
+    fn sounding() {
+        let hpa850 = (100, 0, 85000);
+        let hpa500 = (100, -2, 500);
+        let buf = [
+            field((0, 0), 6, hpa500, 253.15),
+            field((0, 0), 6, hpa850, 283.15),
+            field((1, 1), 6, hpa850, 100.0),
+            field((1, 0), 6, hpa500, 0.01),
+            field((2, 2), 6, hpa850, -10.0),
+            field((2, 3), 6, hpa850, 0.0),
+            field((2, 2), 6, hpa500, 0.0),
+            field((2, 3), 6, hpa500, -20.0),
+            field((3, 5), 6, hpa500, 5880.0),
+            // 等圧面以外のレベルと、別の有効時刻は使わない。
+            field((0, 0), 6, (103, 0, 2), 300.0),
+            field((0, 0), 12, (100, 0, 70000), 270.0),
+        ]
+        .concat();
+        let sectionsets = parse(&buf);
+
+        let sounding = sectionsets
+            .sounding(0, 139.5, 35.5, Interpolation::Bilinear)
+            .unwrap();
+        assert_eq!(sounding.valid_time, Utc.ymd(2023, 6, 1).and_hms(6, 0, 0));
+        let pressures: Vec<f64> = sounding.levels.iter().map(|x| x.pressure).collect();
+        assert_eq!(pressures, [850.0, 500.0]);
+
+        // 相対湿度 100% なら露点温度は気温に等しい。東風は風向 90 度
+        let level = &sounding.levels[0];
+        assert_near(level.temperature, 10.0);
+        assert_near(level.dewpoint, 10.0);
+        assert_near(level.wind_direction, 90.0);
+        assert_near(level.wind_speed, 10.0);
+        assert_eq!(level.height, None);
+
+        // 比湿から露点温度を求め、露点温度から相対湿度を求める。北風は風向 0 度
+        let level = &sounding.levels[1];
+        let e = 0.01 * 500.0 / (0.622 + 0.378 * 0.01);
+        assert_near(level.dewpoint, dewpoint_from_vapor_pressure(e).unwrap());
+        let rh = 100.0 * e / saturation_vapor_pressure(-20.0);
+        assert!((level.relative_humidity.unwrap() - rh).abs() < 1e-3);
+        assert_near(level.wind_direction, 0.0);
+        assert_near(level.height, 5880.0);
+
+        // 別の有効時刻は、その時刻の等圧面だけを使う。
+        let sounding = sectionsets
+            .sounding(10, 139.5, 35.5, Interpolation::Bilinear)
+            .unwrap();
+        assert_eq!(sounding.levels.len(), 1);
+        assert_eq!(sounding.levels[0].pressure, 700.0);
+
+        // 範囲外の index と、格子の外の地点
+        assert!(sectionsets
+            .sounding(99, 139.5, 35.5, Interpolation::Bilinear)
+            .is_none());
+        assert!(sectionsets
+            .sounding(0, 100.0, 35.5, Interpolation::Bilinear)
+            .is_none());
+    }
+
+    // Magnus の式の逆関数
+    #[test]
+    fn dewpoint() {
+        for t in [-30.0, 0.0, 25.0] {
+            assert_near(
+                dewpoint_from_vapor_pressure(saturation_vapor_pressure(t)),
+                t,
+            );
+        }
+        assert!(dewpoint_from_vapor_pressure(0.0).is_none());
+    }
+}
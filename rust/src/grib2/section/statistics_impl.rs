@@ -0,0 +1,216 @@
+//! GRIB2 statistical processing implementation
+
+use chrono::{DateTime, Utc};
+use std::fmt;
+
+use super::super::type_utils_impl::datetime_be;
+use super::super::type_utils_impl::u32_be;
+use super::super::type_utils_impl::u8_be;
+use super::time_offset_impl::time_offset_be;
+use super::Statistics;
+use super::TimeRange;
+
+const TIME_RANGE_LENGTH: usize = 12; // 時間範囲1つあたりのオクテット数
+
+// 統計処理の種類の名称（Code Table 4.10）
+pub(crate) fn process_name(process: usize) -> &'static str {
+    match process {
+        0 => "Average",
+        1 => "Accumulation",
+        2 => "Maximum",
+        3 => "Minimum",
+        4 => "Difference (end minus beginning)",
+        5 => "Root mean square",
+        6 => "Standard deviation",
+        7 => "Covariance",
+        8 => "Difference (beginning minus end)",
+        9 => "Ratio",
+        10 => "Standardized anomaly",
+        11 => "Summation",
+        12 => "Return period",
+        100 => "Severity",
+        101 => "Mode",
+        102 => "Index processing",
+        192..=254 => "Reserved for local use",
+        255 => "Missing",
+        _ => "Reserved",
+    }
+}
+
+// 全時間間隔の終了時刻から始まるバイト列を統計処理として返す。
+//   0-6   全時間間隔の終了時刻
+//   7     時間範囲の数 n
+//   8-11  統計処理で欠損となった資料の総数
+//   12-   時間範囲 12オクテット x n
+pub(crate) fn statistics_be(
+    src: &[u8],
+    reference_time: DateTime<Utc>,
+    start_time: DateTime<Utc>,
+) -> Result<Statistics, String> {
+    let end_time = if src[0..7].iter().all(|x| *x == u8::MAX) {
+        None
+    } else {
+        Some(datetime_be(&src[0..7]))
+    };
+    let count = u8_be(&src[7..8]) as usize;
+    let missing_count = u32_be(&src[8..12]) as usize;
+
+    let mut time_ranges = Vec::new();
+    for n in 0..count {
+        let i = 12 + n * TIME_RANGE_LENGTH;
+        if src.len() < i + TIME_RANGE_LENGTH {
+            break;
+        }
+
+        let process = u8_be(&src[i..i + 1]) as usize;
+        time_ranges.push(TimeRange {
+            process,
+            process_name: process_name(process),
+            increment_type: u8_be(&src[i + 1..i + 2]) as usize,
+            length: time_offset_be(&src[i + 2..i + 7]).ok(),
+            increment: time_offset_be(&src[i + 7..i + 12]).ok(),
+        });
+    }
+
+    // 終了時刻が欠損値なら、開始時刻に最も外側の時間範囲の長さを加えて求める。
+    let end_time = match end_time {
+        Some(end_time) => end_time,
+        None => time_ranges
+            .first()
+            .and_then(|x| x.length)
+            .ok_or_else(|| String::from("Missing end time of overall time interval"))?
+            .after(start_time)?,
+    };
+
+    Ok(Statistics {
+        reference_time,
+        start_time,
+        end_time,
+        missing_count,
+        time_ranges,
+    })
+}
+
+// 秒数を時間か分で表す。
+fn format_seconds(seconds: i64) -> String {
+    if seconds % 3600 == 0 {
+        format!("{} h", seconds / 3600)
+    } else {
+        format!("{} min", seconds / 60)
+    }
+}
+
+impl Statistics {
+    // 最も外側の時間範囲の統計処理の種類
+    pub fn process(&self) -> Option<usize> {
+        Some(self.time_ranges.first()?.process)
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.time_ranges.first() {
+            Some(time_range) => time_range.process_name,
+            None => process_name(255),
+        };
+
+        let start = (self.start_time - self.reference_time).num_seconds();
+        let end = (self.end_time - self.reference_time).num_seconds();
+        match self.process() {
+            // 積算は参照時刻からの範囲で表す。 e.g. Accumulation 0–3 h
+            Some(1) if start % 3600 == 0 && end % 3600 == 0 => {
+                write!(f, "{} {}–{} h", name, start / 3600, end / 3600)
+            }
+            Some(1) => write!(f, "{} {}–{} min", name, start / 60, end / 60),
+
+            // それ以外は期間の長さで表す。 e.g. Maximum over 6 h
+            _ => write!(f, "{} over {}", name, format_seconds(end - start)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    // 時間範囲1つ分の12オクテット。単位は Code Table 4.4
+    fn time_range(process: u8, unit: u8, length: u32) -> Vec<u8> {
+        let mut buf = vec![process, 2, unit];
+        buf.extend_from_slice(&length.to_be_bytes());
+        buf.extend_from_slice(&[unit, 0, 0, 0, 0]);
+        buf
+    }
+
+    // 2023-06-01 00:00:00 を参照時刻とし、開始時刻はそこからの分数で与える。
+    fn build(
+        end_time: &[u8],
+        time_ranges: &[Vec<u8>],
+        start_minute: i64,
+    ) -> Result<Statistics, String> {
+        let mut src = end_time.to_vec();
+        src.push(time_ranges.len() as u8);
+        src.extend_from_slice(&0u32.to_be_bytes());
+        for time_range in time_ranges.iter() {
+            src.extend_from_slice(time_range);
+        }
+        let reference_time = Utc.ymd(2023, 6, 1).and_hms(0, 0, 0);
+        let start_time = reference_time + chrono::Duration::minutes(start_minute);
+        statistics_be(&src, reference_time, start_time)
+    }
+
+    // 2023-06-01 hh:mm:00
+    fn end_time(hour: u8, minute: u8) -> Vec<u8> {
+        vec![0x07, 0xE7, 6, 1, hour, minute, 0]
+    }
+
+    // 積算は参照時刻からの範囲で表す。
+    #[test]
+    fn accumulation() {
+        let statistics = build(&end_time(3, 0), &[time_range(1, 1, 3)], 0).unwrap();
+        assert_eq!(statistics.process(), Some(1));
+        assert_eq!(statistics.end_time, Utc.ymd(2023, 6, 1).and_hms(3, 0, 0));
+        assert_eq!(statistics.time_ranges[0].process_name, "Accumulation");
+        assert_eq!(statistics.time_ranges[0].increment_type, 2);
+        assert_eq!(statistics.to_string(), "Accumulation 0–3 h");
+
+        let statistics = build(&end_time(0, 40), &[time_range(1, 0, 30)], 10).unwrap();
+        assert_eq!(statistics.to_string(), "Accumulation 10–40 min");
+    }
+
+    // それ以外の統計処理は期間の長さで表す。
+    #[test]
+    fn duration() {
+        let statistics = build(&end_time(12, 0), &[time_range(2, 1, 6)], 360).unwrap();
+        assert_eq!(statistics.to_string(), "Maximum over 6 h");
+
+        // 内側の時間範囲があっても、最も外側の時間範囲の統計処理を使う。
+        let time_ranges = [time_range(0, 1, 24), time_range(3, 1, 1)];
+        let statistics = build(&end_time(23, 0), &time_ranges, -60).unwrap();
+        assert_eq!(statistics.time_ranges.len(), 2);
+        assert_eq!(statistics.time_ranges[1].process_name, "Minimum");
+        assert_eq!(statistics.to_string(), "Average over 24 h");
+    }
+
+    // 終了時刻が欠損値なら、開始時刻に最も外側の時間範囲の長さを加える。
+    #[test]
+    fn missing_end_time() {
+        let statistics = build(&[u8::MAX; 7], &[time_range(1, 1, 6)], 180).unwrap();
+        assert_eq!(statistics.end_time, Utc.ymd(2023, 6, 1).and_hms(9, 0, 0));
+        assert_eq!(statistics.to_string(), "Accumulation 3–9 h");
+
+        // 時間範囲がなければ終了時刻を求められない。
+        assert!(build(&[u8::MAX; 7], &[], 0).is_err());
+    }
+
+    // 予約された単位の時間範囲は長さを None とする。
+    #[test]
+    fn reserved_unit() {
+        let statistics = build(&end_time(3, 0), &[time_range(1, 200, 3)], 0).unwrap();
+        assert!(statistics.time_ranges[0].length.is_none());
+        assert!(build(&[u8::MAX; 7], &[time_range(1, 200, 3)], 0).is_err());
+        assert_eq!(process_name(192), "Reserved for local use");
+        assert_eq!(process_name(13), "Reserved");
+    }
+}
@@ -0,0 +1,159 @@
+//! GRIB2 time offset implementation
+
+use chrono::{DateTime, Duration, Months, Utc};
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::super::type_utils_impl::u32_be;
+use super::super::type_utils_impl::u8_be;
+use super::TimeOffset;
+
+// 時間の単位(1) と値(4) の5オクテットから時間を返す。
+// 単位は Code Table 4.4
+pub(crate) fn time_offset_be(src: &[u8]) -> Result<TimeOffset, String> {
+    assert!(src.len() == 5);
+    let unit = u8_be(&src[0..1]);
+    let value = u32_be(&src[1..5]) as i64;
+
+    match unit {
+        0 => Ok(TimeOffset::Seconds(value * 60)),      // Minute
+        1 => Ok(TimeOffset::Seconds(value * 60 * 60)), // Hour
+        2 => Ok(TimeOffset::Seconds(value * 24 * 60 * 60)), // Day
+        3 => Ok(TimeOffset::Months(value)),            // Month
+        4 => Ok(TimeOffset::Months(value * 12)),       // Year
+        5 => Ok(TimeOffset::Months(value * 12 * 10)),  // Decade (10 years)
+        6 => Ok(TimeOffset::Months(value * 12 * 30)),  // Normal (30 years)
+        7 => Ok(TimeOffset::Months(value * 12 * 100)), // Century (100 years)
+        10 => Ok(TimeOffset::Seconds(value * 3 * 60 * 60)), // 3 hours
+        11 => Ok(TimeOffset::Seconds(value * 6 * 60 * 60)), // 6 hours
+        12 => Ok(TimeOffset::Seconds(value * 12 * 60 * 60)), // 12 hours
+        13 => Ok(TimeOffset::Seconds(value)),          // Second
+        255 => Err(String::from("Missing indicator of unit of time range")),
+        _ => Err(format!(
+            "Reserved indicator of unit of time range: {}",
+            unit
+        )),
+    }
+}
+
+impl TimeOffset {
+    // 時刻に時間を加えた時刻を返す。月の加算は暦に従う。
+    pub fn after(&self, datetime: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        // 月数が u32 に収まらなければ範囲外とする。
+        let result = match *self {
+            TimeOffset::Seconds(seconds) => datetime.checked_add_signed(Duration::seconds(seconds)),
+            TimeOffset::Months(months) if 0 <= months => u32::try_from(months)
+                .ok()
+                .and_then(|x| datetime.checked_add_months(Months::new(x))),
+            TimeOffset::Months(months) => u32::try_from(months.unsigned_abs())
+                .ok()
+                .and_then(|x| datetime.checked_sub_months(Months::new(x))),
+        };
+        result.ok_or_else(|| format!("Out of range datetime: {} + {}", datetime, self))
+    }
+}
+
+impl fmt::Display for TimeOffset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TimeOffset::Seconds(seconds) if seconds % 3600 == 0 => {
+                write!(f, "{} h", seconds / 3600)
+            }
+            TimeOffset::Seconds(seconds) if seconds % 60 == 0 => write!(f, "{} min", seconds / 60),
+            TimeOffset::Seconds(seconds) => write!(f, "{} s", seconds),
+            TimeOffset::Months(months) if months % 12 == 0 => write!(f, "{} years", months / 12),
+            TimeOffset::Months(months) => write!(f, "{} months", months),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn offset(unit: u8, value: u32) -> Result<TimeOffset, String> {
+        time_offset_be(&[[unit].as_slice(), &value.to_be_bytes()].concat())
+    }
+
+    // 単位ごとに秒数か月数に換算する。
+    #[test]
+    fn units() {
+        let seconds = |unit, value| match offset(unit, value) {
+            Ok(TimeOffset::Seconds(seconds)) => Some(seconds),
+            _ => None,
+        };
+        assert_eq!(seconds(0, 30), Some(1800));
+        assert_eq!(seconds(1, 6), Some(21600));
+        assert_eq!(seconds(2, 2), Some(172800));
+        assert_eq!(seconds(10, 1), Some(10800));
+        assert_eq!(seconds(11, 1), Some(21600));
+        assert_eq!(seconds(12, 1), Some(43200));
+        assert_eq!(seconds(13, 90), Some(90));
+
+        let months = |unit, value| match offset(unit, value) {
+            Ok(TimeOffset::Months(months)) => Some(months),
+            _ => None,
+        };
+        assert_eq!(months(3, 3), Some(3));
+        assert_eq!(months(4, 1), Some(12));
+        assert_eq!(months(5, 1), Some(120));
+        assert_eq!(months(6, 1), Some(360));
+        assert_eq!(months(7, 1), Some(1200));
+
+        assert!(offset(8, 1).is_err());
+        assert!(offset(255, 1).is_err());
+    }
+
+    // 月の加算は暦に従い、月末を越える日は月末に丸める。
+    #[test]
+    fn month_arithmetic() {
+        let datetime = Utc.ymd(2023, 1, 31).and_hms(12, 0, 0);
+        assert_eq!(
+            TimeOffset::Months(1).after(datetime),
+            Ok(Utc.ymd(2023, 2, 28).and_hms(12, 0, 0))
+        );
+        assert_eq!(
+            TimeOffset::Months(13).after(datetime),
+            Ok(Utc.ymd(2024, 2, 29).and_hms(12, 0, 0))
+        );
+        assert_eq!(
+            TimeOffset::Months(-2).after(datetime),
+            Ok(Utc.ymd(2022, 11, 30).and_hms(12, 0, 0))
+        );
+        assert_eq!(
+            offset(4, 30).unwrap().after(datetime),
+            Ok(Utc.ymd(2053, 1, 31).and_hms(12, 0, 0))
+        );
+        assert_eq!(
+            TimeOffset::Seconds(-3600).after(datetime),
+            Ok(Utc.ymd(2023, 1, 31).and_hms(11, 0, 0))
+        );
+    }
+
+    // 暦の範囲を越える時間はエラーとする。
+    #[test]
+    fn out_of_range() {
+        let datetime = Utc.ymd(2023, 1, 1).and_hms(0, 0, 0);
+        for offset in [
+            TimeOffset::Months(i64::MAX),
+            TimeOffset::Months(i64::MIN),
+            TimeOffset::Months(u32::MAX as i64 + 1),
+            TimeOffset::Months(-(u32::MAX as i64) - 1),
+            TimeOffset::Seconds(1_000_000_000_000_000),
+        ] {
+            let result = offset.after(datetime);
+            assert!(result.unwrap_err().starts_with("Out of range datetime"));
+        }
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(TimeOffset::Seconds(10800).to_string(), "3 h");
+        assert_eq!(TimeOffset::Seconds(600).to_string(), "10 min");
+        assert_eq!(TimeOffset::Seconds(45).to_string(), "45 s");
+        assert_eq!(TimeOffset::Months(24).to_string(), "2 years");
+        assert_eq!(TimeOffset::Months(3).to_string(), "3 months");
+    }
+}
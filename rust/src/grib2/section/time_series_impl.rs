@@ -0,0 +1,283 @@
+//! GRIB2 time series implementation
+
+use std::collections::BTreeMap;
+
+use super::Interpolation;
+use super::SectionSet;
+use super::SectionSets;
+use super::TimeSeriesPoint;
+
+// 同じ要素とみなすためのキー。時刻とレベル以外のパラメーター、プロダクトの種類が等しければ同じキーになる。
+// 作成中枢、作成処理、格子系が異なるものは別のモデルの資料として区別する。
+// 統計処理は処理の種類と期間の長さで区別する。 e.g. 1時間降水量と3時間降水量
+pub(crate) fn variable_key(sectionset: &SectionSet) -> Option<String> {
+    product_key(sectionset, true)
+}
+
+// アンサンブルのメンバーを区別しない、同じ要素とみなすためのキー
+pub(crate) fn ensemble_variable_key(sectionset: &SectionSet) -> Option<String> {
+    product_key(sectionset, false)
+}
+
+// member が true ならアンサンブルの摂動番号も区別する。
+fn product_key(sectionset: &SectionSet, member: bool) -> Option<String> {
+    let mut key = format!(
+        "{}/{}/{}",
+        sectionset.discipline()?,
+        sectionset.parameter_category()?,
+        sectionset.parameter_number()?
+    );
+
+    key += &format!("|centre:{}", sectionset.centre()?);
+    if let Some(process) = sectionset.generating_process_identifier() {
+        key += &format!("|process:{}", process);
+    }
+    let (template, ni, nj) = sectionset.grid_shape()?;
+    key += &format!("|grid:3.{}:{}x{}", template, ni, nj);
+    if let Some(statistics) = sectionset.statistics() {
+        let processes: Vec<String> = statistics
+            .time_ranges
+            .iter()
+            .map(|x| x.process.to_string())
+            .collect();
+        let length = statistics.end_time - statistics.start_time;
+        key += &format!(
+            "|statistics:{}:{}",
+            processes.join(","),
+            length.num_seconds()
+        );
+    }
+    // 種類（コントロール、摂動など）もメンバーごとに異なるので、メンバーを区別するときだけ含める。
+    if let Some(ensemble) = sectionset.ensemble() {
+        if member {
+            key += &format!(
+                "|ensemble:{}:{}",
+                ensemble.ensemble_type, ensemble.perturbation_number
+            );
+        } else {
+            key += "|ensemble";
+        }
+    }
+    if let Some(derived_forecast) = sectionset.derived_forecast() {
+        key += &format!("|derived:{}", derived_forecast.derived_type);
+    }
+    if let Some(probability) = sectionset.probability() {
+        key += &format!("|probability:{}", probability);
+    }
+    if let Some(percentile) = sectionset.percentile() {
+        key += &format!("|percentile:{}", percentile.value);
+    }
+    if let Some(constituent) = sectionset.constituent() {
+        key += &format!("|constituent:{}", constituent);
+    }
+    if let Some(bands) = sectionset.bands() {
+        let bands: Vec<String> = bands.iter().map(|x| x.to_string()).collect();
+        key += &format!("|bands:{}", bands.join(","));
+    }
+    Some(key)
+}
+
+// 時系列で同じ要素とみなすためのキー。レベルも等しければ同じキーになる。
+fn series_key(sectionset: &SectionSet) -> Option<String> {
+    let mut key = variable_key(sectionset)?;
+    if let Some(level) = sectionset.level() {
+        key += &format!("|level:{}", level);
+    }
+    Some(key)
+}
+
+impl<'a> SectionSets<'a> {
+    // index の SectionSet と同じ要素の全時刻について、経度と緯度の値を有効時刻の順に返す。
+    // 同じ有効時刻が複数あれば、参照時刻が最も新しいものを使う。
+    pub(crate) fn time_series(
+        &self,
+        index: usize,
+        lon: f64,
+        lat: f64,
+        method: Interpolation,
+    ) -> Vec<TimeSeriesPoint> {
+        let key = match self.items.get(index).and_then(series_key) {
+            Some(key) => key,
+            None => return Vec::new(),
+        };
+
+        let mut points = BTreeMap::new();
+        for (i, sectionset) in self.iter().enumerate() {
+            if series_key(&sectionset).as_ref() != Some(&key) {
+                continue;
+            }
+            let (reference_time, valid_time) =
+                match (sectionset.reference_datetime(), sectionset.datetime()) {
+                    (Some(reference_time), Some(valid_time)) => (reference_time, valid_time),
+                    _ => continue,
+                };
+
+            let is_newer = match points.get(&valid_time) {
+                Some(TimeSeriesPoint {
+                    reference_time: current,
+                    ..
+                }) => *current < reference_time,
+                None => true,
+            };
+            if is_newer {
+                points.insert(
+                    valid_time,
+                    TimeSeriesPoint {
+                        index: i,
+                        reference_time,
+                        valid_time,
+                        value: sectionset.sample(lon, lat, method),
+                    },
+                );
+            }
+        }
+        points.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::super::super::utils_impl::parse;
+    use super::super::super::utils_impl::tests::*;
+    use super::*;
+
+    // 東経139度、北緯36度から 1 度間隔の格子の地上気温。値は全格子点で value
+    fn temperature(
+        centre: u16,
+        reference_time: DateTime<Utc>,
+        process: u8,
+        hours: u32,
+        ni: u32,
+        value: f32,
+    ) -> Vec<u8> {
+        message(
+            0,
+            &[
+                vec![identification(centre, reference_time)],
+                vec![latlon_grid(ni, 2, 36.0, 139.0, 1.0, 1.0)],
+                vec![product(0, &horizontal((0, 0), process, hours, (103, 0, 2)))],
+                simple_packing(&vec![value; ni as usize * 2]),
+            ]
+            .concat(),
+        )
+    }
+
+    #[test]
+    fn time_series() {
+        let t0 = Utc.ymd(2023, 6, 1).and_hms(0, 0, 0);
+        let t1 = Utc.ymd(2023, 6, 1).and_hms(6, 0, 0);
+        let buf = [
+            temperature(34, t0, 1, 12, 3, 3.0),
+            temperature(34, t0, 1, 0, 3, 1.0),
+            temperature(34, t0, 1, 6, 3, 2.0),
+            // 同じ有効時刻なら参照時刻が新しいものを使う。
+            temperature(34, t1, 1, 0, 3, 20.0),
+            // 作成中枢、作成処理、格子系が異なるものは別の要素
+            temperature(7, t0, 1, 18, 3, 0.0),
+            temperature(34, t0, 2, 18, 3, 0.0),
+            temperature(34, t0, 1, 18, 4, 0.0),
+        ]
+        .concat();
+        let sectionsets = parse(&buf);
+
+        let points = sectionsets.time_series(1, 139.5, 36.0, Interpolation::Bilinear);
+        let valid_times: Vec<DateTime<Utc>> = points.iter().map(|x| x.valid_time).collect();
+        assert_eq!(valid_times, [t0, t1, t0 + chrono::Duration::hours(12)]);
+        let indices: Vec<usize> = points.iter().map(|x| x.index).collect();
+        assert_eq!(indices, [1, 3, 0]);
+        assert_eq!(points[1].reference_time, t1);
+        let values: Vec<Option<f64>> = points.iter().map(|x| x.value).collect();
+        assert_eq!(values, [Some(1.0), Some(20.0), Some(3.0)]);
+
+        // 格子の外の地点は値がない。
+        let points = sectionsets.time_series(0, 100.0, 36.0, Interpolation::Nearest);
+        assert_eq!(points.len(), 3);
+        assert!(points.iter().all(|x| x.value.is_none()));
+
+        for index in 4..7 {
+            let points = sectionsets.time_series(index, 139.0, 36.0, Interpolation::Nearest);
+            assert_eq!(points.len(), 1);
+            assert_eq!(points[0].index, index);
+        }
+        assert!(sectionsets
+            .time_series(7, 139.0, 36.0, Interpolation::Nearest)
+            .is_empty());
+    }
+
+    // レベルが異なれば別の時系列、統計期間の長さが異なれば別の要素とする。
+    #[test]
+    fn keys() {
+        let t0 = Utc.ymd(2023, 6, 1).and_hms(0, 0, 0);
+        let precipitation = |hours: u32, length: u32| {
+            let mut body = horizontal((1, 8), 1, hours - length, (1, 0, 0));
+            body.extend(statistics(
+                t0 + chrono::Duration::hours(hours as i64),
+                1,
+                length,
+            ));
+            message(
+                0,
+                &[
+                    vec![identification(34, t0)],
+                    vec![latlon_grid(2, 2, 36.0, 139.0, 1.0, 1.0)],
+                    vec![product(8, &body)],
+                    simple_packing(&[0.0; 4]),
+                ]
+                .concat(),
+            )
+        };
+        let buf = [
+            precipitation(3, 3),
+            precipitation(6, 3),
+            precipitation(6, 6),
+            temperature(34, t0, 1, 0, 3, 0.0),
+        ]
+        .concat();
+        let sectionsets = parse(&buf);
+        let keys: Vec<Option<String>> = sectionsets.iter().map(|x| series_key(&x)).collect();
+        assert_eq!(keys[0], keys[1]);
+        assert_ne!(keys[0], keys[2]);
+        assert_eq!(
+            keys[3].as_deref(),
+            Some("0/0/0|centre:34|process:1|grid:3.0:3x2|level:Specified height level above ground 2 m")
+        );
+    }
+
+    // メンバーを区別しないキーでは、コントロールと摂動のメンバーを同じ要素とする。
+    #[test]
+    fn ensemble_keys() {
+        let t0 = Utc.ymd(2023, 6, 1).and_hms(0, 0, 0);
+        // アンサンブルの種類（0: 高解像度コントロール、3: 負の摂動、4: 正の摂動）と摂動番号
+        let member = |ensemble_type: u8, number: u8| {
+            let mut body = horizontal((0, 0), 1, 0, (103, 0, 2));
+            body.extend_from_slice(&[ensemble_type, number, 51]);
+            message(
+                0,
+                &[
+                    vec![identification(34, t0)],
+                    vec![latlon_grid(2, 2, 36.0, 139.0, 1.0, 1.0)],
+                    vec![product(1, &body)],
+                    simple_packing(&[0.0; 4]),
+                ]
+                .concat(),
+            )
+        };
+        let buf = [member(0, 0), member(3, 1), member(4, 2)].concat();
+        let sectionsets = parse(&buf);
+
+        let keys: Vec<Option<String>> = sectionsets.iter().map(|x| variable_key(&x)).collect();
+        assert_ne!(keys[0], keys[1]);
+        assert_ne!(keys[1], keys[2]);
+        assert!(keys[1].as_ref().unwrap().ends_with("|ensemble:3:1"));
+
+        let keys: Vec<Option<String>> = sectionsets
+            .iter()
+            .map(|x| ensemble_variable_key(&x))
+            .collect();
+        assert_eq!(keys[0], keys[1]);
+        assert_eq!(keys[1], keys[2]);
+        assert!(keys[0].as_ref().unwrap().ends_with("|ensemble"));
+    }
+}
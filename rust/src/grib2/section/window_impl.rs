@@ -0,0 +1,227 @@
+//! GRIB2 grid window (crop and decimate) implementation
+
+use super::section3_template::TemplateNumber as Section3TemplateNumber;
+use super::Bounds;
+use super::SectionSet;
+use super::Window;
+
+const FULL_CIRCLE: isize = 360_000_000; // [10^-6 度]
+
+impl<'a> SectionSet<'a> {
+    // 範囲 [10^-6 度] を含む格子を stride 格子おきに切り出す範囲を返す。
+    // 経緯度格子（テンプレート 3.0）で北から南、西から東に走査する格子に限る。
+    // 経度を一周する格子なら、範囲が最初の格子点の西側や経度 0 度をまたいでも切り出す。
+    pub(crate) fn window(&self, viewport: &Bounds, stride: usize) -> Option<Window> {
+        match self.geometry()? {
+            Section3TemplateNumber::T0(_) => {}
+            _ => return None,
+        }
+        let periodic = self.geometry()?.is_periodic();
+        let sec3 = self.section3?;
+        // 走査方向が逆の格子や格子間隔が欠損値の格子は扱わない。
+        if sec3.scanning_mode()? & 0xC0 != 0
+            || sec3.di() == u32::MAX as usize
+            || sec3.dj() == u32::MAX as usize
+        {
+            return None;
+        }
+        let (ni, nj) = (sec3.ni() as isize, sec3.nj() as isize);
+        let (lo1, la1) = (sec3.lo1(), sec3.la1());
+        let (di, dj) = (sec3.di() as isize, sec3.dj() as isize);
+        if ni == 0 || nj == 0 || di <= 0 || dj <= 0 || viewport.right < viewport.left {
+            return None;
+        }
+        let stride = stride.max(1);
+
+        // 列は範囲の西端から東端まで
+        let offset = if periodic {
+            (viewport.left - lo1).rem_euclid(FULL_CIRCLE)
+        } else {
+            viewport.left - lo1
+        };
+        let i0 = offset.div_euclid(di);
+        let i1 = (offset + viewport.right - viewport.left + di - 1).div_euclid(di) + 1;
+        let (i0, i1) = if periodic {
+            (i0, i1.min(i0 + ni))
+        } else {
+            (i0.max(0), i1.min(ni))
+        };
+
+        // 行は範囲の北端から南端まで
+        let j0 = (la1 - viewport.top).div_euclid(dj).max(0);
+        let j1 = ((la1 - viewport.bottom + dj - 1).div_euclid(dj) + 1).min(nj);
+        if i1 <= i0 || j1 <= j0 {
+            return None;
+        }
+
+        let columns: Vec<usize> = (i0..i1)
+            .step_by(stride)
+            .map(|i| i.rem_euclid(ni) as usize)
+            .collect();
+        let rows: Vec<usize> = (j0..j1).step_by(stride).map(|j| j as usize).collect();
+
+        let stride = stride as isize;
+        let left = lo1 + i0 * di;
+        let top = la1 - j0 * dj;
+        Some(Window {
+            bounds: Bounds {
+                left,
+                bottom: top - (rows.len() as isize - 1) * stride * dj,
+                right: left + (columns.len() as isize - 1) * stride * di,
+                top,
+            },
+            di: (stride * di) as usize,
+            dj: (stride * dj) as usize,
+            columns,
+            rows,
+        })
+    }
+}
+
+impl Window {
+    pub(crate) fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub(crate) fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    // 幅 width の格子の値から範囲を切り出す。
+    pub(crate) fn crop<T: Copy>(&self, src: &[T], width: usize) -> Vec<T> {
+        self.rows
+            .iter()
+            .flat_map(|j| self.columns.iter().map(move |i| src[j * width + i]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::super::super::utils_impl::parse;
+    use super::super::super::utils_impl::tests::*;
+    use super::*;
+
+    const DEGREE: f64 = 1e6;
+
+    // 格子点の値が通し番号の格子
+    fn grid(grid: Vec<u8>, count: usize) -> Vec<u8> {
+        let values: Vec<f32> = (0..count).map(|x| x as f32).collect();
+        message(
+            0,
+            &[
+                vec![identification(34, Utc.ymd(2023, 6, 1).and_hms(0, 0, 0))],
+                vec![grid],
+                vec![product(0, &horizontal((0, 0), 1, 0, (1, 0, 0)))],
+                simple_packing(&values),
+            ]
+            .concat(),
+        )
+    }
+
+    fn bounds(left: f64, bottom: f64, right: f64, top: f64) -> Bounds {
+        let micro = |x: f64| (x * DEGREE) as isize;
+        Bounds {
+            left: micro(left),
+            bottom: micro(bottom),
+            right: micro(right),
+            top: micro(top),
+        }
+    }
+
+    fn degrees(bounds: &Bounds) -> [f64; 4] {
+        [bounds.left, bounds.bottom, bounds.right, bounds.top].map(|x| x as f64 / DEGREE)
+    }
+
+    // 北緯40度、東経130度から 1 度間隔の 5 x 4 の格子から、範囲を含む格子点を切り出す。
+    #[test]
+    fn crop() {
+        let buf = grid(latlon_grid(5, 4, 40.0, 130.0, 1.0, 1.0), 20);
+        let sectionsets = parse(&buf);
+        let sectionset = sectionsets.try_get(0).unwrap();
+
+        let window = sectionset
+            .window(&bounds(131.5, 37.9, 133.2, 39.5), 1)
+            .unwrap();
+        assert_eq!(window.columns, [1, 2, 3, 4]);
+        assert_eq!(window.rows, [0, 1, 2, 3]);
+        assert_eq!(degrees(&window.bounds), [131.0, 37.0, 134.0, 40.0]);
+        assert_eq!((window.di, window.dj), (1_000_000, 1_000_000));
+
+        // 格子をはみ出す範囲は格子の内側に収める。
+        let window = sectionset
+            .window(&bounds(120.0, 38.5, 131.0, 50.0), 1)
+            .unwrap();
+        assert_eq!(window.columns, [0, 1]);
+        assert_eq!(window.rows, [0, 1, 2]);
+        let values: Vec<usize> = (0..20).collect();
+        assert_eq!(window.crop(&values, 5), [0, 1, 5, 6, 10, 11]);
+
+        // 格子と重ならない範囲、東西が逆の範囲
+        assert!(sectionset
+            .window(&bounds(140.0, 37.0, 141.0, 38.0), 1)
+            .is_none());
+        assert!(sectionset
+            .window(&bounds(133.0, 37.0, 131.0, 38.0), 1)
+            .is_none());
+    }
+
+    // stride 格子おきに間引き、範囲と格子間隔も stride 倍にする。
+    #[test]
+    fn decimate() {
+        let buf = grid(latlon_grid(5, 4, 40.0, 130.0, 1.0, 1.0), 20);
+        let sectionsets = parse(&buf);
+        let sectionset = sectionsets.try_get(0).unwrap();
+
+        let window = sectionset
+            .window(&bounds(130.0, 37.0, 134.0, 40.0), 2)
+            .unwrap();
+        assert_eq!((window.width(), window.height()), (3, 2));
+        assert_eq!(degrees(&window.bounds), [130.0, 38.0, 134.0, 40.0]);
+        assert_eq!((window.di, window.dj), (2_000_000, 2_000_000));
+        let values: Vec<usize> = (0..20).collect();
+        assert_eq!(window.crop(&values, 5), [0, 2, 4, 10, 12, 14]);
+
+        // stride 0 は 1 とみなす。
+        let window = sectionset
+            .window(&bounds(130.0, 37.0, 134.0, 40.0), 0)
+            .unwrap();
+        assert_eq!((window.width(), window.height()), (5, 4));
+    }
+
+    // 経度を一周する格子では経度 0 度をまたぐ範囲を切り出し、列は一周を超えない。
+    #[test]
+    fn periodic() {
+        let buf = grid(latlon_grid(36, 3, 10.0, 0.0, 10.0, 10.0), 108);
+        let sectionsets = parse(&buf);
+        let sectionset = sectionsets.try_get(0).unwrap();
+
+        let window = sectionset
+            .window(&bounds(-15.0, -10.0, 15.0, 10.0), 1)
+            .unwrap();
+        assert_eq!(window.columns, [34, 35, 0, 1, 2]);
+        assert_eq!(degrees(&window.bounds), [340.0, -10.0, 20.0 + 360.0, 10.0]);
+
+        let window = sectionset
+            .window(&bounds(-180.0, -10.0, 540.0, 10.0), 1)
+            .unwrap();
+        assert_eq!(window.width(), 36);
+        assert_eq!(window.columns[0], 18);
+    }
+
+    // 西から東、北から南に走査する格子に限る。
+    #[test]
+    fn scanning_mode() {
+        let mut section3 = latlon_grid(5, 4, 40.0, 130.0, 1.0, 1.0);
+        *section3.last_mut().unwrap() = 0x40;
+        let buf = grid(section3, 20);
+        let sectionsets = parse(&buf);
+        assert!(sectionsets
+            .try_get(0)
+            .unwrap()
+            .window(&bounds(130.0, 37.0, 134.0, 40.0), 1)
+            .is_none());
+    }
+}
@@ -105,6 +105,21 @@ pub(crate) fn i64_be(src: &[u8]) -> i64 {
     return u as i64; // 正数なので変換しない。
 }
 
+// 尺度因子(1) と尺度付きの値(4) の5オクテットから値を返す。
+// 尺度付きの値が欠損値なら None を返す。
+pub(crate) fn scaled_value_be(src: &[u8]) -> Option<f64> {
+    assert!(src.len() == 5);
+    if u32_be(&src[1..5]) == u32::MAX {
+        return None;
+    }
+
+    let value = i32_be(&src[1..5]) as f64;
+    match u8_be(&src[0..1]) {
+        u8::MAX => Some(value),
+        _ => Some(value / 10.0f64.powi(i8_be(&src[0..1]) as i32)),
+    }
+}
+
 pub(crate) fn float_be(src: &[u8]) -> f32 {
     assert!(src.len() == 4);
     f32::from_be_bytes(src.try_into().unwrap())
@@ -120,36 +135,3 @@ pub(crate) fn datetime_be(src: &[u8]) -> DateTime<Utc> {
     let second = src[6] as u32;
     Utc.ymd(year, month, day).and_hms(hour, minute, second)
 }
-
-// [sec]
-pub(crate) fn time_span_be(src: &[u8]) -> usize {
-    assert!(src.len() == 5);
-    let unit = u8_be(&src[0..1]);
-    let value = u32_be(&src[1..5]) as usize;
-
-    let span = match unit {
-        0 => Some(value * 60),           // Minute
-        1 => Some(value * 60 * 60),      // Hour
-        2 => Some(value * 24 * 60 * 60), // Day
-        // 3=> // Month
-        // 4=> // Year
-        // 5=> // Decade (10 years)
-        // 6=> // Normal (30 years)
-        // 7=> // Century (100 years)
-        // 8..=9=>   // Reserved
-        10 => Some(value * 3 * 60 * 60),  // 3 hours
-        11 => Some(value * 6 * 60 * 60),  // 6 hours
-        12 => Some(value * 12 * 60 * 60), // 12 hours
-        13 => Some(value),                // Second
-        // 14..=191=>    // Reserved
-        // 192..=254=>   // Reserved for local use
-        // 255=>   // Missing
-        _ => None,
-    };
-
-    assert!(span != None);
-    match span {
-        Some(x) => x,
-        None => 0,
-    }
-}
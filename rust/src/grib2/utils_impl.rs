@@ -99,56 +99,171 @@ pub(crate) fn parse(buf: &[u8]) -> SectionSets {
     sectionsets
 }
 
-pub(crate) fn first_plane_name(
-    plane_type: usize,
-    plane_factor: isize,
-    plane_value: isize,
-) -> Option<String> {
-    let v = (plane_value as f32) / 10.0f32.powi(plane_factor as i32);
-    match plane_type {
-        0 => Some(String::from("Reserved")),
-        1 => Some(String::from("Ground or Water Surface")),
-        2 => Some(String::from("Cloud Base Level")),
-        3 => Some(String::from("Level of Cloud Tops")),
-        4 => Some(String::from("Level of 0o C Isotherm")),
-        5 => Some(String::from(
-            "Level of Adiabatic Condensation Lifted from the Surface",
-        )),
-        6 => Some(String::from("Maximum Wind Level")),
-        7 => Some(String::from("Tropopause")),
-        8 => Some(String::from("Nominal Top of the Atmosphere")),
-        9 => Some(String::from("Sea Bottom")),
-        10 => Some(String::from("Entire Atmosphere")),
-        11 => Some(format!("Cumulonimbus Base (CB): {}[m]", v)),
-        12 => Some(format!("Cumulonimbus Top (CT): {}[m]", v)),
-        13 => Some(format!("Lowest level where vertically integrated cloud cover exceeds the specified percentage (cloud base for a given percentage cloud cover): {}[%]", v)),
-        14 => Some(String::from("Level of free convection (LFC)")),
-        15 => Some(String::from("Convection condensation level (CCL)")),
-        16 => Some(String::from("Level of neutral buoyancy or equilibrium (LNB)")),
-        17..=19 => Some(String::from("Reserved")),
-        20 => Some(format!("Isothermal Level: {}[K]", v)),
-        21 => Some(format!("Lowest level where mass density exceeds the specified value(base for a given threshold of mass density): {}[kg m-3]", v)),
-        22 => Some(format!("Highest level where mass density exceeds the specified value (top for a given threshold of mass density): {}[kg m-3]", v)),
-        23 => Some(format!("Lowest level where air concentration exceeds the specified value (base for a given threshold of air concentration: {}[Bq m-3]", v)),
-        24 => Some(format!("Highest level where air concentration exceeds the specified value (top for a given threshold of air concentration): {}[Bq m-3]", v)),
-        25 => Some(format!("Highest level where radar reflectivity exceeds the specified value (echo top for a given threshold of reflectivity): {}[dBZ]", v)),
-        26 => Some(format!("Convective cloud layer base: {}[m]", v)),
-        27 => Some(format!("Convective cloud layer top: {}[m]", v)),
-        28..=29 => Some(String::from("Reserved")),
-        30 => Some(format!("Specified radius from the centre of the Sun: {}[m]", v)),
-        31 => Some(String::from("Ionospheric D-region level")),
-        32 => Some(String::from("Ionospheric E-region level")),
-        33 => Some(String::from("Ionospheric F1-region level")),
-        34 => Some(String::from("Ionospheric F1-region level")),
-        35 => Some(String::from("Ionospheric F2-region level")),	
-        36..=99 => Some(String::from("Reserved")),
-        100 => Some(format!("Isobaric Surface: {}[Pa]", v)),
-        101 => Some(format!("Mean Sea Level: {}[m]", v)),
-        103 => Some(format!("Specified Height Level Above Ground: {}[m]", v)),
-        160 => Some(format!("Depth below sea level: {}[m]",v)),
-        161 => Some(format!("Depth below water surface: {}[m]",v)),
-        200 => Some(format!("タンクモデルの全タンク（土壌雨量指数）")),
-        201 => Some(format!("タンクモデルのタンク番号: {}", v)),
-        _ => None,
+#[cfg(test)]
+pub(crate) mod tests {
+    use chrono::{DateTime, Datelike, Timelike, Utc};
+
+    pub(crate) const MISSING_SURFACE: (u8, i8, i32) = (255, -1, -1); // 全ビットが立った固定面
+
+    // 節の長さと節番号を内容の前に加える。
+    pub(crate) fn section(number: u8, body: &[u8]) -> Vec<u8> {
+        let mut buf = ((body.len() + 5) as u32).to_be_bytes().to_vec();
+        buf.push(number);
+        buf.extend_from_slice(body);
+        buf
+    }
+
+    // 最上位ビットを符号とする整数。-1 なら全ビットを立てた欠損値とする。
+    pub(crate) fn signed(value: i32, octets: usize) -> Vec<u8> {
+        let bits = octets as u32 * 8;
+        let magnitude = if value == -1 {
+            u64::MAX >> (64 - bits)
+        } else {
+            let sign = if value < 0 { 1u64 << (bits - 1) } else { 0 };
+            sign | value.unsigned_abs() as u64
+        };
+        magnitude.to_be_bytes()[8 - octets..].to_vec()
+    }
+
+    // 第0節と第8節で節を挟んで1つのメッセージにする。
+    pub(crate) fn message(discipline: u8, sections: &[Vec<u8>]) -> Vec<u8> {
+        let length: usize = 16 + sections.iter().map(|x| x.len()).sum::<usize>() + 4;
+        let mut buf = b"GRIB".to_vec();
+        buf.extend_from_slice(&[0, 0, discipline, 2]);
+        buf.extend_from_slice(&(length as u64).to_be_bytes());
+        for section in sections.iter() {
+            buf.extend_from_slice(section);
+        }
+        buf.extend_from_slice(b"7777");
+        buf
+    }
+
+    // 第1節。作成中枢と参照時刻
+    pub(crate) fn identification(centre: u16, reference_time: DateTime<Utc>) -> Vec<u8> {
+        let mut body = centre.to_be_bytes().to_vec();
+        body.extend_from_slice(&[0, 0, 2, 1, 1]);
+        body.extend_from_slice(&(reference_time.year() as u16).to_be_bytes());
+        body.extend_from_slice(&[
+            reference_time.month() as u8,
+            reference_time.day() as u8,
+            reference_time.hour() as u8,
+            reference_time.minute() as u8,
+            reference_time.second() as u8,
+            0,
+            1,
+        ]);
+        section(1, &body)
+    }
+
+    // 第3節。経緯度格子（テンプレート 3.0）で、北西の格子点から西から東、北から南へ走査する。
+    pub(crate) fn latlon_grid(ni: u32, nj: u32, la1: f64, lo1: f64, di: f64, dj: f64) -> Vec<u8> {
+        let micro = |x: f64| signed((x * 1e6).round() as i32, 4);
+        let mut body = vec![0];
+        body.extend_from_slice(&(ni * nj).to_be_bytes());
+        body.extend_from_slice(&[0, 0, 0, 0, 6]);
+        body.extend_from_slice(&[0; 15]);
+        body.extend_from_slice(&ni.to_be_bytes());
+        body.extend_from_slice(&nj.to_be_bytes());
+        body.extend_from_slice(&0u32.to_be_bytes());
+        body.extend_from_slice(&u32::MAX.to_be_bytes());
+        body.extend(micro(la1));
+        body.extend(micro(lo1));
+        body.push(0x30);
+        body.extend(micro(la1 - (nj - 1) as f64 * dj));
+        body.extend(micro(lo1 + (ni - 1) as f64 * di));
+        body.extend_from_slice(&((di * 1e6).round() as u32).to_be_bytes());
+        body.extend_from_slice(&((dj * 1e6).round() as u32).to_be_bytes());
+        body.push(0x00);
+        section(3, &body)
+    }
+
+    // 第4節。プロダクト定義テンプレート番号とオクテット10以降
+    pub(crate) fn product(template: u16, body: &[u8]) -> Vec<u8> {
+        let mut buf = vec![0, 0];
+        buf.extend_from_slice(&template.to_be_bytes());
+        buf.extend_from_slice(body);
+        section(4, &buf)
+    }
+
+    // 固定面の種類、尺度因子、尺度付きの値の6オクテット
+    pub(crate) fn fixed_surface(surface: (u8, i8, i32)) -> Vec<u8> {
+        let mut buf = vec![surface.0];
+        buf.extend(signed(surface.1 as i32, 1));
+        buf.extend(signed(surface.2, 4));
+        buf
+    }
+
+    // テンプレート 4.0 のオクテット10-34。予報時間の単位は時間、第二固定面は欠損値とする。
+    pub(crate) fn horizontal(
+        parameter: (u8, u8),
+        process: u8,
+        hours: u32,
+        surface: (u8, i8, i32),
+    ) -> Vec<u8> {
+        let mut body = vec![parameter.0, parameter.1, 2, 0, process, 0, 0, 0, 1];
+        body.extend_from_slice(&hours.to_be_bytes());
+        body.extend(fixed_surface(surface));
+        body.extend(fixed_surface(MISSING_SURFACE));
+        body
+    }
+
+    // 全時間間隔の終了時刻から始まる統計処理のオクテット。時間範囲は時間単位で1つだけとする。
+    pub(crate) fn statistics(end_time: DateTime<Utc>, process: u8, hours: u32) -> Vec<u8> {
+        let mut buf = (end_time.year() as u16).to_be_bytes().to_vec();
+        buf.extend_from_slice(&[
+            end_time.month() as u8,
+            end_time.day() as u8,
+            end_time.hour() as u8,
+            end_time.minute() as u8,
+            end_time.second() as u8,
+            1,
+        ]);
+        buf.extend_from_slice(&0u32.to_be_bytes());
+        buf.extend_from_slice(&[process, 2, 1]);
+        buf.extend_from_slice(&hours.to_be_bytes());
+        buf.extend_from_slice(&[1, 0, 0, 0, 0]);
+        buf
+    }
+
+    // 第5節から第7節。10^-2 の精度の単純圧縮で、NaN はビットマップで欠けた格子点にする。
+    pub(crate) fn simple_packing(values: &[f32]) -> Vec<Vec<u8>> {
+        let scaled: Vec<Option<i64>> = values
+            .iter()
+            .map(|x| match x.is_nan() {
+                true => None,
+                false => Some((*x as f64 * 100.0).round() as i64),
+            })
+            .collect();
+        let present: Vec<i64> = scaled.iter().flatten().copied().collect();
+        let r = present.iter().copied().min().unwrap_or(0);
+
+        let mut section5 = (present.len() as u32).to_be_bytes().to_vec();
+        section5.extend_from_slice(&0u16.to_be_bytes());
+        section5.extend_from_slice(&(r as f32).to_be_bytes());
+        section5.extend(signed(0, 2));
+        section5.extend(signed(2, 2));
+        section5.extend_from_slice(&[16, 0]);
+
+        let section6 = if present.len() == values.len() {
+            vec![255]
+        } else {
+            let mut bits = vec![0u8; (values.len() + 7) / 8];
+            for (n, x) in scaled.iter().enumerate() {
+                if x.is_some() {
+                    bits[n / 8] |= 0x80 >> (n % 8);
+                }
+            }
+            [vec![0], bits].concat()
+        };
+
+        let section7: Vec<u8> = present
+            .iter()
+            .flat_map(|x| ((x - r) as u16).to_be_bytes())
+            .collect();
+        vec![
+            section(5, &section5),
+            section(6, &section6),
+            section(7, &section7),
+        ]
     }
 }
@@ -1,12 +1,48 @@
 //! JS との境界を定義したファイル
 
+mod export;
 mod grib2;
+mod render;
 
 use chrono::{DateTime, Utc};
+use export::cf_impl::cf_dataset;
+use export::cog_impl::cog;
+use export::geotiff_impl::geotiff;
+use export::netcdf_impl::NetCdf;
+use export::png_impl::png;
+use export::points_impl::points;
+use export::zarr_impl::zarr;
+use export::Field;
+use grib2::section::time_series_impl::ensemble_variable_key;
+use grib2::section::Band;
 use grib2::section::Bounds;
+use grib2::section::Constituent;
+use grib2::section::CrossSection;
+use grib2::section::DerivedForecast;
+use grib2::section::Ensemble;
+use grib2::section::Interpolation;
+use grib2::section::Level;
 use grib2::section::PackingType;
-use grib2::utils_impl::first_plane_name;
+use grib2::section::Percentile;
+use grib2::section::Probability;
+use grib2::section::RadarOperation;
+use grib2::section::RawRunLengthPackingImage;
+use grib2::section::RawSimplePackingImage;
+use grib2::section::RegridMethod;
+use grib2::section::RegridWeights;
+use grib2::section::SectionSet;
+use grib2::section::Statistics;
+use grib2::section::TimeSeriesPoint;
+use grib2::section::Window;
 use grib2::Grib2;
+use render::colormap_impl::colormap;
+use render::colormap_impl::default_colormap;
+use render::contour_impl::contour_levels;
+use render::contour_impl::contours;
+use render::contour_impl::geojson;
+use render::legend_impl::LEGEND_HEIGHT;
+use render::Colormap;
+use render::ColormapMode;
 use serde::Serialize;
 use std::cmp;
 use wasm_bindgen::prelude::*;
@@ -105,6 +141,117 @@ impl PackingImage {
     }
 }
 
+// 鉛直断面の画像。行は気圧の低い順（上空から）、列は始点からの順に並ぶ。
+#[wasm_bindgen]
+pub struct CrossSectionImage {
+    pub width: usize,
+    pub height: usize,
+    pub min: f32,
+    pub max: f32,
+    cross_section: CrossSection,
+}
+
+#[wasm_bindgen]
+impl CrossSectionImage {
+    pub fn values(&self) -> Vec<f32> {
+        self.cross_section.values.clone()
+    }
+    pub fn distances(&self) -> Vec<f64> {
+        self.cross_section.distances.clone()
+    }
+    pub fn pressures(&self) -> Vec<f64> {
+        self.cross_section.pressures.clone()
+    }
+    pub fn longitudes(&self) -> Vec<f64> {
+        self.cross_section.longitudes.clone()
+    }
+    pub fn latitudes(&self) -> Vec<f64> {
+        self.cross_section.latitudes.clone()
+    }
+}
+
+// 格子系を変換した値。資料の並び順は変換先の格子系に従う。
+#[wasm_bindgen]
+pub struct RegriddedImage {
+    pub width: usize,
+    pub height: usize,
+    pub min: f32,
+    pub max: f32,
+    values: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl RegriddedImage {
+    pub fn values(&self) -> Vec<f32> {
+        self.values.clone()
+    }
+}
+
+// カラーマップで描画した RGBA 画像。行と列は資料の並び順に従う。
+#[wasm_bindgen]
+pub struct RenderedImage {
+    pub width: usize,
+    pub height: usize,
+    rgba: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl RenderedImage {
+    pub fn rgba(&self) -> Vec<u8> {
+        self.rgba.clone()
+    }
+}
+
+// Zarr のディレクトリストアのファイル。パスはストアの最上位からの相対パス
+#[wasm_bindgen]
+pub struct ZarrStore {
+    paths: Vec<String>,
+    files: Vec<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl ZarrStore {
+    pub fn count(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn path(&self, index: usize) -> Option<String> {
+        self.paths.get(index).cloned()
+    }
+
+    pub fn file(&self, index: usize) -> Option<Vec<u8>> {
+        self.files.get(index).cloned()
+    }
+}
+
+// 範囲があれば画像を切り出す。
+fn crop_simple(image: RawSimplePackingImage, window: &Option<Window>) -> RawSimplePackingImage {
+    match window {
+        Some(window) => RawSimplePackingImage {
+            width: window.width(),
+            height: window.height(),
+            pixels: window.crop(&image.pixels, image.width),
+            ..image
+        },
+        None => image,
+    }
+}
+
+fn crop_run_length(
+    image: RawRunLengthPackingImage,
+    window: &Option<Window>,
+) -> RawRunLengthPackingImage {
+    match window {
+        Some(window) => RawRunLengthPackingImage {
+            width: window.width(),
+            height: window.height(),
+            pixels: window.crop(&image.pixels, image.width),
+            ..image
+        },
+        None => image,
+    }
+}
+
 // JSValue は u32, i32 まで
 #[wasm_bindgen]
 #[derive(Serialize)]
@@ -122,14 +269,56 @@ pub struct Item {
     first_plane_type: Option<u32>,
     first_plane_factor: Option<i32>,
     first_plane_value: Option<i32>,
+    level: Option<Level>,
+    level_name: Option<String>,
+    statistics: Option<Statistics>,
+    statistics_name: Option<String>,
+    probability: Option<Probability>,
+    probability_name: Option<String>,
+    percentile: Option<Percentile>,
+    percentile_name: Option<String>,
+    ensemble: Option<Ensemble>,
+    ensemble_name: Option<String>,
+    derived_forecast: Option<DerivedForecast>,
+    derived_forecast_name: Option<String>,
+    constituent: Option<Constituent>,
+    bands: Option<Vec<Band>>,
+    radar_operation: Option<RadarOperation>,
+    radar_operation_name: Option<String>,
+    constituent_name: Option<String>,
+    product_definition: Option<serde_json::Value>,
     information: Option<String>,
 }
 
+// 全格子点の経度と緯度（資料の並び順）
+#[derive(Serialize)]
+pub struct Coordinates {
+    longitudes: Vec<f64>,
+    latitudes: Vec<f64>,
+}
+
+// 1地点の時系列
+#[derive(Serialize)]
+pub struct TimeSeries {
+    parameter_description: Option<String>,
+    parameter_unit: Option<String>,
+    level_name: Option<String>,
+    points: Vec<TimeSeriesPoint>,
+}
+
+type RegridKey = (Vec<u8>, Vec<u8>, RegridMethod);
+
+// 保持する変換の重みの数
+const MAX_REGRID_WEIGHTS: usize = 4;
+
 #[wasm_bindgen]
 pub struct Grib2Wrapper {
     buf: Vec<u8>, // grib2 から参照するデータ
     grib2: Grib2<'static>,
     items: Vec<Item>,
+    // 最近使った順に MAX_REGRID_WEIGHTS 個まで保持する。
+    // key = 変換元の格子系定義, 変換先の格子系定義, 変換方法
+    regrid_weights: Vec<(RegridKey, RegridWeights)>,
 }
 
 #[wasm_bindgen]
@@ -140,12 +329,14 @@ impl Grib2Wrapper {
             buf: Vec::new(),
             grib2: Grib2::new(),
             items: Vec::new(),
+            regrid_weights: Vec::new(),
         }
     }
 
     pub fn clear(&mut self) {
         self.buf.clear();
         self.items.clear();
+        self.regrid_weights.clear();
     }
 
     pub fn load(&mut self, src: &[u8]) {
@@ -158,15 +349,27 @@ impl Grib2Wrapper {
 
         let mut items = Vec::new();
         for sectionset in self.grib2.sectionsets().iter() {
+            let level = sectionset.level();
+            let statistics = sectionset.statistics();
+            let percentile = sectionset.percentile();
+            let ensemble = sectionset.ensemble();
+            let derived_forecast = sectionset.derived_forecast();
+            let constituent = sectionset.constituent();
+            let radar_operation = sectionset.radar_operation();
             items.push(Item {
                 reference_datetime: sectionset.reference_datetime(),
                 packing_type: sectionset.packing_type(),
                 point_count: Self::to_u32(sectionset.point_count()),
-                parameter_description: self.parameter_description(
-                    sectionset.discipline(),
-                    sectionset.parameter_category(),
-                    sectionset.parameter_number(),
-                ),
+                parameter_description: self
+                    .constituent_description(&sectionset)
+                    .or_else(|| self.band_description(&sectionset))
+                    .or_else(|| {
+                        self.parameter_description(
+                            sectionset.discipline(),
+                            sectionset.parameter_category(),
+                            sectionset.parameter_number(),
+                        )
+                    }),
                 parameter_unit: self.parameter_unit(
                     sectionset.discipline(),
                     sectionset.parameter_category(),
@@ -176,14 +379,28 @@ impl Grib2Wrapper {
                 parameter_category: Self::to_u32(sectionset.parameter_category()),
                 parameter_number: Self::to_u32(sectionset.parameter_number()),
                 datetime: sectionset.datetime(),
-                first_plane_name: Self::first_plane_name(
-                    sectionset.first_plane_type(),
-                    sectionset.first_plane_factor(),
-                    sectionset.first_plane_value(),
-                ),
+                first_plane_name: level.as_ref().map(|x| x.first().to_string()),
                 first_plane_type: Self::to_u32(sectionset.first_plane_type()),
                 first_plane_factor: Self::to_i32(sectionset.first_plane_factor()),
                 first_plane_value: Self::to_i32(sectionset.first_plane_value()),
+                level_name: level.as_ref().map(|x| x.to_string()),
+                level,
+                statistics_name: statistics.as_ref().map(|x| x.to_string()),
+                statistics,
+                probability_name: self.probability_name(&sectionset),
+                probability: sectionset.probability(),
+                percentile_name: percentile.as_ref().map(|x| x.to_string()),
+                percentile,
+                ensemble_name: ensemble.as_ref().map(|x| x.to_string()),
+                ensemble,
+                derived_forecast_name: derived_forecast.as_ref().map(|x| x.to_string()),
+                derived_forecast,
+                constituent_name: constituent.as_ref().map(|x| x.to_string()),
+                constituent,
+                bands: sectionset.bands(),
+                radar_operation_name: radar_operation.as_ref().map(|x| x.to_string()),
+                radar_operation,
+                product_definition: sectionset.product_definition(),
                 information: Some(format!("{:?}", sectionset)),
             })
         }
@@ -201,11 +418,7 @@ impl Grib2Wrapper {
                     sectionset.parameter_category(),
                     sectionset.parameter_number(),
                 ),
-                Self::first_plane_name(
-                    sectionset.first_plane_type(),
-                    sectionset.first_plane_factor(),
-                    sectionset.first_plane_value(),
-                ),
+                sectionset.level().map(|x| x.to_string()),
                 sectionset
             ));
         }
@@ -240,12 +453,52 @@ impl Grib2Wrapper {
         return Some(format!("{}", description?.unit));
     }
 
-    pub fn first_plane_name(
-        plane_type: Option<usize>,
-        plane_factor: Option<isize>,
-        plane_value: Option<isize>,
-    ) -> Option<String> {
-        first_plane_name(plane_type?, plane_factor?, plane_value?)
+    // 確率予報の事象をパラメーター名で表す。 e.g. P(Total precipitation > 10 kg m-2)
+    fn probability_name(&self, sectionset: &SectionSet) -> Option<String> {
+        let probability = sectionset.probability()?;
+        let description = self.grib2.parameter_description(
+            sectionset.discipline()?,
+            sectionset.parameter_category()?,
+            sectionset.parameter_number()?,
+        );
+
+        Some(match description {
+            Some(description) => probability.describe(&description.name, &description.unit),
+            None => probability.to_string(),
+        })
+    }
+
+    // 大気化学成分をパラメーター名に加えて表す。 e.g. Mass density – dust, 0.03–0.55 µm [kg m-3]
+    fn constituent_description(&self, sectionset: &SectionSet) -> Option<String> {
+        let constituent = sectionset.constituent()?;
+        let description = self.grib2.parameter_description(
+            sectionset.discipline()?,
+            sectionset.parameter_category()?,
+            sectionset.parameter_number()?,
+        )?;
+
+        let name = constituent.describe(&description.name);
+        if description.unit.is_empty() {
+            return Some(name);
+        }
+        Some(format!("{} [{}]", name, description.unit))
+    }
+
+    // 衛星のバンドをパラメーター名に加えて表す。 e.g. Brightness temperature – Himawari-9 band 13 (10.4 µm) [K]
+    fn band_description(&self, sectionset: &SectionSet) -> Option<String> {
+        let bands = sectionset.bands().filter(|x| !x.is_empty())?;
+        let description = self.grib2.parameter_description(
+            sectionset.discipline()?,
+            sectionset.parameter_category()?,
+            sectionset.parameter_number()?,
+        )?;
+
+        let bands: Vec<String> = bands.iter().map(|x| x.to_string()).collect();
+        let name = format!("{} – {}", description.name, bands.join(", "));
+        if description.unit.is_empty() {
+            return Some(name);
+        }
+        Some(format!("{} [{}]", name, description.unit))
     }
 
     pub fn to_u32(src: Option<usize>) -> Option<u32> {
@@ -256,22 +509,537 @@ impl Grib2Wrapper {
         Some(src? as i32)
     }
 
+    // メッセージの数
+    pub fn item_count(&self) -> usize {
+        self.items.len()
+    }
+
     // JSON 形式で返す
     pub fn items(&self) -> JsValue {
-        serde_wasm_bindgen::to_value(&self.items).unwrap()
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        self.items.serialize(&serializer).unwrap()
+    }
+
+    // 格子点の位置 (i, j) の経度と緯度を [lon, lat] で返す。
+    pub fn lonlat(&self, index: usize, i: f64, j: f64) -> Option<Vec<f64>> {
+        let geometry = self.grib2.sectionsets().try_get(index)?.geometry()?;
+        let (lon, lat) = geometry.ij_to_lonlat(i, j);
+        Some(vec![lon, lat])
+    }
+
+    // 経度と緯度の格子点の位置（小数）を [i, j] で返す。格子の外なら undefined
+    pub fn ij(&self, index: usize, lon: f64, lat: f64) -> Option<Vec<f64>> {
+        let geometry = self.grib2.sectionsets().try_get(index)?.geometry()?;
+        let (i, j) = geometry.lonlat_to_ij(lon, lat)?;
+        Some(vec![i, j])
+    }
+
+    // 全格子点の経度と緯度を返す。
+    pub fn coordinates(&self, index: usize) -> JsValue {
+        let sectionset = self.grib2.sectionsets().try_get(index);
+        let geometry = match sectionset.and_then(|x| x.geometry()) {
+            Some(geometry) => geometry,
+            None => return JsValue::UNDEFINED,
+        };
+
+        let (longitudes, latitudes) = geometry.coordinates();
+        serde_wasm_bindgen::to_value(&Coordinates {
+            longitudes,
+            latitudes,
+        })
+        .unwrap()
+    }
+
+    // 格子点 (i, j) のセルの四隅を [lon0, lat0, lon1, lat1, ...] で返す。
+    pub fn cell_corners(&self, index: usize, i: usize, j: usize) -> Option<Vec<f64>> {
+        let geometry = self.grib2.sectionsets().try_get(index)?.geometry()?;
+        if geometry.ni() <= i || geometry.nj() <= j {
+            return None;
+        }
+
+        let corners = geometry.cell_corners(i, j);
+        Some(corners.iter().flat_map(|(lon, lat)| [*lon, *lat]).collect())
+    }
+
+    // 経度と緯度の値を補間して返す。method は "nearest", "bilinear", "bicubic" のいずれか
+    pub fn sample(&self, index: usize, lon: f64, lat: f64, method: &str) -> Option<f64> {
+        let method = method.parse::<Interpolation>().ok()?;
+        self.grib2
+            .sectionsets()
+            .try_get(index)?
+            .sample(lon, lat, method)
+    }
+
+    // 複数地点の値をまとめて補間して返す。値がない地点は NaN
+    pub fn sample_points(
+        &self,
+        index: usize,
+        lons: Vec<f64>,
+        lats: Vec<f64>,
+        method: &str,
+    ) -> Vec<f64> {
+        let method = match method.parse::<Interpolation>() {
+            Ok(method) => method,
+            Err(_) => return vec![f64::NAN; lons.len()],
+        };
+
+        let sectionset = match self.grib2.sectionsets().try_get(index) {
+            Some(sectionset) => sectionset,
+            None => return vec![f64::NAN; lons.len()],
+        };
+
+        let points: Vec<(f64, f64)> = lons.into_iter().zip(lats).collect();
+        sectionset
+            .sample_points(&points, method)
+            .into_iter()
+            .map(|x| x.unwrap_or(f64::NAN))
+            .collect()
+    }
+
+    // index と同じ要素の全時刻について、経度と緯度の値を有効時刻の順に返す。
+    // 複数のファイルを load していれば、ファイルをまたいで集める。
+    pub fn time_series(&self, index: usize, lon: f64, lat: f64, method: &str) -> JsValue {
+        let method = match method.parse::<Interpolation>() {
+            Ok(method) => method,
+            Err(_) => return JsValue::UNDEFINED,
+        };
+        let item = match self.items.get(index) {
+            Some(item) => item,
+            None => return JsValue::UNDEFINED,
+        };
+
+        serde_wasm_bindgen::to_value(&TimeSeries {
+            parameter_description: item.parameter_description.clone(),
+            parameter_unit: item.parameter_unit.clone(),
+            level_name: item.level_name.clone(),
+            points: self
+                .grib2
+                .sectionsets()
+                .time_series(index, lon, lat, method),
+        })
+        .unwrap()
+    }
+
+    // index と同じ時刻の等圧面の気温、湿度、風、高度から、経度と緯度の鉛直プロファイルを返す。
+    pub fn sounding(&self, index: usize, lon: f64, lat: f64, method: &str) -> JsValue {
+        let sounding = method
+            .parse::<Interpolation>()
+            .ok()
+            .and_then(|method| self.grib2.sectionsets().sounding(index, lon, lat, method));
+
+        match sounding {
+            Some(sounding) => serde_wasm_bindgen::to_value(&sounding).unwrap(),
+            None => JsValue::UNDEFINED,
+        }
+    }
+
+    // index と同じ要素、同じ時刻の全等圧面について、
+    // (lon1, lat1) から (lon2, lat2) への大円上の count 点の値を鉛直断面として返す。
+    #[allow(clippy::too_many_arguments)]
+    pub fn cross_section(
+        &self,
+        index: usize,
+        lon1: f64,
+        lat1: f64,
+        lon2: f64,
+        lat2: f64,
+        count: usize,
+        method: &str,
+    ) -> Option<CrossSectionImage> {
+        let method = method.parse::<Interpolation>().ok()?;
+        let cross_section = self.grib2.sectionsets().cross_section(
+            index,
+            (lon1, lat1),
+            (lon2, lat2),
+            count,
+            method,
+        )?;
+
+        let values = cross_section.values.iter().filter(|x| !x.is_nan());
+        let min = values.clone().fold(f32::INFINITY, |a, b| a.min(*b));
+        let max = values.fold(f32::NEG_INFINITY, |a, b| a.max(*b));
+        Some(CrossSectionImage {
+            width: cross_section.width,
+            height: cross_section.height,
+            min,
+            max,
+            cross_section,
+        })
+    }
+
+    // source_index の値を target_index の格子系に変換して返す。method は "nearest", "bilinear", "conservative" のいずれか
+    // 変換の重みは格子系と変換方法ごとに最近使ったものを保持し、次の変換で使い回す。
+    pub fn regrid(
+        &mut self,
+        source_index: usize,
+        target_index: usize,
+        method: &str,
+    ) -> Option<RegriddedImage> {
+        let method = method.parse::<RegridMethod>().ok()?;
+        let source = self.grib2.sectionsets().try_get(source_index)?;
+        let target = self.grib2.sectionsets().try_get(target_index)?;
+
+        let key = (
+            source.grid_definition()?.to_vec(),
+            target.grid_definition()?.to_vec(),
+            method,
+        );
+        let entry = match self.regrid_weights.iter().position(|(x, _)| *x == key) {
+            Some(i) => self.regrid_weights.remove(i),
+            None => (key, source.regrid_weights(&target, method)?),
+        };
+        if MAX_REGRID_WEIGHTS <= self.regrid_weights.len() {
+            self.regrid_weights.remove(0);
+        }
+        self.regrid_weights.push(entry);
+        let (_, weights) = self.regrid_weights.last().unwrap();
+
+        let values = source.regrid(weights).ok()?;
+        let valid = values.iter().filter(|x| !x.is_nan());
+        let min = valid.clone().fold(f32::INFINITY, |a, b| a.min(*b));
+        let max = valid.fold(f32::NEG_INFINITY, |a, b| a.max(*b));
+        Some(RegriddedImage {
+            width: weights.ni,
+            height: weights.nj,
+            min,
+            max,
+            values,
+        })
+    }
+
+    // カラーマップで RGBA 画像に描画する。mode は "stepped", "continuous" のいずれか
+    // colormap を省略すれば要素に対応するカラーマップ、なければ値の範囲に合わせた虹色を使う。
+    pub fn render(
+        &self,
+        index: usize,
+        colormap_name: Option<String>,
+        mode: &str,
+    ) -> Option<RenderedImage> {
+        let sectionset = self.grib2.sectionsets().try_get(index)?;
+        let colormap = self.colormap_for(&sectionset, colormap_name)?;
+        self.render_in(&sectionset, &colormap, mode)
+    }
+
+    // カラーマップで描画した PNG を返す。legend が true なら画像の下に凡例を加える。
+    // 要素、レベル、時刻をテキストのチャンクに記す。
+    pub fn export_png(
+        &self,
+        index: usize,
+        colormap_name: Option<String>,
+        mode: &str,
+        legend: bool,
+    ) -> Option<Vec<u8>> {
+        let sectionset = self.grib2.sectionsets().try_get(index)?;
+        let colormap = self.colormap_for(&sectionset, colormap_name)?;
+        let image = self.render_in(&sectionset, &colormap, mode)?;
+
+        let mut rgba = image.rgba;
+        let mut height = image.height;
+        if legend {
+            rgba.extend(colormap.legend(image.width, mode.parse().ok()?));
+            height += LEGEND_HEIGHT;
+        }
+
+        let item = self.items.get(index)?;
+        let mut texts = vec![("Software", String::from("grib2-visualization"))];
+        if let Some(description) = &item.parameter_description {
+            texts.push(("Parameter", description.clone()));
+        }
+        if let Some(level_name) = &item.level_name {
+            texts.push(("Level", level_name.clone()));
+        }
+        if let Some(datetime) = item.datetime {
+            texts.push(("Valid time", datetime.to_rfc3339()));
+        }
+        if let Some(datetime) = item.reference_datetime {
+            texts.push(("Reference time", datetime.to_rfc3339()));
+        }
+
+        png(&rgba, image.width, height, &texts).ok()
+    }
+
+    // 値を Float32 の GeoTIFF にする。欠測は NaN とする。
+    // 要素、レベル、時刻とプロダクト定義テンプレートの全フィールドを GDAL のメタデータに記す。
+    pub fn export_geotiff(&self, index: usize) -> Option<Vec<u8>> {
+        let sectionset = self.grib2.sectionsets().try_get(index)?;
+        let geometry = sectionset.geometry()?;
+        let values = sectionset.values().ok()?;
+
+        geotiff(
+            &values,
+            geometry.ni(),
+            geometry.nj(),
+            &geometry.transform(),
+            &self.metadata(&sectionset, index),
+        )
+        .ok()
+    }
+
+    // 値をタイル分けと概観付きの Cloud Optimized GeoTIFF にする。compression は "deflate", "lzw" のいずれか
+    pub fn export_cog(&self, index: usize, compression: &str) -> Option<Vec<u8>> {
+        let sectionset = self.grib2.sectionsets().try_get(index)?;
+        let geometry = sectionset.geometry()?;
+        let values = sectionset.values().ok()?;
+
+        cog(
+            &values,
+            geometry.ni(),
+            geometry.nj(),
+            &geometry.transform(),
+            &self.metadata(&sectionset, index),
+            compression.parse().ok()?,
+        )
+        .ok()
+    }
+
+    // 範囲 [10^-6 度] に含まれる格子点を stride 格子おきに経度、緯度、値の行にして返す。format は "csv", "ndjson" のいずれか
+    // skip_missing が true なら欠測の格子点を除き、false なら値を空（NDJSON では null）にする。
+    // metadata が true なら要素、単位、レベル、時刻の列を加える。
+    #[allow(clippy::too_many_arguments)]
+    pub fn export_points(
+        &self,
+        index: usize,
+        format: &str,
+        left: isize,
+        bottom: isize,
+        right: isize,
+        top: isize,
+        stride: usize,
+        skip_missing: bool,
+        metadata: bool,
+    ) -> Option<String> {
+        let sectionset = self.grib2.sectionsets().try_get(index)?;
+        let geometry = sectionset.geometry()?;
+        let values = sectionset.values().ok()?;
+        let viewport = Bounds {
+            left,
+            bottom,
+            right,
+            top,
+        };
+
+        let mut columns = Vec::new();
+        if metadata {
+            let item = self.items.get(index)?;
+            let entries = [
+                ("parameter", item.parameter_description.clone()),
+                ("unit", item.parameter_unit.clone()),
+                ("level", item.level_name.clone()),
+                ("valid_time", item.datetime.map(|x| x.to_rfc3339())),
+                (
+                    "reference_time",
+                    item.reference_datetime.map(|x| x.to_rfc3339()),
+                ),
+            ];
+            for (name, value) in entries.iter() {
+                columns.push((name.to_string(), value.clone().unwrap_or_default()));
+            }
+        }
+
+        Some(points(
+            &geometry,
+            &values,
+            &viewport,
+            stride,
+            skip_missing,
+            &columns,
+            format.parse().ok()?,
+        ))
+    }
+
+    // 同じ格子系のメッセージを CF 規約に沿った NetCDF-3 にする。
+    // 同じ要素は時刻（予報対象時刻）、アンサンブルのメンバー、レベル（固定面の値）の次元を持つ1つの変数にまとめる。
+    pub fn export_netcdf(&self, indices: Vec<usize>) -> Option<Vec<u8>> {
+        self.dataset(&indices)?.write().ok()
+    }
+
+    // 同じ格子系のメッセージを Zarr v2 のディレクトリストアにする。変数のまとめ方は export_netcdf と同じ
+    pub fn export_zarr(&self, indices: Vec<usize>) -> Option<ZarrStore> {
+        let (paths, files) = zarr(&self.dataset(&indices)?).into_iter().unzip();
+        Some(ZarrStore { paths, files })
+    }
+
+    // 同じ格子系のメッセージを CF 規約に沿ったデータセットにする。
+    // 範囲外の番号や、時刻・メンバー・レベルが重なるメッセージがあれば None を返す。
+    fn dataset(&self, indices: &[usize]) -> Option<NetCdf> {
+        if indices.iter().any(|x| self.items.get(*x).is_none()) {
+            return None;
+        }
+        let sectionsets = self.grib2.sectionsets();
+        let first = sectionsets.try_get(*indices.first()?)?;
+        let geometry = first.geometry()?;
+        let grid_definition = first.grid_definition()?;
+
+        let mut fields = Vec::new();
+        for index in indices.iter() {
+            let sectionset = sectionsets.try_get(*index)?;
+            if sectionset.grid_definition()? != grid_definition {
+                return None;
+            }
+            let item = self.items.get(*index)?;
+            let parameter = (
+                sectionset.discipline()?,
+                sectionset.parameter_category()?,
+                sectionset.parameter_number()?,
+            );
+            let description =
+                self.grib2
+                    .parameter_description(parameter.0, parameter.1, parameter.2);
+            let mut long_name = item
+                .probability_name
+                .clone()
+                .or_else(|| item.parameter_description.clone())?;
+            if let Some(statistics_name) = &item.statistics_name {
+                long_name = format!("{} ({})", long_name, statistics_name);
+            }
+            fields.push(Field {
+                key: ensemble_variable_key(&sectionset)?,
+                name: description.map_or_else(|| long_name.clone(), |x| x.name.clone()),
+                description: long_name,
+                unit: description.map(|x| x.unit.clone()).unwrap_or_default(),
+                parameter,
+                probability: item.probability.is_some(),
+                level: sectionset.level(),
+                member: sectionset.ensemble().map(|x| x.perturbation_number),
+                valid_time: sectionset.datetime()?,
+                reference_time: sectionset.reference_datetime()?,
+                values: sectionset.values().ok()?,
+            });
+        }
+
+        cf_dataset(&geometry, &fields).ok()
+    }
+
+    // ファイルに記すメタデータの名前と値
+    fn metadata(&self, sectionset: &SectionSet, index: usize) -> Vec<(String, String)> {
+        let mut metadata = Vec::new();
+        if let Some(item) = self.items.get(index) {
+            let entries = [
+                ("GRIB_COMMENT", item.parameter_description.clone()),
+                ("GRIB_UNIT", item.parameter_unit.clone()),
+                ("GRIB_LEVEL", item.level_name.clone()),
+                ("GRIB_STATISTICS", item.statistics_name.clone()),
+                ("GRIB_VALID_TIME", item.datetime.map(|x| x.to_rfc3339())),
+                (
+                    "GRIB_REF_TIME",
+                    item.reference_datetime.map(|x| x.to_rfc3339()),
+                ),
+                ("GRIB_DISCIPLINE", item.discipline.map(|x| x.to_string())),
+            ];
+            for (name, value) in entries.iter() {
+                if let Some(value) = value {
+                    metadata.push((name.to_string(), value.clone()));
+                }
+            }
+        }
+        for (name, value) in sectionset.product_definition_entries() {
+            metadata.push((format!("GRIB_PDS_{}", name.to_uppercase()), value));
+        }
+        metadata
+    }
+
+    // interval おきの等値線を value プロパティを持つ LineString の GeoJSON（FeatureCollection）で返す。
+    // 等値線の値は base + k * interval とし、smoothing 回だけ角を丸める。
+    pub fn contours(
+        &self,
+        index: usize,
+        interval: f64,
+        base: f64,
+        smoothing: usize,
+    ) -> Option<String> {
+        let sectionset = self.grib2.sectionsets().try_get(index)?;
+        let geometry = sectionset.geometry()?;
+        let values = sectionset.values().ok()?;
+        let levels = contour_levels(&values, interval, base).ok()?;
+        Some(geojson(&contours(&geometry, &values, &levels, smoothing)))
+    }
+
+    // 名称のカラーマップ。省略すれば要素に対応するカラーマップ、なければ値の範囲に合わせた虹色
+    fn colormap_for(
+        &self,
+        sectionset: &SectionSet,
+        colormap_name: Option<String>,
+    ) -> Option<Colormap> {
+        match colormap_name {
+            Some(name) => colormap(&name),
+            None => Some(default_colormap(
+                sectionset.discipline(),
+                sectionset.parameter_category(),
+                sectionset.parameter_number(),
+                &sectionset.values().ok()?,
+            )),
+        }
+    }
+
+    // しきい値と 0-1 の RGBA を並べた色のカラーマップで RGBA 画像に描画する。
+    pub fn render_with_colormap(
+        &self,
+        index: usize,
+        thresholds: Vec<f64>,
+        colors: Vec<f32>,
+        mode: &str,
+    ) -> Option<RenderedImage> {
+        let colormap = Colormap::from_rgba(&thresholds, &colors).ok()?;
+        self.render_in(&self.grib2.sectionsets().try_get(index)?, &colormap, mode)
+    }
+
+    fn render_in(
+        &self,
+        sectionset: &SectionSet,
+        colormap: &Colormap,
+        mode: &str,
+    ) -> Option<RenderedImage> {
+        let mode = mode.parse::<ColormapMode>().ok()?;
+        let geometry = sectionset.geometry()?;
+        let values = sectionset.values().ok()?;
+        Some(RenderedImage {
+            width: geometry.ni(),
+            height: geometry.nj(),
+            rgba: colormap.render(&values, mode),
+        })
     }
 
     pub fn unpack_image(&self, index: usize) -> Option<PackingImage> {
+        self.unpack_image_in(index, None)
+    }
+
+    // 範囲 [10^-6 度] を含む格子を stride 格子おきに切り出して返す。
+    // bounds, di, dj は切り出した格子のものになる。
+    pub fn unpack_image_window(
+        &self,
+        index: usize,
+        left: isize,
+        bottom: isize,
+        right: isize,
+        top: isize,
+        stride: usize,
+    ) -> Option<PackingImage> {
+        let viewport = Bounds {
+            left,
+            bottom,
+            right,
+            top,
+        };
+        let window = self
+            .grib2
+            .sectionsets()
+            .try_get(index)?
+            .window(&viewport, stride)?;
+        self.unpack_image_in(index, Some(window))
+    }
+
+    fn unpack_image_in(&self, index: usize, window: Option<Window>) -> Option<PackingImage> {
         let sectionsets = self.grib2.sectionsets();
-        let sectionset = sectionsets.get(index);
-        let bounds = sectionset.bounds()?;
-        let di = sectionset.di()?;
-        let dj = sectionset.dj()?;
+        let sectionset = sectionsets.try_get(index)?;
+        let (bounds, di, dj) = match &window {
+            Some(window) => (window.bounds.clone(), window.di, window.dj),
+            None => (sectionset.bounds()?, sectionset.di()?, sectionset.dj()?),
+        };
         let packing_type = sectionset.packing_type()?;
 
         match packing_type {
             PackingType::Simple => {
-                let image = sectionset.unpack_simple().ok()?;
+                let image = crop_simple(sectionset.unpack_simple().ok()?, &window);
 
                 // u16::MAX はビットマップ適用して範囲外のピクセル
                 let mut min = u16::MAX - 1;
@@ -305,7 +1073,7 @@ impl Grib2Wrapper {
                 })
             }
             PackingType::RunLength => {
-                let image = sectionset.unpack_run_length().ok()?;
+                let image = crop_run_length(sectionset.unpack_run_length().ok()?, &window);
 
                 let mut min = image.pixels[0];
                 let mut max = image.pixels[0];
@@ -333,9 +1101,12 @@ impl Grib2Wrapper {
                 })
             }
             PackingType::ComplexPackingAndSpatialDifferencing => {
-                let image = sectionset
-                    .unpack_complex_packing_and_spatial_differencing()
-                    .ok()?;
+                let image = crop_simple(
+                    sectionset
+                        .unpack_complex_packing_and_spatial_differencing()
+                        .ok()?,
+                    &window,
+                );
 
                 // u16::MAX はビットマップ適用して範囲外のピクセル
                 let mut min = u16::MAX - 1;
@@ -371,3 +1142,90 @@ impl Grib2Wrapper {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::grib2::utils_impl::tests::*;
+    use super::*;
+
+    // 範囲外の番号では panic せずに None を返す。
+    #[test]
+    fn out_of_range_index() {
+        let buf = message(
+            0,
+            &[
+                vec![identification(34, Utc.ymd(2023, 6, 1).and_hms(0, 0, 0))],
+                vec![latlon_grid(3, 2, 36.0, 139.0, 1.0, 1.0)],
+                vec![product(0, &horizontal((0, 0), 1, 0, (1, 0, 0)))],
+                simple_packing(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
+            ]
+            .concat(),
+        );
+        let mut grib2 = Grib2Wrapper::new();
+        grib2.load(&buf);
+
+        assert!(grib2.lonlat(0, 0.0, 0.0).is_some());
+        assert!(grib2.lonlat(1, 0.0, 0.0).is_none());
+        assert!(grib2.ij(1, 139.0, 36.0).is_none());
+        assert!(grib2.cell_corners(1, 0, 0).is_none());
+        assert!(grib2.sample(1, 139.0, 36.0, "nearest").is_none());
+        assert!(grib2.sample_points(1, vec![139.0], vec![36.0], "nearest")[0].is_nan());
+        assert!(grib2.regrid(0, 1, "nearest").is_none());
+        assert!(grib2.regrid(1, 0, "nearest").is_none());
+        assert!(grib2.render(1, None, "stepped").is_none());
+        assert!(grib2
+            .render_with_colormap(1, vec![0.0], vec![0.0, 0.0, 0.0, 1.0], "stepped")
+            .is_none());
+        assert!(grib2.export_png(1, None, "stepped", false).is_none());
+        assert!(grib2.export_geotiff(1).is_none());
+        assert!(grib2.export_cog(1, "deflate").is_none());
+        assert!(grib2
+            .export_points(1, "csv", 0, 0, 1, 1, 1, false, false)
+            .is_none());
+        assert!(grib2.export_netcdf(vec![0, 1]).is_none());
+        assert!(grib2.contours(1, 1.0, 0.0, 0).is_none());
+        assert!(grib2.unpack_image(0).is_some());
+        assert!(grib2.unpack_image(1).is_none());
+        assert!(grib2.unpack_image_window(1, 0, 0, 1, 1, 1).is_none());
+    }
+
+    // 変換の重みは最近使ったものだけを保持する。
+    #[test]
+    fn regrid_weight_cache() {
+        let buf: Vec<u8> = (2..8)
+            .flat_map(|ni| {
+                message(
+                    0,
+                    &[
+                        vec![identification(34, Utc.ymd(2023, 6, 1).and_hms(0, 0, 0))],
+                        vec![latlon_grid(ni, 2, 36.0, 139.0, 1.0, 1.0)],
+                        vec![product(0, &horizontal((0, 0), 1, 0, (1, 0, 0)))],
+                        simple_packing(&vec![1.0; ni as usize * 2]),
+                    ]
+                    .concat(),
+                )
+            })
+            .collect();
+        let mut grib2 = Grib2Wrapper::new();
+        grib2.load(&buf);
+
+        for target in 1..6 {
+            assert_eq!(
+                grib2.regrid(0, target, "nearest").unwrap().width,
+                target + 2
+            );
+        }
+        assert_eq!(grib2.regrid_weights.len(), MAX_REGRID_WEIGHTS);
+
+        // 使った重みは最後に移る。
+        let key = grib2.regrid_weights[0].0.clone();
+        assert!(grib2.regrid(0, 2, "nearest").is_some());
+        assert_eq!(grib2.regrid_weights.len(), MAX_REGRID_WEIGHTS);
+        assert_eq!(grib2.regrid_weights.last().unwrap().0, key);
+
+        grib2.clear();
+        assert!(grib2.regrid_weights.is_empty());
+    }
+}
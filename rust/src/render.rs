@@ -0,0 +1,27 @@
+//! 描画 declaration
+
+pub mod colormap_impl;
+pub mod contour_impl;
+pub mod legend_impl;
+pub mod render_impl;
+
+// カラーマップの適用方法
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ColormapMode {
+    Stepped,    // しきい値以上で次のしきい値未満の値を同じ色で塗る
+    Continuous, // しきい値の間の色を線形補間する
+}
+
+// しきい値と色（RGBA）の組。しきい値は昇順に並ぶ。
+#[derive(Clone, Debug)]
+pub struct Colormap {
+    pub(crate) thresholds: Vec<f64>,
+    pub(crate) colors: Vec<[u8; 4]>,
+}
+
+// 等値線。点は経度と緯度
+#[derive(Clone, Debug)]
+pub(crate) struct Contour {
+    pub(crate) value: f64,
+    pub(crate) points: Vec<(f64, f64)>,
+}
@@ -0,0 +1,411 @@
+//! カラーマップ implementation
+//! react/src/colormap-utils.js と同じカラーマップを定義する。
+
+use super::Colormap;
+
+// 0-255 の RGB から不透明な色を返す。
+const fn rgb(r: u8, g: u8, b: u8) -> [u8; 4] {
+    [r, g, b, 255]
+}
+
+const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+const ZERO_CELSIUS: f64 = 273.15;
+
+// 要素に対応するカラーマップの名称
+pub(crate) fn colormap_name(
+    discipline: usize,
+    parameter_category: usize,
+    parameter_number: usize,
+) -> Option<&'static str> {
+    match (discipline, parameter_category, parameter_number) {
+        // 0 Meteorological products
+        (0, 0, 0) => Some("temperature"),     // Temperature [K]
+        (0, 1, 1) => Some("percentage"),      // Relative Humidity [%]
+        (0, 1, 200) => Some("precipitation"), // 1時間降水量レベル値
+        (0, 1, 201) => Some("precipitation"), // 10分間降水強度（１時間換算値）レベル値
+        (0, 1, 202) => Some("precipitation-10min"), // 10分間降水量レベル値
+        (0, 1, 203) => Some("precipitation"), // 降水強度レベル値(解析、予報）
+        (0, 1, 204) => Some("precipitation"), // 総降水量のレベル値
+        (0, 1, 206) => Some("precipitation"), // 土壌雨量タンクレベル値
+        (0, 1, 208) => Some("sediment-warning-index"), // 土砂災害警戒判定値
+        (0, 1, 216..=218) => Some("warning-index"), // 浸水・洪水危険度判定値
+        (0, 1, 233) => Some("precipitation"), // 降雪の深さの合計のレベル値
+        (0, 2, 2) | (0, 2, 3) => Some("wind"), // U, V-Component of Wind [m s-1]
+        (0, 4, 51) | (0, 4, 52) => Some("uv-index"), // UV index
+        (0, 6, 1) | (0, 6, 3..=5) => Some("cloud"), // Cloud Cover [%]
+        (0, 13, 192) => Some("dust-lower-layer"), // ダスト下層濃度
+        (0, 13, 193) => Some("dust-air-pillar"), // ダスト気柱積算量
+        (0, 19, 0) => Some("visibility"),     // Visibility
+        (0, 191, 192) => Some("weather"),     // 天気
+        (0, 193, 0) => Some("tornado"),       // 竜巻発生確度
+        (0, 193, 1) => Some("thunder"),       // 雷活動度
+
+        // 10 Oceanographic products
+        (10, 2, 0) => Some("ice-cover"),              // Ice cover
+        (10, 2, 1) => Some("ice-thickness"),          // Ice thickness
+        (10, 2, 4) | (10, 2, 5) => Some("ice-drift"), // u, v-component of ice drift
+        (10, 3, 0) => Some("temperature"),            // Water temperature [K]
+        (10, 3, 200) | (10, 3, 201) => Some("tide"),  // 天文潮位, 実際の潮位
+        _ => None,
+    }
+}
+
+// 名称のカラーマップを返す。
+pub(crate) fn colormap(name: &str) -> Option<Colormap> {
+    let steps: &[(f64, [u8; 4])] = match name {
+        "temperature" => &[
+            (-10.0 + ZERO_CELSIUS, rgb(0, 29, 114)),
+            (-5.0 + ZERO_CELSIUS, rgb(0, 57, 248)),
+            (0.0 + ZERO_CELSIUS, rgb(0, 139, 250)),
+            (5.0 + ZERO_CELSIUS, rgb(169, 232, 253)),
+            (10.0 + ZERO_CELSIUS, rgb(255, 255, 239)),
+            (15.0 + ZERO_CELSIUS, rgb(255, 255, 148)),
+            (20.0 + ZERO_CELSIUS, rgb(252, 243, 55)),
+            (25.0 + ZERO_CELSIUS, rgb(255, 143, 39)),
+            (30.0 + ZERO_CELSIUS, rgb(255, 38, 27)),
+            (35.0 + ZERO_CELSIUS, rgb(180, 8, 92)),
+        ],
+        "percentage" => &[
+            (0.0, rgb(26, 26, 26)),
+            (10.0, rgb(51, 51, 51)),
+            (20.0, rgb(77, 77, 77)),
+            (30.0, rgb(102, 102, 102)),
+            (40.0, rgb(128, 128, 128)),
+            (50.0, rgb(153, 153, 153)),
+            (60.0, rgb(179, 179, 179)),
+            (70.0, rgb(204, 204, 204)),
+            (80.0, rgb(230, 230, 230)),
+            (90.0, rgb(255, 255, 255)),
+        ],
+        "cloud" => &[
+            (0.0, rgb(0, 0, 0)),
+            (10.0, rgb(51, 51, 51)),
+            (20.0, rgb(77, 77, 77)),
+            (30.0, rgb(102, 102, 102)),
+            (40.0, rgb(128, 128, 128)),
+            (50.0, rgb(153, 153, 153)),
+            (60.0, rgb(179, 179, 179)),
+            (70.0, rgb(204, 204, 204)),
+            (80.0, rgb(230, 230, 230)),
+            (90.0, rgb(255, 255, 255)),
+        ],
+        "precipitation" => &[
+            (0.0, TRANSPARENT), // No echo
+            (0.1, rgb(240, 240, 254)),
+            (1.0, rgb(153, 204, 253)),
+            (5.0, rgb(44, 131, 251)),
+            (10.0, rgb(27, 65, 250)),
+            (20.0, rgb(253, 241, 49)),
+            (30.0, rgb(251, 143, 36)),
+            (50.0, rgb(250, 46, 28)),
+            (80.0, rgb(168, 23, 93)),
+        ],
+        // 10分間降水強度
+        "precipitation-10min" => &[
+            (0.0 / 6.0, TRANSPARENT), // No echo
+            (0.1 / 6.0, rgb(240, 240, 254)),
+            (1.0 / 6.0, rgb(153, 204, 253)),
+            (5.0 / 6.0, rgb(44, 131, 251)),
+            (10.0 / 6.0, rgb(27, 65, 250)),
+            (20.0 / 6.0, rgb(253, 241, 49)),
+            (30.0 / 6.0, rgb(251, 143, 36)),
+            (50.0 / 6.0, rgb(250, 46, 28)),
+            (80.0 / 6.0, rgb(168, 23, 93)),
+        ],
+        "total-precipitation" => &[
+            (0.0, TRANSPARENT),
+            (0.1, rgb(240, 240, 254)),
+            (50.0, rgb(153, 204, 253)),
+            (80.0, rgb(44, 131, 251)),
+            (100.0, rgb(27, 65, 250)),
+            (150.0, rgb(253, 241, 49)),
+            (200.0, rgb(251, 143, 36)),
+            (250.0, rgb(250, 46, 28)),
+            (300.0, rgb(168, 23, 93)),
+        ],
+        "precipitation-level" => &[
+            (0.0, rgb(240, 240, 254)),
+            (1.0, rgb(153, 204, 253)),
+            (5.0, rgb(44, 131, 251)),
+            (10.0, rgb(27, 65, 250)),
+            (20.0, rgb(253, 241, 49)),
+            (30.0, rgb(251, 143, 36)),
+            (50.0, rgb(250, 46, 28)),
+            (80.0, rgb(168, 23, 93)),
+        ],
+        "wind" => &[
+            (0.0, rgb(240, 240, 254)),
+            (5.0, rgb(0, 57, 248)),
+            (10.0, rgb(252, 243, 55)),
+            (15.0, rgb(255, 143, 39)),
+            (20.0, rgb(255, 38, 27)),
+            (25.0, rgb(180, 8, 92)),
+        ],
+        "weather" => &[
+            (0.0, rgb(0, 0, 0)),
+            (1.0, rgb(255, 208, 148)),
+            (2.0, rgb(208, 208, 208)),
+            (3.0, rgb(128, 156, 252)),
+            (4.0, rgb(198, 229, 254)),
+            (5.0, rgb(247, 247, 255)),
+        ],
+        // 竜巻発生確度
+        "tornado" => &[
+            (0.0, TRANSPARENT),        // 計算領域外又は欠測
+            (1.0, TRANSPARENT),        // なし
+            (2.0, rgb(251, 248, 151)), // 発生確度1
+            (3.0, rgb(252, 150, 141)), // 発生確度2
+        ],
+        // 雷活動度
+        "thunder" => &[
+            (0.0, TRANSPARENT),        // 計算領域外又は欠測
+            (1.0, TRANSPARENT),        // なし
+            (2.0, rgb(254, 248, 151)), // 活動度1
+            (3.0, rgb(253, 207, 146)), // 活動度2
+            (4.0, rgb(252, 150, 141)), // 活動度3
+            (5.0, rgb(223, 147, 252)), // 活動度4
+        ],
+        // 土砂災害警戒判定値
+        "sediment-warning-index" => &[
+            (0.0, TRANSPARENT),      // 欠測値
+            (1.0, TRANSPARENT),      // 海等の格子
+            (2.0, TRANSPARENT),      // 警戒判定対象外格子
+            (3.0, TRANSPARENT),      // 土砂災害警戒判定値0
+            (4.0, rgb(242, 231, 0)), // 土砂災害警戒判定値1
+            (5.0, rgb(255, 40, 0)),  // 土砂災害警戒判定値2
+            (6.0, rgb(170, 0, 170)), // 土砂災害警戒判定値3
+            (7.0, rgb(12, 0, 12)),   // 土砂災害警戒判定値4
+            (8.0, TRANSPARENT),      // 予備
+        ],
+        // 警戒判定値
+        "warning-index" => &[
+            (0.0, TRANSPARENT),      // 欠測値
+            (1.0, TRANSPARENT),      // 警戒判定値0
+            (2.0, rgb(242, 231, 0)), // 警戒判定値1
+            (3.0, rgb(255, 40, 0)),  // 警戒判定値2
+            (4.0, rgb(170, 0, 170)), // 警戒判定値3
+            (5.0, rgb(12, 0, 12)),   // 警戒判定値4
+            (6.0, TRANSPARENT),      // 予備
+        ],
+        "uv-index" => return Some(Colormap::rainbow(0.0, 13.0, 14)),
+        "short-wave-radiation-flux" => return Some(Colormap::grayscale(0.0, 1000.0, 10)),
+        "pressure" => return Some(Colormap::grayscale(95000.0, 105000.0, 10)),
+        "vertical-velocity" => return Some(Colormap::grayscale(0.0, 1000.0, 10)),
+        "geopotential-height" => return Some(Colormap::grayscale(0.0, 1000.0, 10)),
+        "tide" => return Some(Colormap::rainbow(0.6, 3.0, 24)),
+        "visibility" => return Some(Colormap::rainbow(0.0, 10000.0, 20)),
+        "dust-lower-layer" => return Some(Colormap::rainbow(0.0, 2e-7, 20)),
+        "dust-air-pillar" => return Some(Colormap::rainbow(0.0, 2e-4, 20)),
+        "ice-cover" => return Some(Colormap::rainbow(0.0, 1.0, 20)),
+        "ice-thickness" => return Some(Colormap::rainbow(0.05, 1.2, 23)),
+        "ice-drift" => return Some(Colormap::rainbow(0.0, 1.4, 14)),
+        _ => return None,
+    };
+
+    Some(Colormap {
+        thresholds: steps.iter().map(|(x, _)| *x).collect(),
+        colors: steps.iter().map(|(_, x)| *x).collect(),
+    })
+}
+
+// 0-1 の色成分を 0-255 にする。
+fn to_u8(x: f64) -> u8 {
+    (x.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+// H: Hue angle, S: Saturation, V: Value
+fn hsv_to_rgb(h: f64, s: f64, v: f64) -> [u8; 4] {
+    let h = if 360.0 <= h { 0.0 } else { h };
+
+    let hi = (h / 60.0).floor() as usize % 6;
+    let f = h / 60.0 - hi as f64;
+    let p = v * (1.0 - s);
+    let q = v * (1.0 - s * f);
+    let t = v * (1.0 - s * (1.0 - f));
+
+    let (r, g, b) = match hi {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    };
+    rgb(to_u8(r), to_u8(g), to_u8(b))
+}
+
+// 値の範囲を差の桁に合わせて内側に丸める。
+pub(crate) fn normalize_range(min: f64, max: f64) -> (f64, f64) {
+    // 差が 10^-n 未満なら 10^(n+1) 倍して丸める。最も細かくて 10^8 倍
+    let d = max - min;
+    let mut scale = 1.0;
+    let mut limit = 1.0;
+    while d < limit && scale < 1e8 {
+        scale *= 10.0;
+        limit /= 10.0;
+    }
+    ((min * scale).ceil() / scale, (max * scale).floor() / scale)
+}
+
+impl Colormap {
+    // 黒から白への steps 段階のカラーマップ
+    pub(crate) fn grayscale(min: f64, max: f64, steps: usize) -> Self {
+        let mut thresholds = Vec::with_capacity(steps);
+        let mut colors = Vec::with_capacity(steps);
+        for i in 0..steps {
+            let d = i as f64 / (steps - 1) as f64;
+            thresholds.push(min + (max - min) * d);
+            colors.push(rgb(to_u8(d), to_u8(d), to_u8(d)));
+        }
+        Self { thresholds, colors }
+    }
+
+    // 青から赤への steps 段階のカラーマップ
+    pub(crate) fn rainbow(min: f64, max: f64, steps: usize) -> Self {
+        let mut thresholds = Vec::with_capacity(steps);
+        let mut colors = Vec::with_capacity(steps);
+        for i in 0..steps {
+            let d = i as f64 / (steps - 1) as f64;
+            let threshold = min + (max - min) * d;
+            let h = if min < max {
+                ((1.0 - (threshold - min) / (max - min)) * 240.0).max(0.0)
+            } else {
+                0.0
+            };
+            thresholds.push(threshold);
+            colors.push(hsv_to_rgb(h, 1.0, 1.0));
+        }
+        Self { thresholds, colors }
+    }
+}
+
+// 要素に対応するカラーマップを返す。なければ値の範囲に合わせた20段階の虹色にする。
+pub(crate) fn default_colormap(
+    discipline: Option<usize>,
+    parameter_category: Option<usize>,
+    parameter_number: Option<usize>,
+    values: &[f32],
+) -> Colormap {
+    let name = match (discipline, parameter_category, parameter_number) {
+        (Some(d), Some(c), Some(n)) => colormap_name(d, c, n),
+        _ => None,
+    };
+    if let Some(colormap) = name.and_then(colormap) {
+        return colormap;
+    }
+
+    let valid = values.iter().filter(|x| !x.is_nan());
+    let min = valid.clone().fold(f32::INFINITY, |a, b| a.min(*b));
+    let max = valid.fold(f32::NEG_INFINITY, |a, b| a.max(*b));
+    if max < min {
+        return Colormap::rainbow(0.0, 1.0, 20);
+    }
+    let (min, max) = normalize_range(min as f64, max as f64);
+    Colormap::rainbow(min, max, 20)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 名称のカラーマップはしきい値と色が同じ数で、しきい値は昇順に並ぶ。
+    #[test]
+    fn named() {
+        let names = [
+            "temperature",
+            "percentage",
+            "cloud",
+            "precipitation",
+            "precipitation-10min",
+            "total-precipitation",
+            "precipitation-level",
+            "wind",
+            "weather",
+            "tornado",
+            "thunder",
+            "sediment-warning-index",
+            "warning-index",
+            "uv-index",
+            "short-wave-radiation-flux",
+            "pressure",
+            "vertical-velocity",
+            "geopotential-height",
+            "tide",
+            "visibility",
+            "dust-lower-layer",
+            "dust-air-pillar",
+            "ice-cover",
+            "ice-thickness",
+            "ice-drift",
+        ];
+        for name in names.iter() {
+            let colormap = colormap(name).unwrap();
+            assert_eq!(colormap.thresholds.len(), colormap.colors.len(), "{}", name);
+            assert!(
+                colormap.thresholds.windows(2).all(|x| x[0] < x[1]),
+                "{}",
+                name
+            );
+        }
+        assert!(colormap("unknown").is_none());
+        assert_eq!(colormap_name(0, 0, 0), Some("temperature"));
+        assert_eq!(colormap_name(0, 1, 217), Some("warning-index"));
+        assert_eq!(colormap_name(0, 0, 1), None);
+    }
+
+    #[test]
+    fn generated() {
+        let gray = Colormap::grayscale(0.0, 100.0, 3);
+        assert_eq!(gray.thresholds, [0.0, 50.0, 100.0]);
+        assert_eq!(
+            gray.colors,
+            [rgb(0, 0, 0), rgb(128, 128, 128), rgb(255, 255, 255)]
+        );
+
+        // 青、シアン、緑、黄、赤
+        let rainbow = Colormap::rainbow(0.0, 4.0, 5);
+        assert_eq!(rainbow.thresholds, [0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(
+            rainbow.colors,
+            [
+                rgb(0, 0, 255),
+                rgb(0, 255, 255),
+                rgb(0, 255, 0),
+                rgb(255, 255, 0),
+                rgb(255, 0, 0)
+            ]
+        );
+        // 範囲がなければすべて赤
+        assert!(Colormap::rainbow(1.0, 1.0, 3)
+            .colors
+            .iter()
+            .all(|x| *x == rgb(255, 0, 0)));
+    }
+
+    #[test]
+    fn range() {
+        assert_eq!(normalize_range(273.4, 301.7), (274.0, 301.0));
+        assert_eq!(normalize_range(0.123, 0.987), (0.2, 0.9));
+        assert_eq!(normalize_range(0.0012, 0.0087), (0.002, 0.008));
+        assert_eq!(normalize_range(5.0, 5.0), (5.0, 5.0));
+    }
+
+    // 要素のカラーマップがなければ値の範囲に合わせる。欠測は除く。
+    #[test]
+    fn default() {
+        let colormap = default_colormap(Some(0), Some(0), Some(0), &[]);
+        assert_eq!(colormap.thresholds[0], -10.0 + ZERO_CELSIUS);
+
+        let colormap = default_colormap(Some(0), Some(0), Some(1), &[f32::NAN, 1.2, 8.7]);
+        assert_eq!(colormap.thresholds.len(), 20);
+        assert_eq!(colormap.thresholds[0], 2.0);
+        assert_eq!(colormap.thresholds[19], 8.0);
+
+        let colormap = default_colormap(None, None, None, &[f32::NAN]);
+        assert_eq!(
+            (colormap.thresholds[0], colormap.thresholds[19]),
+            (0.0, 1.0)
+        );
+    }
+}
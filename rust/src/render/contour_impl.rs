@@ -0,0 +1,467 @@
+//! Contour implementation
+//! マーチングスクエア法で格子の値から等値線を求め、GeoJSON にする。
+
+use std::collections::HashMap;
+
+use serde_json::json;
+use serde_json::Value;
+
+use crate::grib2::section::section3_template::TemplateNumber as Section3TemplateNumber;
+
+use super::Contour;
+
+const MAX_LEVELS: usize = 1000; // 等値線の値の数の上限
+
+// 格子の辺。(横の辺なら 0 縦の辺なら 1, i, j)
+// 横の辺 (0, i, j) は (i, j) と (i + 1, j)、縦の辺 (1, i, j) は (i, j) と (i, j + 1) を結ぶ。
+type Edge = (u8, usize, usize);
+
+// セルの四隅 a = (i, j), b = (i + 1, j), c = (i + 1, j + 1), d = (i, j + 1) のうち値が等値線以上の隅から、
+// 等値線が横切る辺の組を返す。辺は 0: a-b, 1: b-c, 2: d-c, 3: a-d
+// 鞍点はセルの中心の値（四隅の平均）で分ける。
+fn segments(case: usize, center_above: bool) -> &'static [(usize, usize)] {
+    match case {
+        1 | 14 => &[(3, 0)],
+        2 | 13 => &[(0, 1)],
+        3 | 12 => &[(3, 1)],
+        4 | 11 => &[(1, 2)],
+        6 | 9 => &[(0, 2)],
+        7 | 8 => &[(2, 3)],
+        5 if center_above => &[(0, 1), (2, 3)],
+        5 => &[(3, 0), (1, 2)],
+        10 if center_above => &[(3, 0), (1, 2)],
+        10 => &[(0, 1), (2, 3)],
+        _ => &[],
+    }
+}
+
+// 値の範囲に含まれる base + k * interval を等値線の値として返す。
+pub(crate) fn contour_levels(values: &[f32], interval: f64, base: f64) -> Result<Vec<f64>, String> {
+    if !interval.is_finite() || interval <= 0.0 {
+        return Err(format!("Invalid contour interval: {}", interval));
+    }
+    let (min, max) = values
+        .iter()
+        .filter(|x| !x.is_nan())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+            (min.min(*x as f64), max.max(*x as f64))
+        });
+    if max < min {
+        return Ok(Vec::new());
+    }
+
+    let first = ((min - base) / interval).ceil() as i64;
+    let last = ((max - base) / interval).floor() as i64;
+    if MAX_LEVELS as i64 <= last - first {
+        return Err(format!(
+            "Too many contour levels: {} (interval {})",
+            last - first + 1,
+            interval
+        ));
+    }
+    Ok((first..=last).map(|k| base + k as f64 * interval).collect())
+}
+
+// 線分をつないで折れ線にする。閉じた線は最初と最後の辺が同じになる。
+fn chain(segments: &[(Edge, Edge)]) -> Vec<Vec<Edge>> {
+    let mut by_edge: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (k, (a, b)) in segments.iter().enumerate() {
+        by_edge.entry(*a).or_default().push(k);
+        by_edge.entry(*b).or_default().push(k);
+    }
+
+    let mut used = vec![false; segments.len()];
+    // edge を端に持つ未使用の線分があれば、使用済みにして反対側の辺を返す。
+    let follow = |used: &mut [bool], edge: Edge| -> Option<Edge> {
+        let k = *by_edge.get(&edge)?.iter().find(|k| !used[**k])?;
+        used[k] = true;
+        let (a, b) = segments[k];
+        Some(if a == edge { b } else { a })
+    };
+
+    let mut lines = Vec::new();
+    for k in 0..segments.len() {
+        if used[k] {
+            continue;
+        }
+        used[k] = true;
+        let (start, end) = segments[k];
+        let mut line = vec![start, end];
+        let mut edge = end;
+        while let Some(next) = follow(&mut used, edge) {
+            line.push(next);
+            edge = next;
+            if next == start {
+                break;
+            }
+        }
+        // 閉じていなければ反対向きにも延ばす。
+        if line.last() != Some(&start) {
+            let mut edge = start;
+            let mut head = Vec::new();
+            while let Some(next) = follow(&mut used, edge) {
+                head.push(next);
+                edge = next;
+            }
+            head.reverse();
+            head.extend(line);
+            line = head;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+// Chaikin 法で角を丸める。閉じていない線の両端は動かさない。
+fn smooth(points: &[(f64, f64)], iterations: usize) -> Vec<(f64, f64)> {
+    let mut points = points.to_vec();
+    for _ in 0..iterations {
+        if points.len() < 3 {
+            break;
+        }
+        let closed = points.first() == points.last();
+        let mut dest = Vec::with_capacity(points.len() * 2);
+        if !closed {
+            dest.push(points[0]);
+        }
+        for w in points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (w[0], w[1]);
+            dest.push((0.75 * x0 + 0.25 * x1, 0.75 * y0 + 0.25 * y1));
+            dest.push((0.25 * x0 + 0.75 * x1, 0.25 * y0 + 0.75 * y1));
+        }
+        if closed {
+            dest.push(dest[0]);
+        } else {
+            dest.push(*points.last().unwrap());
+        }
+        points = dest;
+    }
+    points
+}
+
+// 経度が ±180 度をまたぐところで線を分ける。
+fn split_antimeridian(points: &[(f64, f64)]) -> Vec<Vec<(f64, f64)>> {
+    let mut lines = Vec::new();
+    let mut line: Vec<(f64, f64)> = Vec::new();
+    for &(lon, lat) in points.iter() {
+        if let Some(&(lon0, lat0)) = line.last() {
+            if 180.0 < (lon - lon0).abs() {
+                // 前の点から連続する経度で交点の緯度を求める。
+                let edge = 180f64.copysign(lon0);
+                let lon1 = lon + 360f64.copysign(lon0);
+                let f = (edge - lon0) / (lon1 - lon0);
+                let crossing = lat0 + (lat - lat0) * f;
+                line.push((edge, crossing));
+                lines.push(line);
+                line = vec![(-edge, crossing)];
+            }
+        }
+        line.push((lon, lat));
+    }
+    lines.push(line);
+    // 閉じた線では最後の線と最初の線がつながる。
+    if 2 < points.len() && points.first() == points.last() && 1 < lines.len() {
+        let first = lines.remove(0);
+        if let Some(last) = lines.last_mut() {
+            last.extend(first.into_iter().skip(1));
+        }
+    }
+    lines.into_iter().filter(|x| 2 <= x.len()).collect()
+}
+
+// 格子の値から levels の各値の等値線を求める。欠測（NaN）の格子点を含むセルでは線を切る。
+// smoothing は角を丸める回数
+pub(crate) fn contours(
+    geometry: &Section3TemplateNumber,
+    values: &[f32],
+    levels: &[f64],
+    smoothing: usize,
+) -> Vec<Contour> {
+    let (ni, nj) = (geometry.ni(), geometry.nj());
+    if values.len() != ni * nj || ni < 2 || nj < 2 {
+        return Vec::new();
+    }
+    // 経度が一周していれば最後の列と最初の列の間のセルも使う。
+    let periodic = geometry.is_periodic();
+    let columns = if periodic { ni } else { ni - 1 };
+    let value = |i: usize, j: usize| values[j * ni + i % ni] as f64;
+
+    let mut dest = Vec::new();
+    for &level in levels.iter() {
+        // 辺と等値線の交点（格子点の位置）
+        let mut crossings: HashMap<Edge, (f64, f64)> = HashMap::new();
+        let mut lines: Vec<(Edge, Edge)> = Vec::new();
+        for j in 0..nj - 1 {
+            for i in 0..columns {
+                let corners = [
+                    value(i, j),
+                    value(i + 1, j),
+                    value(i + 1, j + 1),
+                    value(i, j + 1),
+                ];
+                if corners.iter().any(|x| x.is_nan()) {
+                    continue;
+                }
+                let case = corners
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, x)| level <= **x)
+                    .fold(0, |case, (k, _)| case | 1 << k);
+                let center_above = level <= corners.iter().sum::<f64>() / 4.0;
+
+                let i1 = (i + 1) % ni;
+                let edges = [(0, i, j), (1, i1, j), (0, i, j + 1), (1, i, j)];
+                // 辺の両端の隅
+                let ends = [(0, 1), (1, 2), (3, 2), (0, 3)];
+                let origins = [(i, j), (i + 1, j), (i, j + 1), (i, j)];
+                for (a, b) in segments(case, center_above).iter() {
+                    for e in [*a, *b] {
+                        crossings.entry(edges[e]).or_insert_with(|| {
+                            let (p, q) = ends[e];
+                            let t = (level - corners[p]) / (corners[q] - corners[p]);
+                            let (oi, oj) = origins[e];
+                            match edges[e].0 {
+                                0 => (oi as f64 + t, oj as f64),
+                                _ => (oi as f64, oj as f64 + t),
+                            }
+                        });
+                    }
+                    lines.push((edges[*a], edges[*b]));
+                }
+            }
+        }
+
+        for line in chain(&lines) {
+            // 周期的な格子では i が連続するよう一周分ずらす。経度を一周する線は始点と終点の i が一周分異なる。
+            let mut points: Vec<(f64, f64)> = Vec::with_capacity(line.len());
+            for edge in line.iter() {
+                let (mut i, j) = crossings[edge];
+                if let Some(&(i0, _)) = points.last() {
+                    if periodic {
+                        let period = ni as f64;
+                        i += ((i0 - i) / period).round() * period;
+                    }
+                }
+                points.push((i, j));
+            }
+
+            let points: Vec<(f64, f64)> = smooth(&points, smoothing)
+                .iter()
+                .map(|(i, j)| geometry.ij_to_lonlat(*i, *j))
+                .collect();
+            for points in split_antimeridian(&points) {
+                dest.push(Contour {
+                    value: level,
+                    points,
+                });
+            }
+        }
+    }
+    dest
+}
+
+// 小数点以下6桁に丸める。10^-6 を掛けると誤差が出るため 10^6 で割る。
+fn round(value: f64) -> f64 {
+    (value * 1e6).round() / 1e6
+}
+
+// 等値線を value プロパティを持つ LineString の FeatureCollection にする。
+pub(crate) fn geojson(contours: &[Contour]) -> String {
+    let features: Vec<Value> = contours
+        .iter()
+        .map(|contour| {
+            let coordinates: Vec<[f64; 2]> = contour
+                .points
+                .iter()
+                .map(|(lon, lat)| [round(*lon), round(*lat)])
+                .collect();
+            json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "LineString",
+                    "coordinates": coordinates,
+                },
+                "properties": {
+                    "value": contour.value,
+                },
+            })
+        })
+        .collect();
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grib2::section::Section3;
+    use crate::grib2::utils_impl::tests::latlon_grid;
+
+    // 北緯36度、東経139度から 1 度間隔の格子の等値線
+    fn contour(ni: u32, nj: u32, values: &[f32], level: f64) -> Vec<Contour> {
+        let buf = latlon_grid(ni, nj, 36.0, 139.0, 1.0, 1.0);
+        let geometry = Section3::create(&buf).template().unwrap();
+        contours(&geometry, values, &[level], 0)
+    }
+
+    // 座標を 10^-6 度に丸め、向きによらず比べられるように並べ替える。
+    fn normalized(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        let mut points: Vec<(f64, f64)> =
+            points.iter().map(|(x, y)| (round(*x), round(*y))).collect();
+        points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        points
+    }
+
+    #[test]
+    fn levels() {
+        assert_eq!(
+            contour_levels(&[0.5, f32::NAN, 9.7], 2.0, 1.0),
+            Ok(vec![1.0, 3.0, 5.0, 7.0, 9.0])
+        );
+        assert_eq!(contour_levels(&[f32::NAN], 2.0, 0.0), Ok(Vec::new()));
+        assert!(contour_levels(&[0.0, 1.0], 0.0, 0.0).is_err());
+        assert!(contour_levels(&[0.0, 1.0], f64::NAN, 0.0).is_err());
+        assert!(contour_levels(&[0.0, 1.0], 1e-4, 0.0).is_err());
+    }
+
+    // 中央が高い 3 x 3 の格子では中央を囲む閉じた線になる。
+    #[test]
+    fn closed() {
+        let values = [0.0, 0.0, 0.0, 0.0, 10.0, 0.0, 0.0, 0.0, 0.0];
+        let lines = contour(3, 3, &values, 5.0);
+        assert_eq!(lines.len(), 1);
+        let points = &lines[0].points;
+        assert_eq!(points.len(), 5);
+        assert_eq!(points.first(), points.last());
+        assert_eq!(
+            normalized(&points[1..]),
+            [(139.5, 35.0), (140.0, 34.5), (140.0, 35.5), (140.5, 35.0)]
+        );
+        assert_eq!(lines[0].value, 5.0);
+
+        // 中央が欠測ならセルを飛ばして線を引かない。
+        let mut values = values;
+        values[4] = f32::NAN;
+        assert!(contour(3, 3, &values, 5.0).is_empty());
+    }
+
+    // 鞍点のセルはセルの中心の値（四隅の平均 5）で分ける。
+    #[test]
+    fn saddle() {
+        // (0, 0) と (1, 1) が高い。
+        let values = [10.0, 0.0, 0.0, 10.0];
+        let pairs = |level| {
+            let mut lines: Vec<Vec<(f64, f64)>> = contour(2, 2, &values, level)
+                .iter()
+                .map(|x| normalized(&x.points))
+                .collect();
+            lines.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            lines
+        };
+        // 中心が等値線以上なら低い隅 (1, 0) と (0, 1) を切り離す。
+        assert_eq!(
+            pairs(4.0),
+            [
+                vec![(139.0, 35.4), (139.4, 35.0)],
+                vec![(139.6, 36.0), (140.0, 35.6)],
+            ]
+        );
+        // 中心が等値線未満なら高い隅 (0, 0) と (1, 1) を切り離す。
+        assert_eq!(
+            pairs(6.0),
+            [
+                vec![(139.0, 35.6), (139.4, 36.0)],
+                vec![(139.6, 35.0), (140.0, 35.4)],
+            ]
+        );
+    }
+
+    #[test]
+    fn antimeridian() {
+        let lines = split_antimeridian(&[(170.0, 0.0), (-170.0, 10.0)]);
+        assert_eq!(
+            lines,
+            [
+                vec![(170.0, 0.0), (180.0, 5.0)],
+                vec![(-180.0, 5.0), (-170.0, 10.0)]
+            ]
+        );
+
+        // 閉じた線は最後の線と最初の線をつなぐ。
+        let ring = [
+            (170.0, 0.0),
+            (-170.0, 0.0),
+            (-170.0, 10.0),
+            (170.0, 10.0),
+            (170.0, 0.0),
+        ];
+        assert_eq!(
+            split_antimeridian(&ring),
+            [
+                vec![(-180.0, 0.0), (-170.0, 0.0), (-170.0, 10.0), (-180.0, 10.0)],
+                vec![(180.0, 10.0), (170.0, 10.0), (170.0, 0.0), (180.0, 0.0)],
+            ]
+        );
+    }
+
+    // 経度を一周する格子では最後の列と最初の列の間にも線を引き、経度 180 度で分ける。
+    #[test]
+    fn periodic() {
+        // 経度 0, 90, 180, 270 度の 4 x 3 の格子。経度 180 度、緯度 0 度が高い。
+        let buf = latlon_grid(4, 3, 10.0, 0.0, 90.0, 10.0);
+        let geometry = Section3::create(&buf).template().unwrap();
+        let mut values = [0.0; 12];
+        values[6] = 10.0;
+        let lines = contours(&geometry, &values, &[5.0], 0);
+        assert_eq!(lines.len(), 2);
+        for line in lines.iter() {
+            assert!(line
+                .points
+                .windows(2)
+                .all(|x| (x[1].0 - x[0].0).abs() <= 180.0));
+        }
+        let mut points: Vec<(f64, f64)> = lines.iter().flat_map(|x| x.points.clone()).collect();
+        points.dedup();
+        assert!(points.contains(&(135.0, 0.0)));
+        assert!(points.contains(&(-135.0, 0.0)));
+        assert!(points.iter().any(|x| x.0 == 180.0) && points.iter().any(|x| x.0 == -180.0));
+    }
+
+    // 閉じていない線の両端は動かさず、閉じた線は閉じたままにする。
+    #[test]
+    fn smoothing() {
+        let line = smooth(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0)], 1);
+        assert_eq!(
+            line,
+            [
+                (0.0, 0.0),
+                (1.0, 0.0),
+                (3.0, 0.0),
+                (4.0, 1.0),
+                (4.0, 3.0),
+                (4.0, 4.0)
+            ]
+        );
+        let ring = smooth(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 0.0)], 2);
+        assert_eq!(ring.first(), ring.last());
+    }
+
+    #[test]
+    fn feature_collection() {
+        let json = geojson(&[Contour {
+            value: 5.0,
+            points: vec![(139.1234567, 36.0), (140.0, 35.5)],
+        }]);
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["type"], "FeatureCollection");
+        let feature = &value["features"][0];
+        assert_eq!(feature["properties"]["value"], 5.0);
+        assert_eq!(
+            feature["geometry"]["coordinates"],
+            json!([[139.123457, 36.0], [140.0, 35.5]])
+        );
+    }
+}
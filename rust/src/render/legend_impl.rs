@@ -0,0 +1,164 @@
+//! 凡例 implementation
+
+use super::Colormap;
+use super::ColormapMode;
+
+pub(crate) const LEGEND_HEIGHT: usize = 30;
+
+const MARGIN: usize = 4;
+const BAR_HEIGHT: usize = 12;
+const LABEL_GAP: usize = 3;
+
+const BACKGROUND: [u8; 4] = [255, 255, 255, 255];
+const FOREGROUND: [u8; 4] = [32, 32, 32, 255];
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+
+// 5x7 のビットマップフォント。各行の下位5ビットを左から並べる。
+fn glyph(c: char) -> Option<[u8; GLYPH_HEIGHT]> {
+    match c {
+        '0' => Some([0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+        '1' => Some([0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+        '2' => Some([0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+        '3' => Some([0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+        '4' => Some([0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+        '5' => Some([0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+        '6' => Some([0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+        '7' => Some([0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+        '8' => Some([0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+        '9' => Some([0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+        '.' => Some([0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+        '-' => Some([0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+        '+' => Some([0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00]),
+        'e' => Some([0x00, 0x00, 0x0E, 0x11, 0x1F, 0x10, 0x0E]),
+        _ => None,
+    }
+}
+
+// しきい値を短く表す。 e.g. 0.1, 273.15, 2e-7
+fn format_label(value: f64) -> String {
+    if value == 0.0 {
+        return String::from("0");
+    }
+    if !(1e-3..1e5).contains(&value.abs()) {
+        return format!("{:e}", value);
+    }
+    let label = format!("{:.3}", value);
+    label
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+fn label_width(label: &str) -> usize {
+    label.chars().count() * (GLYPH_WIDTH + 1)
+}
+
+// 幅 width の RGBA の画像
+struct Canvas {
+    width: usize,
+    rgba: Vec<u8>,
+}
+
+impl Canvas {
+    fn set(&mut self, x: usize, y: usize, color: [u8; 4]) {
+        if x < self.width {
+            let k = (y * self.width + x) * 4;
+            if k + 4 <= self.rgba.len() {
+                self.rgba[k..k + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    fn draw_text(&mut self, x: usize, y: usize, text: &str) {
+        for (n, c) in text.chars().enumerate() {
+            let rows = match glyph(c) {
+                Some(rows) => rows,
+                None => continue,
+            };
+            for (dy, row) in rows.iter().enumerate() {
+                for dx in 0..GLYPH_WIDTH {
+                    if row & (0x10 >> dx) != 0 {
+                        self.set(x + n * (GLYPH_WIDTH + 1) + dx, y + dy, FOREGROUND);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 透明な色は背景に重ねて表す。
+fn over_background(color: [u8; 4]) -> [u8; 4] {
+    let alpha = color[3] as u32;
+    let mut dest = BACKGROUND;
+    for k in 0..3 {
+        dest[k] = ((color[k] as u32 * alpha + BACKGROUND[k] as u32 * (255 - alpha)) / 255) as u8;
+    }
+    dest
+}
+
+impl Colormap {
+    // 幅 width、高さ LEGEND_HEIGHT の凡例を RGBA で返す。
+    // 色を等しい幅で並べ、各色の左端にしきい値を記す。重なるしきい値は省く。
+    pub(crate) fn legend(&self, width: usize, mode: ColormapMode) -> Vec<u8> {
+        let mut canvas = Canvas {
+            width,
+            rgba: BACKGROUND.repeat(width * LEGEND_HEIGHT),
+        };
+        let count = self.colors.len();
+        if count == 0 || width < MARGIN * 2 + count {
+            return canvas.rgba;
+        }
+
+        let bar_width = width - MARGIN * 2;
+        let bar_top = MARGIN;
+        let segment = |x: usize| (x * count / bar_width).min(count - 1);
+        for x in 0..bar_width {
+            let k = segment(x);
+            let color = match mode {
+                ColormapMode::Stepped => self.colors[k],
+                ColormapMode::Continuous => {
+                    // 区間の中でしきい値の間を補間する。
+                    let start = k * bar_width / count;
+                    let end = (k + 1) * bar_width / count;
+                    let t = (x - start) as f64 / (end - start).max(1) as f64;
+                    let value = match self.thresholds.get(k + 1) {
+                        Some(next) => self.thresholds[k] + (next - self.thresholds[k]) * t,
+                        None => self.thresholds[k],
+                    };
+                    self.color(value, mode)
+                }
+            };
+            let color = over_background(color);
+            for y in bar_top..bar_top + BAR_HEIGHT {
+                canvas.set(MARGIN + x, y, color);
+            }
+        }
+
+        // 枠
+        for x in MARGIN - 1..=MARGIN + bar_width {
+            canvas.set(x, bar_top - 1, FOREGROUND);
+            canvas.set(x, bar_top + BAR_HEIGHT, FOREGROUND);
+        }
+        for y in bar_top - 1..=bar_top + BAR_HEIGHT {
+            canvas.set(MARGIN - 1, y, FOREGROUND);
+            canvas.set(MARGIN + bar_width, y, FOREGROUND);
+        }
+
+        // しきい値
+        let label_top = bar_top + BAR_HEIGHT + LABEL_GAP;
+        let mut next_x = 0;
+        for (k, threshold) in self.thresholds.iter().enumerate() {
+            let x = MARGIN + k * bar_width / count;
+            let label = format_label(*threshold);
+            if x < next_x || width < x + label_width(&label) {
+                continue;
+            }
+            canvas.set(x, bar_top + BAR_HEIGHT + 1, FOREGROUND);
+            canvas.draw_text(x, label_top, &label);
+            next_x = x + label_width(&label) + GLYPH_WIDTH;
+        }
+        canvas.rgba
+    }
+}
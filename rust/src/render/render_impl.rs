@@ -0,0 +1,154 @@
+//! 描画 implementation
+
+use std::str::FromStr;
+
+use super::Colormap;
+use super::ColormapMode;
+
+const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+impl FromStr for ColormapMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stepped" => Ok(ColormapMode::Stepped),
+            "continuous" => Ok(ColormapMode::Continuous),
+            _ => Err(format!("Unknown colormap mode: {}", s)),
+        }
+    }
+}
+
+// 2つの色を t : (1 - t) で混ぜる。
+fn mix(a: [u8; 4], b: [u8; 4], t: f64) -> [u8; 4] {
+    let mut color = [0u8; 4];
+    for (k, c) in color.iter_mut().enumerate() {
+        *c = (a[k] as f64 + (b[k] as f64 - a[k] as f64) * t).round() as u8;
+    }
+    color
+}
+
+impl Colormap {
+    // JS から渡されたしきい値と 0-1 の RGBA を並べた色からカラーマップを返す。
+    pub(crate) fn from_rgba(thresholds: &[f64], colors: &[f32]) -> Result<Self, String> {
+        if thresholds.is_empty() || colors.len() != thresholds.len() * 4 {
+            return Err(format!(
+                "Invalid colormap: {} thresholds and {} color components",
+                thresholds.len(),
+                colors.len()
+            ));
+        }
+
+        let colors = colors
+            .chunks_exact(4)
+            .map(|x| {
+                let mut color = [0u8; 4];
+                for (c, v) in color.iter_mut().zip(x) {
+                    *c = (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+                color
+            })
+            .collect();
+        Ok(Self {
+            thresholds: thresholds.to_vec(),
+            colors,
+        })
+    }
+
+    // 値の色を返す。欠測（NaN）は透明にする。
+    // 最初のしきい値未満は最初の色、最後のしきい値以上は最後の色とする。
+    pub(crate) fn color(&self, value: f64, mode: ColormapMode) -> [u8; 4] {
+        if value.is_nan() || self.colors.is_empty() {
+            return TRANSPARENT;
+        }
+
+        // 値以下で最大のしきい値の位置
+        let i = self.thresholds.partition_point(|x| *x <= value);
+        if i == 0 {
+            return self.colors[0];
+        }
+        let i = i - 1;
+
+        match mode {
+            ColormapMode::Stepped => self.colors[i],
+            ColormapMode::Continuous => match self.thresholds.get(i + 1) {
+                Some(next) => {
+                    let t = (value - self.thresholds[i]) / (next - self.thresholds[i]);
+                    mix(self.colors[i], self.colors[i + 1], t)
+                }
+                None => self.colors[i],
+            },
+        }
+    }
+
+    // 値を資料の並び順で RGBA の並びにする。
+    pub(crate) fn render(&self, values: &[f32], mode: ColormapMode) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|x| self.color(*x as f64, mode))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+
+    fn colormap() -> Colormap {
+        Colormap {
+            thresholds: vec![0.0, 10.0, 20.0],
+            colors: vec![RED, BLUE, GREEN],
+        }
+    }
+
+    #[test]
+    fn stepped() {
+        let colormap = colormap();
+        let color = |x| colormap.color(x, ColormapMode::Stepped);
+        assert_eq!(color(-5.0), RED);
+        assert_eq!(color(0.0), RED);
+        assert_eq!(color(9.9), RED);
+        assert_eq!(color(10.0), BLUE);
+        assert_eq!(color(25.0), GREEN);
+        assert_eq!(color(f64::NAN), TRANSPARENT);
+    }
+
+    #[test]
+    fn continuous() {
+        let colormap = colormap();
+        let color = |x| colormap.color(x, ColormapMode::Continuous);
+        assert_eq!(color(-5.0), RED);
+        assert_eq!(color(5.0), [128, 0, 128, 255]);
+        assert_eq!(color(10.0), BLUE);
+        assert_eq!(color(17.5), [0, 191, 64, 255]);
+        assert_eq!(color(25.0), GREEN);
+        assert_eq!(color(f64::NAN), TRANSPARENT);
+    }
+
+    // 値ごとに RGBA の4バイトを並べる。
+    #[test]
+    fn render() {
+        let rgba = colormap().render(&[f32::NAN, 0.0, 15.0], ColormapMode::Stepped);
+        assert_eq!(rgba, [TRANSPARENT, RED, BLUE].concat());
+    }
+
+    #[test]
+    fn from_rgba() {
+        let colormap =
+            Colormap::from_rgba(&[0.0, 1.0], &[1.0, 0.0, 0.0, 1.0, 0.0, 0.5, 2.0, -1.0]).unwrap();
+        assert_eq!(colormap.colors, [RED, [0, 128, 255, 0]]);
+        assert!(Colormap::from_rgba(&[], &[]).is_err());
+        assert!(Colormap::from_rgba(&[0.0, 1.0], &[1.0, 0.0, 0.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn mode() {
+        assert_eq!("stepped".parse(), Ok(ColormapMode::Stepped));
+        assert_eq!("continuous".parse(), Ok(ColormapMode::Continuous));
+        assert!("linear".parse::<ColormapMode>().is_err());
+    }
+}